@@ -5,12 +5,14 @@
 use pyo3::prelude::*;
 use pyo3::{PyResult, PyErr};
 use pyo3::types::PyDict;
+use chrono::{DateTime, Utc};
 
 // Internal imports
 use codelist_rs::codelist::CodeList;
 use codelist_rs::codelist_options::CodeListOptions;
 use codelist_rs::types::CodeListType;
-use codelist_rs::metadata::{Metadata, MetadataSource};
+use codelist_rs::metadata::{format_timestamp, Metadata, MetadataSource};
+use codelist_rs::version::CodeListVersion;
 
 
 /// Python wrapper for the CodeList struct
@@ -26,7 +28,13 @@ pub struct PyCodeList {
 #[pymethods]
 impl PyCodeList {
     #[new]
-    #[pyo3(signature = (codelist_type, source, authors=None, version=None, description=None, options=None))]
+    #[pyo3(signature = (
+        codelist_type, source, authors=None, version=None, description=None, options=None,
+        title=None, id=None, created=None, updated=None, licence=None, terminology=None,
+        terminology_release=None, references=None, intended_use=None, source_version=None,
+        metadata_id=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         codelist_type: &str,
         source: &str,
@@ -34,6 +42,17 @@ impl PyCodeList {
         version: Option<String>,
         description: Option<String>,
         options: Option<&PyDict>,
+        title: Option<String>,
+        id: Option<String>,
+        created: Option<&str>,
+        updated: Option<&str>,
+        licence: Option<String>,
+        terminology: Option<String>,
+        terminology_release: Option<String>,
+        references: Option<Vec<String>>,
+        intended_use: Option<String>,
+        source_version: Option<String>,
+        metadata_id: Option<String>,
     ) -> PyResult<Self> {
         // Convert string to CodeListType
         let codelist_type = match codelist_type.to_uppercase().as_str() {
@@ -45,22 +64,132 @@ impl PyCodeList {
             )),
         };
 
-        // Create metadata
-        let metadata = Metadata::new(
+        // Parse the version, which must be a date or a semantic version
+        let version = version
+            .map(|version| version.parse::<CodeListVersion>())
+            .transpose()
+            .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))?;
+
+        // Create metadata, setting each field that was given with its builder
+        let mut metadata = Metadata::new(
             MetadataSource::ManuallyCreated,
             authors,
             version,
             description,
         );
+        if let Some(metadata_id) = metadata_id {
+            metadata = metadata.with_id(metadata_id);
+        }
+        if let Some(source_version) = source_version {
+            metadata = metadata.with_source_version(source_version);
+        }
+        if let Some(title) = title {
+            metadata = metadata.with_title(title);
+        }
+        if let Some(created) = created {
+            metadata = metadata.with_created(parse_timestamp(created)?);
+        }
+        if let Some(updated) = updated {
+            metadata = metadata.with_updated(parse_timestamp(updated)?);
+        }
+        if let Some(licence) = licence {
+            metadata = metadata.with_licence(licence);
+        }
+        match (terminology, terminology_release) {
+            (Some(terminology), release) => metadata = metadata.with_terminology(terminology, release),
+            (None, Some(_)) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "terminology_release needs a terminology",
+            )),
+            (None, None) => {}
+        }
+        if let Some(references) = references {
+            metadata = metadata.with_references(references);
+        }
+        if let Some(intended_use) = intended_use {
+            metadata = metadata.with_intended_use(intended_use);
+        }
 
         // Parse CodeListOptions from PyDict
         let codelist_options = CodeListOptions::default();
 
         // Create codelist
-        let codelist = CodeList::new(codelist_type, metadata, Some(codelist_options));
+        let mut codelist = CodeList::new(codelist_type, metadata, Some(codelist_options));
+        codelist.id = id;
         Ok(PyCodeList { inner: codelist })
     }
 
+    /// The identifier of the codelist
+    #[getter]
+    fn id(&self) -> Option<String> {
+        self.inner.id.clone()
+    }
+
+    /// The stable identifier of the codelist from its metadata, such as a DOI
+    #[getter]
+    fn metadata_id(&self) -> Option<String> {
+        self.inner.metadata.id.clone()
+    }
+
+    /// The version of the codelist, a date or a semantic version
+    #[getter]
+    fn version(&self) -> Option<String> {
+        self.inner.metadata.version.as_ref().map(ToString::to_string)
+    }
+
+    /// The version given by the source the codelist was imported from, kept as given
+    #[getter]
+    fn source_version(&self) -> Option<String> {
+        self.inner.metadata.source_version.clone()
+    }
+
+    /// The human readable title of the codelist
+    #[getter]
+    fn title(&self) -> Option<String> {
+        self.inner.metadata.title.clone()
+    }
+
+    /// When the codelist was created, as an RFC 3339 timestamp
+    #[getter]
+    fn created(&self) -> Option<String> {
+        self.inner.metadata.created.as_ref().map(format_timestamp)
+    }
+
+    /// When the codelist was last updated, as an RFC 3339 timestamp
+    #[getter]
+    fn updated(&self) -> Option<String> {
+        self.inner.metadata.updated.as_ref().map(format_timestamp)
+    }
+
+    /// The licence the codelist is published under
+    #[getter]
+    fn licence(&self) -> Option<String> {
+        self.inner.metadata.licence.clone()
+    }
+
+    /// The terminology edition the codelist was built against
+    #[getter]
+    fn terminology(&self) -> Option<String> {
+        self.inner.metadata.terminology.clone()
+    }
+
+    /// The release of the terminology the codelist was built against
+    #[getter]
+    fn terminology_release(&self) -> Option<String> {
+        self.inner.metadata.terminology_release.clone()
+    }
+
+    /// References for the codelist, such as DOIs or URLs
+    #[getter]
+    fn references(&self) -> Option<Vec<String>> {
+        self.inner.metadata.references.clone()
+    }
+
+    /// What the codelist is intended to be used for
+    #[getter]
+    fn intended_use(&self) -> Option<String> {
+        self.inner.metadata.intended_use.clone()
+    }
+
     /// Add an entry to the codelist
     #[pyo3(text_signature = "($self, code, term, comment=None)")]
     fn add_entry(&mut self, code: String, term: String, comment: Option<String>) -> PyResult<()> {
@@ -76,4 +205,11 @@ impl PyCodeList {
             .map(|entry| (entry.code.clone(), entry.term.clone()))
             .collect()
     }
-}
\ No newline at end of file
+}
+
+/// Parse an RFC 3339 timestamp given from python, such as "2024-12-19T09:30:00Z"
+fn parse_timestamp(value: &str) -> PyResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|_| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid RFC 3339 timestamp: {}", value)))
+}
@@ -34,5 +34,68 @@ class TestCodeList(unittest.TestCase):
         entries = codelist.entries()
         self.assertEqual(len(entries), 1)
 
+
+    def test_rich_metadata(self):
+        codelist = CodeList(
+            codelist_type="SNOMED",
+            source="test",
+            title="Type 2 diabetes",
+            id="type-2-diabetes",
+            metadata_id="phenotype-0042",
+            created="2024-12-19T09:30:00Z",
+            licence="CC-BY-4.0",
+            terminology="SNOMED CT UK Clinical Edition",
+            terminology_release="39.0.0",
+            references=["https://doi.org/10.1000/182"],
+            intended_use="Identifying type 2 diabetes in primary care records",
+        )
+        self.assertEqual(codelist.title, "Type 2 diabetes")
+        self.assertEqual(codelist.id, "type-2-diabetes")
+        self.assertEqual(codelist.metadata_id, "phenotype-0042")
+        self.assertEqual(codelist.created, "2024-12-19T09:30:00Z")
+        self.assertIsNone(codelist.updated)
+        self.assertEqual(codelist.licence, "CC-BY-4.0")
+        self.assertEqual(codelist.terminology_release, "39.0.0")
+        self.assertEqual(codelist.references, ["https://doi.org/10.1000/182"])
+
+    def test_ids_are_separate(self):
+        codelist = CodeList(codelist_type="ICD10", source="test", id="asthma")
+        self.assertEqual(codelist.id, "asthma")
+        self.assertIsNone(codelist.metadata_id)
+
+        codelist = CodeList(codelist_type="ICD10", source="test", metadata_id="asthma-v1")
+        self.assertIsNone(codelist.id)
+        self.assertEqual(codelist.metadata_id, "asthma-v1")
+
+    def test_terminology_release_without_terminology(self):
+        with self.assertRaises(ValueError) as e:
+            CodeList(codelist_type="SNOMED", source="test", terminology_release="39.0.0")
+        self.assertEqual(str(e.exception), "terminology_release needs a terminology")
+
+
+    def test_version(self):
+        codelist = CodeList(codelist_type="ICD10", source="test", version="2024-12-19")
+        self.assertEqual(codelist.version, "2024-12-19")
+        self.assertIsNone(codelist.source_version)
+
+        with self.assertRaises(ValueError) as e:
+            CodeList(codelist_type="ICD10", source="test", version="draft")
+        self.assertTrue(str(e.exception).startswith("Invalid codelist version: draft."))
+
+    def test_source_version(self):
+        codelist = CodeList(codelist_type="ICD10", source="test", source_version="2")
+        self.assertIsNone(codelist.version)
+        self.assertEqual(codelist.source_version, "2")
+
+
+    def test_invalid_timestamp(self):
+        with self.assertRaises(ValueError) as e:
+            CodeList(
+                codelist_type="ICD10",
+                source="test",
+                created="19/12/2024",
+            )
+        self.assertEqual(str(e.exception), "Invalid RFC 3339 timestamp: 19/12/2024")
+
 if __name__ == '__main__':
     unittest.main()
\ No newline at end of file
@@ -11,8 +11,9 @@ fn main() -> Result<(), CodeListError> {
     let metadata = Metadata {
         source: MetadataSource::ManuallyCreated,
         authors: Some(vec!["Caroline Morton".to_string()]),
-        version: Some("2024-12-19".to_string()),
+        version: Some("2024-12-19".parse().unwrap()),
         description: Some("A test codelist".to_string()),
+        ..Metadata::default()
     };
 
     // Create a new codelist
@@ -0,0 +1,414 @@
+//! This file contains codelist bundles: zip archives of codelist files with a manifest, for
+//! sharing the codelists of a study in one file
+//!
+//! The manifest, `manifest.json`, lists the name, id, type, version, entry count, path and
+//! fingerprint of each codelist. Fingerprints are SHA-256 hashes of the codelist files and are
+//! checked when a bundle is read.
+
+// External imports
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_format::CodeListFormat;
+use crate::compression::MAX_DECOMPRESSED_SIZE;
+use crate::errors::CodeListError;
+use crate::types::CodeListType;
+use crate::version::CodeListVersion;
+
+/// The version of the bundle manifest written by this library
+pub const BUNDLE_MANIFEST_VERSION: u64 = 1;
+
+/// The path of the manifest in a bundle
+pub const BUNDLE_MANIFEST_PATH: &str = "manifest.json";
+
+/// The folder of the codelist files in a bundle
+const BUNDLE_CODELIST_FOLDER: &str = "codelists";
+
+/// Struct to represent a codelist listed in a bundle manifest
+///
+/// # Fields
+/// * `path` - The path of the codelist file in the bundle
+/// * `format` - The format of the codelist file
+/// * `name` - The name of the codelist
+/// * `id` - The id of the codelist
+/// * `codelist_type` - The type of the codelist
+/// * `version` - The version of the codelist, from its metadata
+/// * `entry_count` - The number of entries in the codelist
+/// * `fingerprint` - The SHA-256 hash of the codelist file, as "sha256:" and lowercase hex
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub path: String,
+    pub format: String,
+    pub name: Option<String>,
+    pub id: Option<String>,
+    pub codelist_type: CodeListType,
+    pub version: Option<CodeListVersion>,
+    pub entry_count: usize,
+    pub fingerprint: String,
+}
+
+/// Struct to represent the manifest of a bundle
+///
+/// # Fields
+/// * `manifest_version` - The version of the manifest format
+/// * `created_by` - The library and version that wrote the bundle
+/// * `codelists` - The codelists in the bundle, in the order they were written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub manifest_version: u64,
+    pub created_by: String,
+    pub codelists: Vec<BundleEntry>,
+}
+
+/// Get the fingerprint of file contents
+///
+/// # Arguments
+/// * `bytes` - The contents of the file
+///
+/// # Returns
+/// * `String` - The SHA-256 hash, as "sha256:" and lowercase hex
+pub fn fingerprint(bytes: &[u8]) -> String {
+    let hash: String = Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256:{}", hash)
+}
+
+/// Read a file from a zip archive
+///
+/// # Arguments
+/// * `archive` - The zip archive
+/// * `path` - The path of the file in the archive
+///
+/// # Returns
+/// * `Result<Vec<u8>, CodeListError>` - The contents of the file or an error
+///
+/// # Errors
+/// * `CodeListError::ZipError` - If the file is not in the archive
+/// * `CodeListError::IOError` - If an error occurs when reading the file
+/// * `CodeListError::InvalidInput` - If the file decompresses to more than `MAX_DECOMPRESSED_SIZE` bytes
+fn read_archive_file<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str) -> Result<Vec<u8>, CodeListError> {
+    read_archive_file_with_limit(archive, path, MAX_DECOMPRESSED_SIZE)
+}
+
+/// Read a file from a zip archive, up to a size limit
+///
+/// # Arguments
+/// * `archive` - The zip archive
+/// * `path` - The path of the file in the archive
+/// * `limit` - The largest size, in bytes, to decompress the file to
+///
+/// # Returns
+/// * `Result<Vec<u8>, CodeListError>` - The contents of the file or an error
+///
+/// # Errors
+/// * `CodeListError::ZipError` - If the file is not in the archive
+/// * `CodeListError::IOError` - If an error occurs when reading the file
+/// * `CodeListError::InvalidInput` - If the file decompresses to more than `limit` bytes
+fn read_archive_file_with_limit<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str, limit: u64) -> Result<Vec<u8>, CodeListError> {
+    let mut bytes = Vec::new();
+    // Read one byte past the limit, so that a file of exactly the limit is accepted
+    archive.by_name(path)?.take(limit + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        return Err(CodeListError::invalid_input(format!("The bundle file {} decompresses to more than {} bytes", path, limit)));
+    }
+    Ok(bytes)
+}
+
+/// Read and check the manifest of a zip archive
+///
+/// # Arguments
+/// * `archive` - The zip archive
+///
+/// # Returns
+/// * `Result<BundleManifest, CodeListError>` - The manifest or an error
+fn read_manifest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<BundleManifest, CodeListError> {
+    let manifest: BundleManifest = serde_json::from_slice(&read_archive_file(archive, BUNDLE_MANIFEST_PATH)?)?;
+    if manifest.manifest_version > BUNDLE_MANIFEST_VERSION {
+        return Err(CodeListError::invalid_input(format!(
+            "Unsupported bundle manifest version: {}. Supported version: {}",
+            manifest.manifest_version, BUNDLE_MANIFEST_VERSION
+        )));
+    }
+    Ok(manifest)
+}
+
+impl CodeListFactory {
+    /// Save codelists to a bundle file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the bundle file
+    /// * `codelists` - The codelists
+    /// * `format` - The format to save each codelist in
+    ///
+    /// # Returns
+    /// * `Result<BundleManifest, CodeListError>` - The manifest of the bundle or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when creating the file
+    /// * See `save_codelists_to_bundle_writer` for other errors
+    pub fn save_codelists_to_bundle(&self, file_path: &str, codelists: Vec<CodeList>, format: CodeListFormat) -> Result<BundleManifest, CodeListError> {
+        let file = File::create(file_path)?;
+        self.save_codelists_to_bundle_writer(file, codelists, format)
+    }
+
+    /// Write codelists as a bundle to any seekable writer
+    ///
+    /// Each codelist is saved in the `codelists` folder of the archive under a name from
+    /// `codelist_file_names`, and listed in `manifest.json` with its fingerprint.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the bundle to
+    /// * `codelists` - The codelists
+    /// * `format` - The format to save each codelist in
+    ///
+    /// # Returns
+    /// * `Result<BundleManifest, CodeListError>` - The manifest of the bundle or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the file name template is invalid
+    /// * `CodeListError::ZipError` - If an error occurs when writing the archive
+    /// * See the saver of each format for other errors
+    pub fn save_codelists_to_bundle_writer<W: Write + Seek>(&self, writer: W, codelists: Vec<CodeList>, format: CodeListFormat) -> Result<BundleManifest, CodeListError> {
+        let file_names = self.codelist_file_names(&codelists, format.extension())?;
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+        let mut manifest = BundleManifest {
+            manifest_version: BUNDLE_MANIFEST_VERSION,
+            created_by: format!("codelist-rs {}", env!("CARGO_PKG_VERSION")),
+            codelists: Vec::with_capacity(codelists.len()),
+        };
+
+        for (codelist, file_name) in codelists.iter().zip(file_names) {
+            let mut bytes = Vec::new();
+            codelist.save_to_writer(&mut bytes, format)?;
+            let path = format!("{}/{}", BUNDLE_CODELIST_FOLDER, file_name);
+            zip.start_file(path.as_str(), options)?;
+            zip.write_all(&bytes)?;
+            manifest.codelists.push(BundleEntry {
+                path,
+                format: format.to_string(),
+                name: codelist.name.clone(),
+                id: codelist.id.clone(),
+                codelist_type: codelist.codelist_type.clone(),
+                version: codelist.metadata.version.clone(),
+                entry_count: codelist.entries.len(),
+                fingerprint: fingerprint(&bytes),
+            });
+        }
+
+        zip.start_file(BUNDLE_MANIFEST_PATH, options)?;
+        serde_json::to_writer_pretty(&mut zip, &manifest)?;
+        zip.finish()?;
+        Ok(manifest)
+    }
+
+    /// Read the manifest of a bundle file, without loading its codelists
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the bundle file
+    ///
+    /// # Returns
+    /// * `Result<BundleManifest, CodeListError>` - The manifest or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error opening the file
+    /// * `CodeListError::ZipError` - If the file is not a zip archive or has no manifest
+    /// * `CodeListError::JSONError` - If the manifest is not valid
+    /// * `CodeListError::InvalidInput` - If the manifest was written by a newer version of the library,
+    ///   or decompresses to more than `MAX_DECOMPRESSED_SIZE` bytes
+    pub fn load_bundle_manifest(&self, file_path: &str) -> Result<BundleManifest, CodeListError> {
+        read_manifest(&mut ZipArchive::new(File::open(file_path)?)?)
+    }
+
+    /// Load the codelists of a bundle file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the bundle file
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists, in manifest order, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error opening the file
+    /// * See `load_codelists_from_bundle_reader` for other errors
+    pub fn load_codelists_from_bundle(&self, file_path: &str) -> Result<Vec<CodeList>, CodeListError> {
+        self.load_codelists_from_bundle_reader(File::open(file_path)?)
+    }
+
+    /// Load the codelists of a bundle from any seekable reader
+    ///
+    /// Every codelist file is checked against its fingerprint before it is loaded. Codelists are
+    /// loaded with the factory options and metadata, but with the type from the manifest, and
+    /// take their name, id and version from the manifest when the file does not store them.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the bundle from
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists, in manifest order, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::ZipError` - If the data is not a zip archive, or a listed file is missing
+    /// * `CodeListError::JSONError` - If the manifest is not valid
+    /// * `CodeListError::InvalidInput` - If the manifest was written by a newer version of the
+    ///   library or lists an unsupported format, or a file decompresses to more than
+    ///   `MAX_DECOMPRESSED_SIZE` bytes
+    /// * `CodeListError::FingerprintMismatch` - If a codelist file does not match its fingerprint
+    /// * See the loader of each format for errors in the codelist files
+    pub fn load_codelists_from_bundle_reader<R: Read + Seek>(&self, reader: R) -> Result<Vec<CodeList>, CodeListError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let manifest = read_manifest(&mut archive)?;
+
+        let mut codelists = Vec::with_capacity(manifest.codelists.len());
+        for entry in manifest.codelists {
+            let bytes = read_archive_file(&mut archive, &entry.path)?;
+            let actual = fingerprint(&bytes);
+            if actual != entry.fingerprint {
+                return Err(CodeListError::fingerprint_mismatch(entry.path, entry.fingerprint, actual));
+            }
+
+            let format: CodeListFormat = entry.format.parse()?;
+            let mut factory = CodeListFactory::new(self.codelist_options.clone(), self.metadata.clone(), entry.codelist_type);
+            factory.processing_steps = self.processing_steps.clone();
+            let mut codelist = factory.load_codelist_from_reader(bytes.as_slice(), Some(format))?;
+            if codelist.name.is_none() {
+                codelist.name = entry.name;
+            }
+            if codelist.id.is_none() {
+                codelist.id = entry.id;
+            }
+            if codelist.metadata.version.is_none() {
+                codelist.metadata.version = entry.version;
+            }
+            codelists.push(codelist);
+        }
+        Ok(codelists)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    fn create_test_codelists() -> Result<Vec<CodeList>, CodeListError> {
+        let mut diabetes = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        diabetes.set_name("Diabetes");
+        diabetes.add_entry("E10".to_string(), "Type 1 diabetes mellitus".to_string(), None)?;
+        diabetes.add_entry("E11".to_string(), "Type 2 diabetes mellitus".to_string(), None)?;
+        let mut asthma = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        asthma.set_name("Asthma");
+        asthma.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+        Ok(vec![diabetes, asthma])
+    }
+
+    #[test]
+    fn test_bundle_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("study.zip");
+        let file_path = file_path.to_str().unwrap();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let original = create_test_codelists()?;
+
+        let manifest = factory.save_codelists_to_bundle(file_path, original.clone(), CodeListFormat::Csv)?;
+        assert_eq!(manifest, factory.load_bundle_manifest(file_path)?);
+        assert_eq!(manifest.manifest_version, BUNDLE_MANIFEST_VERSION);
+        let paths: Vec<&str> = manifest.codelists.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec!["codelists/diabetes.csv", "codelists/asthma.csv"]);
+        assert_eq!(manifest.codelists[1].codelist_type, CodeListType::SNOMED);
+        assert_eq!(manifest.codelists[1].entry_count, 1);
+        assert!(manifest.codelists[0].fingerprint.starts_with("sha256:"));
+        assert_eq!(manifest.codelists[0].fingerprint.len(), "sha256:".len() + 64);
+
+        let loaded = factory.load_codelists_from_bundle(file_path)?;
+        assert_eq!(loaded.len(), 2);
+        for (loaded, original) in loaded.iter().zip(&original) {
+            assert_eq!(loaded.entries, original.entries);
+            assert_eq!(loaded.codelist_type, original.codelist_type);
+            assert_eq!(loaded.name, original.name);
+            assert_eq!(loaded.id, original.id);
+            assert_eq!(loaded.metadata.version, Some("2024-12-19".parse()?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_of_saved_json() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let mut original = create_test_codelists()?;
+        original[0].add_log("Reviewed".to_string());
+
+        let mut buffer = Cursor::new(Vec::new());
+        factory.save_codelists_to_bundle_writer(&mut buffer, original.clone(), CodeListFormat::Json)?;
+        buffer.set_position(0);
+        let loaded = factory.load_codelists_from_bundle_reader(buffer)?;
+        assert_eq!(loaded[0].logs, vec!["Reviewed"]);
+        assert_eq!(loaded[0].metadata, original[0].metadata);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_fingerprint_mismatch() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelists = create_test_codelists()?;
+        let mut buffer = Cursor::new(Vec::new());
+        let mut manifest = factory.save_codelists_to_bundle_writer(&mut buffer, codelists, CodeListFormat::Csv)?;
+
+        // Rewrite the bundle with a tampered codelist file but the original manifest
+        buffer.set_position(0);
+        let mut archive = ZipArchive::new(buffer)?;
+        let mut tampered = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut tampered);
+        zip.start_file("codelists/diabetes.csv", SimpleFileOptions::default())?;
+        zip.write_all(b"code,term\nE10,Type 1 diabetes mellitus\n")?;
+        zip.start_file("codelists/asthma.csv", SimpleFileOptions::default())?;
+        zip.write_all(&read_archive_file(&mut archive, "codelists/asthma.csv")?)?;
+        zip.start_file(BUNDLE_MANIFEST_PATH, SimpleFileOptions::default())?;
+        zip.write_all(&read_archive_file(&mut archive, BUNDLE_MANIFEST_PATH)?)?;
+        zip.finish()?;
+
+        tampered.set_position(0);
+        let error = factory.load_codelists_from_bundle_reader(tampered).unwrap_err();
+        let expected = manifest.codelists.remove(0).fingerprint;
+        assert!(matches!(error, CodeListError::FingerprintMismatch { path, expected: found_expected, .. } if path == "codelists/diabetes.csv" && found_expected == expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_archive_file_size_limit() -> Result<(), CodeListError> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("codelists/large.csv", SimpleFileOptions::default())?;
+        zip.write_all(&[b'a'; 1000])?;
+        zip.finish()?;
+
+        buffer.set_position(0);
+        let mut archive = ZipArchive::new(buffer)?;
+        assert_eq!(read_archive_file_with_limit(&mut archive, "codelists/large.csv", 1000)?.len(), 1000);
+        let error = read_archive_file_with_limit(&mut archive, "codelists/large.csv", 999).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "The bundle file codelists/large.csv decompresses to more than 999 bytes"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundle_without_manifest() -> Result<(), CodeListError> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("codelists/diabetes.csv", SimpleFileOptions::default())?;
+        zip.write_all(b"code,term\nE10,Type 1 diabetes mellitus\n")?;
+        zip.finish()?;
+
+        buffer.set_position(0);
+        let error = create_test_codelist_factory(CodeListType::ICD10).load_codelists_from_bundle_reader(buffer).unwrap_err();
+        assert!(matches!(error, CodeListError::ZipError(_)));
+        Ok(())
+    }
+}
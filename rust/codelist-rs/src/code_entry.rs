@@ -40,7 +40,6 @@ impl CodeEntry {
     /// # Errors
     /// * `CodeListError::EmptyCode` - If the code is an empty string
     /// * `CodeListError::EmptyTerm` - If the term is an empty string
-    
     pub fn new<T: Into<String>>(code: T, term: String, comment: Option<String>) -> Result<CodeEntry, CodeListError> {
         let code = code.into();
 
@@ -3,19 +3,35 @@
 // External imports
 use std::collections::HashSet;
 use std::io::Write;
+use std::path::Path;
 use serde::{Serialize, Deserialize};
 use csv::Writer;
+use rust_xlsxwriter::{Format, Workbook};
 
 // Internal imports
 use crate::types::CodeListType;
 use crate::code_entry::CodeEntry;
-use crate::metadata::Metadata;
+use crate::metadata::{format_timestamp, Metadata};
 use crate::errors::CodeListError;
 use crate::codelist_options::CodeListOptions;
+use crate::codelist_format::CodeListFormat;
+use crate::schema::migrate_saved_json;
+use crate::compression::OutputFile;
+use crate::metadata_sidecar::{write_metadata_sidecar, MetadataSidecarFormat};
+use crate::version::{CodeListVersion, VersionBump};
+
+/// The schema version written to codelist JSON files by `save_to_json`
+///
+/// The schema is published as JSON Schema in `schema::CODELIST_JSON_SCHEMA`. Changing the saved
+/// form of a codelist needs a new version and a migration in the `schema` module.
+pub const CODELIST_SCHEMA_VERSION: u64 = 5;
 
 /// Struct to represent a codelist
 ///
 /// # Fields
+/// * `name` - The name of the codelist, such as the stem of the file it was loaded from
+/// * `id` - An identifier for the codelist, made from the name unless set directly (see also
+///   `Metadata::id` for an identifier given by the publisher)
 /// * `entries` - The set of code entries
 /// * `codelist_type` - The type of codelist
 /// * `metadata` - Metadata about the codelist
@@ -23,6 +39,10 @@ use crate::codelist_options::CodeListOptions;
 /// * `codelist_options` - Options for the codelist
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CodeList {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
     pub entries: HashSet<CodeEntry>,
     pub codelist_type: CodeListType,
     pub metadata: Metadata,
@@ -30,6 +50,54 @@ pub struct CodeList {
     pub codelist_options: CodeListOptions,
 }
 
+/// Make a stable identifier from a codelist name
+///
+/// The name is lowercased and each run of characters that are not letters or digits becomes a
+/// single hyphen, so "Asthma Diagnosis (v2)" becomes "asthma-diagnosis-v2".
+///
+/// # Arguments
+/// * `name` - The name of the codelist
+///
+/// # Returns
+/// * `String` - The identifier
+pub fn codelist_id_from_name(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            id.push(c);
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+/// Struct used to write a codelist to JSON in the current schema version
+///
+/// The fields are listed here rather than taken from `CodeList`, so that changes to the struct
+/// cannot change the saved form without a new schema version.
+///
+/// # Fields
+/// * `schema_version` - The schema version of the file
+/// * `name` - The name of the codelist
+/// * `id` - The identifier of the codelist
+/// * `entries` - The entries, sorted by code so that files diff cleanly
+/// * `codelist_type` - The type of codelist
+/// * `metadata` - Metadata about the codelist
+/// * `logs` - Logs of anything that happened to the codelist
+/// * `codelist_options` - Options for the codelist
+#[derive(Serialize)]
+struct SavedCodeList<'a> {
+    schema_version: u64,
+    name: &'a Option<String>,
+    id: &'a Option<String>,
+    entries: Vec<&'a CodeEntry>,
+    codelist_type: &'a CodeListType,
+    metadata: &'a Metadata,
+    logs: &'a Vec<String>,
+    codelist_options: &'a CodeListOptions,
+}
+
 
 impl CodeList {
     /// Create a new CodeList
@@ -43,6 +111,8 @@ impl CodeList {
     /// * `CodeList` - The new CodeList
     pub fn new(codelist_type: CodeListType, metadata: Metadata, options: Option<CodeListOptions>) -> Self {
         CodeList {
+            name: None,
+            id: None,
             entries: HashSet::new(),
             codelist_type,
             metadata,
@@ -59,6 +129,81 @@ impl CodeList {
         &self.codelist_type
     }
 
+    /// Set the name of the codelist, along with an identifier made from it
+    ///
+    /// # Arguments
+    /// * `name` - The name of the codelist
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.to_string());
+        self.id = Some(codelist_id_from_name(name));
+    }
+
+    /// Fill in a missing name and identifier from the stem of the file the codelist came from
+    ///
+    /// A name or identifier that is already set, for example one read from the file itself, is
+    /// kept.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    pub fn set_name_from_file_path(&mut self, file_path: &str) {
+        if self.name.is_none() {
+            self.name = Path::new(file_path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|stem| !stem.trim().is_empty())
+                .map(|stem| stem.trim().to_string());
+        }
+        if self.id.is_none() {
+            self.id = self.name.as_deref().map(codelist_id_from_name).filter(|id| !id.is_empty());
+        }
+    }
+
+    /// Render the file name of the codelist from a template
+    ///
+    /// The placeholders are `{id}`, `{name}`, `{index}`, `{version}` and `{type}`. A missing id or
+    /// name is replaced with the index and a missing version with "unversioned". Characters that
+    /// are not allowed in file names are replaced with underscores.
+    ///
+    /// # Arguments
+    /// * `template` - The file name template, without the extension
+    /// * `index` - The one-based position of the codelist in its collection
+    ///
+    /// # Returns
+    /// * `Result<String, CodeListError>` - The file name, without the extension, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the template has an unknown or unclosed placeholder
+    pub fn render_file_name(&self, template: &str, index: usize) -> Result<String, CodeListError> {
+        let mut rendered = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let end = rest[start..].find('}')
+                .ok_or_else(|| CodeListError::invalid_input(format!("Unclosed placeholder in file name template: {}", template)))?;
+            let value = match &rest[start + 1..start + end] {
+                "id" => self.id.clone().unwrap_or_else(|| index.to_string()),
+                "name" => self.name.clone().unwrap_or_else(|| index.to_string()),
+                "index" => index.to_string(),
+                "version" => self.metadata.version.as_ref().map_or_else(|| "unversioned".to_string(), ToString::to_string),
+                "type" => self.codelist_type.to_string(),
+                other => return Err(CodeListError::invalid_input(format!("Unknown placeholder in file name template: {{{}}}", other))),
+            };
+            rendered.push_str(&value);
+            rest = &rest[start + end + 1..];
+        }
+        rendered.push_str(rest);
+
+        let file_name: String = rendered.chars()
+            .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+            .collect();
+        let file_name = file_name.trim().trim_matches('.').to_string();
+        if file_name.is_empty() {
+            Ok(index.to_string())
+        } else {
+            Ok(file_name)
+        }
+    }
+
     /// Add an entry to the codelist
     ///
     /// # Arguments
@@ -95,6 +240,16 @@ impl CodeList {
         &self.entries
     }
 
+    /// Get the entries of the codelist sorted by code, then term
+    ///
+    /// # Returns
+    /// * `Vec<&CodeEntry>` - The sorted entries of the codelist
+    pub fn entries_sorted_by_code(&self) -> Vec<&CodeEntry> {
+        let mut entries: Vec<&CodeEntry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.code.cmp(&b.code).then_with(|| a.term.cmp(&b.term)));
+        entries
+    }
+
     /// Get the code and term of the codelist
     ///
     /// # Returns
@@ -119,35 +274,244 @@ impl CodeList {
 
     /// Save the codelist entries to a CSV file
     ///
+    /// By default only the entries are written, so the metadata is lost when the file is loaded
+    /// again. With the `write_metadata_sidecar` option on (it is off by default), the metadata is
+    /// also written to a sidecar file, as in `save_to_csv_with_metadata`.
+    ///
     /// # Arguments
     /// * `file_path` - The path to the file to save the codelist entries to
     ///
     /// # Errors
     /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::JSONError` - If an error occurs when writing the metadata sidecar file
     pub fn save_to_csv(&self, file_path: &str) -> std::result::Result<(), CodeListError> {
-        let mut wtr = Writer::from_path(file_path)?;
+        if self.codelist_options.write_metadata_sidecar {
+            return self.save_to_csv_with_metadata(file_path);
+        }
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_csv_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Save the codelist entries to a CSV file and the metadata to a json sidecar file next to it
+    ///
+    /// The sidecar file is named after the csv, such as `asthma.csv.meta.json` for `asthma.csv`,
+    /// and is read back when the csv is loaded.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist entries to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to either file
+    /// * `CodeListError::JSONError` - If an error occurs when writing the metadata sidecar file
+    pub fn save_to_csv_with_metadata(&self, file_path: &str) -> std::result::Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_csv_writer(&mut file)?;
+        file.finish()?;
+        write_metadata_sidecar(file_path, &self.metadata, MetadataSidecarFormat::Json)
+    }
+
+    /// Write the codelist entries as csv to any writer, such as stdout or an in-memory buffer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the csv data to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    pub fn save_to_csv_writer<W: Write>(&self, writer: W) -> std::result::Result<(), CodeListError> {
+        let mut wtr = Writer::from_writer(writer);
         // use column names from options
-        wtr.write_record(&[&self.codelist_options.code_field_name, &self.codelist_options.term_field_name])?;
+        wtr.write_record([&self.codelist_options.code_field_name, &self.codelist_options.term_field_name])?;
         for entry in self.entries.iter() {
-            wtr.write_record(&[&entry.code, &entry.term])?;
+            wtr.write_record([&entry.code, &entry.term])?;
         }
         wtr.flush()?;
         Ok(())
     }
 
+    /// Save the codelist to an xlsx workbook
+    ///
+    /// The entries are written to an "Entries" sheet, sorted by code, using the column names from
+    /// the codelist options. The metadata and logs are written to a "Metadata" sheet.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when creating the file
+    /// * `CodeListError::XLSXWriteError` - If an error occurs when writing the workbook
+    pub fn save_to_xlsx(&self, file_path: &str) -> std::result::Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_xlsx_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the codelist as an xlsx workbook to any writer
+    ///
+    /// See `save_to_xlsx` for the layout of the workbook.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the workbook to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::XLSXWriteError` - If an error occurs when building the workbook
+    pub fn save_to_xlsx_writer<W: Write>(&self, mut writer: W) -> std::result::Result<(), CodeListError> {
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+
+        let entries_sheet = workbook.add_worksheet();
+        entries_sheet.set_name("Entries")?;
+        entries_sheet.write_string_with_format(0, 0, &self.codelist_options.code_field_name, &bold)?;
+        entries_sheet.write_string_with_format(0, 1, &self.codelist_options.term_field_name, &bold)?;
+        entries_sheet.write_string_with_format(0, 2, &self.codelist_options.comment_column_name, &bold)?;
+
+        for (row, entry) in (1u32..).zip(self.entries_sorted_by_code()) {
+            entries_sheet.write_string(row, 0, &entry.code)?;
+            entries_sheet.write_string(row, 1, &entry.term)?;
+            if let Some(comment) = &entry.comment {
+                entries_sheet.write_string(row, 2, comment)?;
+            }
+        }
+
+        let metadata_sheet = workbook.add_worksheet();
+        metadata_sheet.set_name("Metadata")?;
+        let metadata_rows = [
+            ("codelist_type", self.codelist_type.to_string()),
+            ("source", self.metadata.source.to_string()),
+            ("authors", self.metadata.authors.as_ref().map(|authors| authors.join("; ")).unwrap_or_default()),
+            ("version", self.metadata.version.as_ref().map(ToString::to_string).unwrap_or_default()),
+            ("source_version", self.metadata.source_version.clone().unwrap_or_default()),
+            ("description", self.metadata.description.clone().unwrap_or_default()),
+            ("id", self.metadata.id.clone().unwrap_or_default()),
+            ("title", self.metadata.title.clone().unwrap_or_default()),
+            ("created", self.metadata.created.as_ref().map(format_timestamp).unwrap_or_default()),
+            ("updated", self.metadata.updated.as_ref().map(format_timestamp).unwrap_or_default()),
+            ("licence", self.metadata.licence.clone().unwrap_or_default()),
+            ("terminology", self.metadata.terminology.clone().unwrap_or_default()),
+            ("terminology_release", self.metadata.terminology_release.clone().unwrap_or_default()),
+            ("references", self.metadata.references.as_ref().map(|references| references.join("; ")).unwrap_or_default()),
+            ("intended_use", self.metadata.intended_use.clone().unwrap_or_default()),
+        ];
+        let logs_row = metadata_rows.len() as u32 + 1;
+        for (row, (field, value)) in (0u32..).zip(metadata_rows) {
+            metadata_sheet.write_string_with_format(row, 0, field, &bold)?;
+            metadata_sheet.write_string(row, 1, value)?;
+        }
+
+        metadata_sheet.write_string_with_format(logs_row, 0, "logs", &bold)?;
+        for (row, log) in (logs_row..).zip(&self.logs) {
+            metadata_sheet.write_string(row, 1, log)?;
+        }
+
+        writer.write_all(&workbook.save_to_buffer()?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
     /// Save the codelist struct to a JSON file
     ///
+    /// The file contains the full codelist (entries, type, metadata, logs and options) along
+    /// with a `schema_version` field, and can be loaded back with `CodeList::from_saved_json`.
+    ///
     /// # Arguments
     /// * `file_path` - The path to the file to save the codelist struct to
     ///
     /// # Errors
     /// * `CodeListError::IOError` - If an error occurs when writing to the file
     pub fn save_to_json(&self, file_path: &str) -> std::result::Result<(), CodeListError> {
-        let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(file_path, json)?;
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_json_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the codelist struct as JSON to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the JSON to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the codelist
+    pub fn save_to_json_writer<W: Write>(&self, mut writer: W) -> std::result::Result<(), CodeListError> {
+        let saved = SavedCodeList {
+            schema_version: CODELIST_SCHEMA_VERSION,
+            name: &self.name,
+            id: &self.id,
+            entries: self.entries_sorted_by_code(),
+            codelist_type: &self.codelist_type,
+            metadata: &self.metadata,
+            logs: &self.logs,
+            codelist_options: &self.codelist_options,
+        };
+        serde_json::to_writer_pretty(&mut writer, &saved)?;
+        writer.flush()?;
         Ok(())
     }
 
+    /// Write the codelist to any writer in the given format
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the codelist to
+    /// * `format` - The format to write
+    ///
+    /// # Errors
+    /// * See the saver of each format
+    pub fn save_to_writer<W: Write + Send>(&self, writer: W, format: CodeListFormat) -> std::result::Result<(), CodeListError> {
+        match format {
+            CodeListFormat::Csv => self.save_to_csv_writer(writer),
+            CodeListFormat::Json => self.save_to_json_writer(writer),
+            CodeListFormat::Xlsx => self.save_to_xlsx_writer(writer),
+            #[cfg(feature = "arrow")]
+            CodeListFormat::ArrowIpc => self.save_to_arrow_ipc_writer(writer),
+            #[cfg(feature = "parquet")]
+            CodeListFormat::Parquet => self.save_to_parquet_writer(writer),
+        }
+    }
+
+    /// Save the codelist to a file in the given format, regardless of its extension
+    ///
+    /// Like the other savers, the file is compressed with gzip or zstd when its path ends in
+    /// `.gz` or `.zst`, such as `asthma.csv.gz`. As in `save_to_csv`, csv files get a metadata
+    /// sidecar file when the `write_metadata_sidecar` option is on.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist to
+    /// * `format` - The format to write
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when creating the file
+    /// * See the saver of each format for other errors
+    pub fn save_to_file_with_format(&self, file_path: &str, format: CodeListFormat) -> std::result::Result<(), CodeListError> {
+        if format == CodeListFormat::Csv {
+            return self.save_to_csv(file_path);
+        }
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_writer(&mut file, format)?;
+        file.finish()
+    }
+
+    /// Create a codelist from JSON written by `save_to_json`
+    ///
+    /// # Arguments
+    /// * `json_data` - The parsed JSON of a saved codelist
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the JSON is not an object or has no valid `schema_version` field
+    /// * `CodeListError::UnsupportedSchemaVersion` - If the schema version is not supported by this library
+    /// * `CodeListError::JSONError` - If the JSON does not match the saved codelist format
+    pub fn from_saved_json(json_data: serde_json::Value) -> Result<CodeList, CodeListError> {
+        let (json_data, version) = migrate_saved_json(json_data)?;
+        let mut codelist: CodeList = serde_json::from_value(json_data)?;
+        if version < CODELIST_SCHEMA_VERSION {
+            codelist.add_log(format!("Upgraded from codelist schema version {} to {}", version, CODELIST_SCHEMA_VERSION));
+        }
+        Ok(codelist)
+    }
+
     /// Save the logs to a file
     ///
     /// # Arguments
@@ -156,11 +520,11 @@ impl CodeList {
     /// # Errors
     /// * `CodeListError::IOError` - If an error occurs when writing to the file
     pub fn save_log(&self, file_path: &str) -> std::result::Result<(), CodeListError> {
-        let mut file = std::fs::File::create(file_path)?;
+        let mut file = OutputFile::create(file_path)?;
         for log in &self.logs {
             writeln!(file, "{}", log)?;
         }
-        Ok(())
+        file.finish()
     }
 
     /// Add a log message to the codelist
@@ -179,6 +543,25 @@ impl CodeList {
         &self.metadata
     }
 
+    /// Bump the version of the codelist, for when it is edited after it was published
+    ///
+    /// The new version is logged. See `CodeListVersion::bumped` for how versions are bumped.
+    ///
+    /// # Arguments
+    /// * `bump` - Which part of a semantic version to increase
+    ///
+    /// # Returns
+    /// * `Result<&CodeListVersion, CodeListError>` - The new version or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the codelist has no version to bump
+    pub fn bump_version(&mut self, bump: VersionBump) -> Result<&CodeListVersion, CodeListError> {
+        let old_version = self.metadata.version.take()
+            .ok_or_else(|| CodeListError::invalid_input("Codelist has no version to bump"))?;
+        let new_version = old_version.bumped(bump);
+        self.add_log(format!("Bumped version from {} to {}", old_version, new_version));
+        Ok(self.metadata.version.insert(new_version))
+    }
 }
 
 
@@ -186,18 +569,11 @@ impl CodeList {
 mod tests {
     use super::*;
     use crate::metadata::MetadataSource;
+    use crate::codelist_options::XlsxSheet;
+    use crate::codelist_factory::CodeListFactory;
+    use crate::test_utils::create_test_metadata;
     use tempfile::TempDir;
 
-    // Helper function to create test metadata
-    fn create_test_metadata() -> Metadata {
-        Metadata {
-            source: MetadataSource::ManuallyCreated,
-            authors: Some(vec!["Caroline Morton".to_string()]),
-            version: Some("2024-12-19".to_string()),
-            description: Some("A test codelist".to_string()),
-        }
-    }
-
     // Helper function to create a test codelist with two entries, default options and test metadata
     fn create_test_codelist() -> Result<CodeList, CodeListError> {
         let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
@@ -213,7 +589,7 @@ mod tests {
 
         assert_eq!(codelist.metadata().source, MetadataSource::ManuallyCreated);
         assert_eq!(codelist.metadata().authors, Some(vec!["Caroline Morton".to_string()]));
-        assert_eq!(codelist.metadata().version, Some("2024-12-19".to_string()));
+        assert_eq!(codelist.metadata().version, Some("2024-12-19".parse()?));
         assert_eq!(codelist.metadata().description, Some("A test codelist".to_string()));
         assert_eq!(codelist.codelist_type(), &CodeListType::ICD10);
         assert_eq!(codelist.full_entries().len(), 2);
@@ -235,21 +611,45 @@ mod tests {
             term_column_name: "test_term".to_string(),
             code_field_name: "test_code".to_string(),
             term_field_name: "test_term".to_string(),
+            comment_column_name: "test_comment".to_string(),
+            xlsx_sheet: XlsxSheet::Name("test_sheet".to_string()),
+            xlsx_header_row: 2,
+            csv_delimiter: Some('\t'),
+            csv_encoding: Some("windows-1252".to_string()),
+            trim_headers: false,
+            case_insensitive_headers: false,
+            lenient_loading: true,
+            file_name_template: "{type}_{id}".to_string(),
+            type_column_name: Some("coding_system".to_string()),
+            overwrite_existing_files: false,
+            write_metadata_sidecar: true,
         };
         
         let codelist = CodeList::new(CodeListType::ICD10, metadata, Some(codelist_options));
 
-        assert_eq!(codelist.codelist_options.allow_duplicates, true);
-        assert_eq!(codelist.codelist_options.truncate_to_3_digits, true);
-        assert_eq!(codelist.codelist_options.add_x_codes, true);
+        assert!(codelist.codelist_options.allow_duplicates);
+        assert!(codelist.codelist_options.truncate_to_3_digits);
+        assert!(codelist.codelist_options.add_x_codes);
         assert_eq!(codelist.codelist_options.code_field_name, "test_code".to_string());
         assert_eq!(codelist.codelist_options.term_field_name, "test_term".to_string());
         assert_eq!(codelist.codelist_options.code_column_name, "test_code".to_string());
         assert_eq!(codelist.codelist_options.term_column_name, "test_term".to_string());
+        assert_eq!(codelist.codelist_options.comment_column_name, "test_comment".to_string());
+        assert_eq!(codelist.codelist_options.xlsx_sheet, XlsxSheet::Name("test_sheet".to_string()));
+        assert_eq!(codelist.codelist_options.xlsx_header_row, 2);
+        assert_eq!(codelist.codelist_options.csv_delimiter, Some('\t'));
+        assert_eq!(codelist.codelist_options.csv_encoding, Some("windows-1252".to_string()));
+        assert!(!codelist.codelist_options.trim_headers);
+        assert!(!codelist.codelist_options.case_insensitive_headers);
+        assert!(codelist.codelist_options.lenient_loading);
+        assert_eq!(codelist.codelist_options.file_name_template, "{type}_{id}".to_string());
+        assert_eq!(codelist.codelist_options.type_column_name, Some("coding_system".to_string()));
+        assert!(!codelist.codelist_options.overwrite_existing_files);
+        assert!(codelist.codelist_options.write_metadata_sidecar);
 
         assert_eq!(codelist.metadata().source, MetadataSource::ManuallyCreated);
         assert_eq!(codelist.metadata().authors, Some(vec!["Caroline Morton".to_string()]));
-        assert_eq!(codelist.metadata().version, Some("2024-12-19".to_string()));
+        assert_eq!(codelist.metadata().version, Some("2024-12-19".parse().unwrap()));
         assert_eq!(codelist.metadata().description, Some("A test codelist".to_string()));
         assert_eq!(codelist.codelist_type(), &CodeListType::ICD10);
         assert_eq!(codelist.full_entries().len(), 0);
@@ -342,6 +742,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_entries_sorted_by_code() -> Result<(), CodeListError> {
+        let codelist = create_test_codelist()?;
+        let codes: Vec<&str> = codelist.entries_sorted_by_code().iter().map(|entry| entry.code.as_str()).collect();
+
+        assert_eq!(codes, vec!["A48.51", "R65.2"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_codes() -> Result<(), CodeListError> {
         let codelist = create_test_codelist()?;
@@ -375,6 +785,57 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_save_to_xlsx() -> Result<(), CodeListError> {
+        use calamine::{open_workbook, Data, Reader, Xlsx};
+
+        let temp_dir = TempDir::new()?;
+        let file_path = temp_dir.path().join("test.xlsx");
+        let file_path_str = file_path.to_str().ok_or(CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+        let mut codelist = create_test_codelist()?;
+        codelist.add_log("Test log message".to_string());
+        codelist.save_to_xlsx(file_path_str)?;
+
+        let mut workbook: Xlsx<_> = open_workbook(file_path_str)?;
+        assert_eq!(workbook.sheet_names(), vec!["Entries".to_string(), "Metadata".to_string()]);
+
+        let entries = workbook.worksheet_range("Entries")?;
+        let rows: Vec<Vec<String>> = entries.rows()
+            .map(|row| row.iter().map(Data::to_string).collect())
+            .collect();
+        assert_eq!(rows, vec![
+            vec!["code", "term", "comment"],
+            vec!["A48.51", "Infant botulism", "test comment"],
+            vec!["R65.2", "Severe sepsis", ""],
+        ]);
+
+        let metadata = workbook.worksheet_range("Metadata")?;
+        let rows: Vec<Vec<String>> = metadata.rows()
+            .map(|row| row.iter().map(Data::to_string).collect())
+            .collect();
+        assert_eq!(rows, vec![
+            vec!["codelist_type", "ICD10"],
+            vec!["source", "Manually created"],
+            vec!["authors", "Caroline Morton"],
+            vec!["version", "2024-12-19"],
+            vec!["source_version", ""],
+            vec!["description", "A test codelist"],
+            vec!["id", ""],
+            vec!["title", ""],
+            vec!["created", ""],
+            vec!["updated", ""],
+            vec!["licence", ""],
+            vec!["terminology", ""],
+            vec!["terminology_release", ""],
+            vec!["references", ""],
+            vec!["intended_use", ""],
+            vec!["", ""],
+            vec!["logs", "Test log message"],
+        ]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_save_to_json() -> Result<(), CodeListError> {
         let temp_dir = TempDir::new()?;
@@ -391,6 +852,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_save_to_json_writes_schema_version() -> Result<(), CodeListError> {
+        let temp_dir = TempDir::new()?;
+        let file_path = temp_dir.path().join("test_codelist.json");
+        let file_path_str = file_path.to_str().ok_or(CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        create_test_codelist()?.save_to_json(file_path_str)?;
+        let json_data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(file_path_str)?)?;
+
+        assert_eq!(json_data["schema_version"], CODELIST_SCHEMA_VERSION);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_saved_json_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = TempDir::new()?;
+        let file_path = temp_dir.path().join("test_codelist.json");
+        let file_path_str = file_path.to_str().ok_or(CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        let mut original_codelist = create_test_codelist()?;
+        original_codelist.add_log("Test log message".to_string());
+        original_codelist.save_to_json(file_path_str)?;
+        let json_data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(file_path_str)?)?;
+        let loaded_codelist = CodeList::from_saved_json(json_data)?;
+
+        assert_eq!(original_codelist, loaded_codelist);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bump_version() -> Result<(), CodeListError> {
+        let mut codelist = create_test_codelist()?;
+        codelist.metadata.version = Some("1.4.2".parse()?);
+
+        assert_eq!(codelist.bump_version(VersionBump::Minor)?, &CodeListVersion::semantic(1, 5, 0));
+        assert_eq!(codelist.metadata.version, Some(CodeListVersion::semantic(1, 5, 0)));
+        assert_eq!(codelist.logs, vec!["Bumped version from 1.4.2 to 1.5.0".to_string()]);
+
+        codelist.metadata.version = None;
+        let error = codelist.bump_version(VersionBump::Patch).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Codelist has no version to bump"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_saved_json_missing_schema_version() -> Result<(), CodeListError> {
+        let error = CodeList::from_saved_json(serde_json::json!({"logs": []})).unwrap_err();
+
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "No schema_version field found in saved codelist JSON"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_saved_json_upgrades_older_versions() -> Result<(), CodeListError> {
+        // Files written before schema versions were added are the plain serde form of the codelist
+        let original_codelist = create_test_codelist()?;
+        let mut json_data = serde_json::to_value(&original_codelist)?;
+        let object = json_data.as_object_mut().unwrap();
+        object.remove("name");
+        object.remove("id");
+        let loaded_codelist = CodeList::from_saved_json(json_data.clone())?;
+        assert_eq!(loaded_codelist.entries, original_codelist.entries);
+        assert_eq!(loaded_codelist.logs, vec!["Upgraded from codelist schema version 0 to 5".to_string()]);
+
+        json_data["schema_version"] = serde_json::json!(1);
+        let loaded_codelist = CodeList::from_saved_json(json_data)?;
+        assert_eq!(loaded_codelist.logs, vec!["Upgraded from codelist schema version 1 to 5".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_saved_json_unsupported_schema_version() -> Result<(), CodeListError> {
+        let mut json_data = serde_json::to_value(create_test_codelist()?)?;
+        json_data["schema_version"] = serde_json::json!(99);
+        let error = CodeList::from_saved_json(json_data).unwrap_err();
+
+        assert_eq!(error.to_string(), "Unsupported codelist schema version: 99. Supported version: 5");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_saved_json_not_an_object() {
+        let error = CodeList::from_saved_json(serde_json::json!([])).unwrap_err();
+
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Saved codelist JSON must be an object"));
+    }
+
     #[test]
     fn test_add_to_log() -> Result<(), CodeListError> {
         let mut codelist = create_test_codelist()?;
@@ -425,6 +978,80 @@ mod tests {
 
         assert_eq!(codelist.metadata(), &metadata);
     }
+
+    #[test]
+    fn test_save_to_writer() -> Result<(), CodeListError> {
+        let codelist = create_test_codelist()?;
+        let factory = CodeListFactory::new(CodeListOptions::default(), create_test_metadata(), CodeListType::ICD10);
+
+        let mut csv_data = Vec::new();
+        codelist.save_to_writer(&mut csv_data, CodeListFormat::Csv)?;
+        let loaded_codelist = factory.load_codelist_from_csv_reader(csv_data.as_slice())?;
+        assert_eq!(loaded_codelist.code_term_entries(), codelist.code_term_entries());
+
+        let mut json_data = Vec::new();
+        codelist.save_to_writer(&mut json_data, CodeListFormat::Json)?;
+        let loaded_codelist = factory.load_codelist_from_saved_json_reader(json_data.as_slice())?;
+        assert_eq!(loaded_codelist, codelist);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_file_with_format() -> Result<(), CodeListError> {
+        let codelist = create_test_codelist()?;
+        let temp_dir = TempDir::new()?;
+        let file_path = temp_dir.path().join("codelist.out");
+        let file_path_str = file_path.to_str().unwrap();
+
+        codelist.save_to_file_with_format(file_path_str, CodeListFormat::Xlsx)?;
+
+        let factory = CodeListFactory::new(CodeListOptions::default(), create_test_metadata(), CodeListType::ICD10);
+        let loaded_codelist = factory.load_codelist_from_file_with_format(file_path_str, None)?;
+        assert_eq!(loaded_codelist.entries, codelist.entries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codelist_id_from_name() {
+        assert_eq!(codelist_id_from_name("Asthma Diagnosis (v2)"), "asthma-diagnosis-v2");
+        assert_eq!(codelist_id_from_name("  --Type_2 diabetes--  "), "type-2-diabetes");
+        assert_eq!(codelist_id_from_name("!!!"), "");
+    }
+
+    #[test]
+    fn test_set_name_from_file_path() {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        assert_eq!(codelist.name, None);
+        assert_eq!(codelist.id, None);
+
+        codelist.set_name_from_file_path("folder/Asthma Codes.csv");
+        assert_eq!(codelist.name, Some("Asthma Codes".to_string()));
+        assert_eq!(codelist.id, Some("asthma-codes".to_string()));
+
+        // An existing name and id are kept
+        codelist.set_name_from_file_path("folder/other.csv");
+        assert_eq!(codelist.name, Some("Asthma Codes".to_string()));
+        assert_eq!(codelist.id, Some("asthma-codes".to_string()));
+    }
+
+    #[test]
+    fn test_render_file_name() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        assert_eq!(codelist.render_file_name("{id}", 3)?, "3");
+
+        codelist.set_name("Asthma: Adults/Children");
+        assert_eq!(codelist.render_file_name("{id}", 3)?, "asthma-adults-children");
+        assert_eq!(codelist.render_file_name("{name}", 3)?, "Asthma_ Adults_Children");
+        assert_eq!(codelist.render_file_name("{type}_{id}_{version}_{index}", 3)?, "ICD10_asthma-adults-children_2024-12-19_3");
+
+        let error = codelist.render_file_name("{title}", 3).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Unknown placeholder in file name template: {title}"));
+        let error = codelist.render_file_name("{id", 3).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Unclosed placeholder in file name template: {id"));
+        Ok(())
+    }
 }
 
 // add tests for get code, get code term entries
@@ -0,0 +1,601 @@
+//! This file contains the Arrow IPC and Parquet readers and writers for the codelist
+//!
+//! The entries are stored as `code`, `term` and `comment` string columns. The codelist type,
+//! metadata, logs and options are stored as JSON in the file key-value metadata, so they survive
+//! a round trip. Only available with the `arrow` (and for Parquet, the `parquet`) cargo feature.
+
+// External imports
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use arrow::array::{Array, ArrayRef, RecordBatch, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+#[cfg(feature = "parquet")]
+use bytes::Bytes;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+#[cfg(feature = "parquet")]
+use parquet::file::metadata::KeyValue;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+use serde_json::{Map, Value};
+
+// Internal imports
+use crate::codelist::{CodeList, CODELIST_SCHEMA_VERSION};
+use crate::codelist_factory::CodeListFactory;
+use crate::errors::CodeListError;
+use crate::metadata::Metadata;
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+use crate::schema::migrate_saved_json;
+
+/// Key-value metadata keys used to store the codelist fields that are not entries
+const SCHEMA_VERSION_KEY: &str = "codelist.schema_version";
+const CODELIST_TYPE_KEY: &str = "codelist.codelist_type";
+const METADATA_KEY: &str = "codelist.metadata";
+const LOGS_KEY: &str = "codelist.logs";
+const CODELIST_OPTIONS_KEY: &str = "codelist.codelist_options";
+const NAME_KEY: &str = "codelist.name";
+const ID_KEY: &str = "codelist.id";
+
+/// Upgrade the codelist fields stored in key-value metadata to the current schema version
+///
+/// The stored metadata, logs and options are upgraded with the same migrations as saved codelist
+/// json files. Without a stored schema version, such as for files written by other tools, the
+/// fields are returned as they are.
+///
+/// # Arguments
+/// * `key_value_metadata` - The key-value metadata of an Arrow or Parquet file
+///
+/// # Returns
+/// * `Result<Map<String, Value>, CodeListError>` - The upgraded metadata, logs and options that were stored, or an error
+///
+/// # Errors
+/// * `CodeListError::JSONError` - If the stored metadata, logs or options are not valid json
+/// * `CodeListError::InvalidInput` - If the stored schema version is not a positive integer
+/// * `CodeListError::UnsupportedSchemaVersion` - If the file was written by a newer version of the library
+fn migrate_key_value_metadata(key_value_metadata: &HashMap<String, String>) -> Result<Map<String, Value>, CodeListError> {
+    let mut stored = Map::new();
+    for (field, key) in [("metadata", METADATA_KEY), ("logs", LOGS_KEY), ("codelist_options", CODELIST_OPTIONS_KEY)] {
+        if let Some(value) = key_value_metadata.get(key) {
+            stored.insert(field.to_string(), serde_json::from_str(value)?);
+        }
+    }
+    let Some(version) = key_value_metadata.get(SCHEMA_VERSION_KEY) else {
+        return Ok(stored);
+    };
+    let version: u64 = version.trim().parse()
+        .map_err(|_| CodeListError::invalid_input(format!("{} must be a positive integer, found: {}", SCHEMA_VERSION_KEY, version)))?;
+    stored.insert("schema_version".to_string(), Value::from(version));
+    match migrate_saved_json(Value::Object(stored))? {
+        (Value::Object(upgraded), _) => Ok(upgraded),
+        _ => unreachable!("migrations keep the codelist fields an object"),
+    }
+}
+
+impl CodeList {
+    /// Get the key-value metadata describing the codelist, as stored in Arrow and Parquet files
+    ///
+    /// # Returns
+    /// * `Result<HashMap<String, String>, CodeListError>` - The key-value metadata or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::JSONError` - If the metadata, logs or options cannot be serialised
+    fn arrow_key_value_metadata(&self) -> Result<HashMap<String, String>, CodeListError> {
+        let mut key_value_metadata = HashMap::from([
+            (SCHEMA_VERSION_KEY.to_string(), CODELIST_SCHEMA_VERSION.to_string()),
+            (CODELIST_TYPE_KEY.to_string(), self.codelist_type.to_string()),
+            (METADATA_KEY.to_string(), serde_json::to_string(&self.metadata)?),
+            (LOGS_KEY.to_string(), serde_json::to_string(&self.logs)?),
+            (CODELIST_OPTIONS_KEY.to_string(), serde_json::to_string(&self.codelist_options)?),
+        ]);
+        if let Some(name) = &self.name {
+            key_value_metadata.insert(NAME_KEY.to_string(), name.clone());
+        }
+        if let Some(id) = &self.id {
+            key_value_metadata.insert(ID_KEY.to_string(), id.clone());
+        }
+        Ok(key_value_metadata)
+    }
+
+    /// Convert the codelist entries to an Arrow record batch, sorted by code
+    ///
+    /// The column names are taken from the codelist options and the codelist metadata is stored
+    /// in the schema metadata.
+    ///
+    /// # Returns
+    /// * `Result<RecordBatch, CodeListError>` - The record batch or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::ArrowError` - If the record batch cannot be created
+    /// * `CodeListError::JSONError` - If the metadata, logs or options cannot be serialised
+    pub fn to_record_batch(&self) -> Result<RecordBatch, CodeListError> {
+        let entries = self.entries_sorted_by_code();
+
+        let schema = Schema::new_with_metadata(vec![
+            Field::new(&self.codelist_options.code_field_name, DataType::Utf8, false),
+            Field::new(&self.codelist_options.term_field_name, DataType::Utf8, false),
+            Field::new(&self.codelist_options.comment_column_name, DataType::Utf8, true),
+        ], self.arrow_key_value_metadata()?);
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(entries.iter().map(|entry| &entry.code))),
+            Arc::new(StringArray::from_iter_values(entries.iter().map(|entry| &entry.term))),
+            Arc::new(StringArray::from_iter(entries.iter().map(|entry| entry.comment.as_deref()))),
+        ];
+
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+
+    /// Save the codelist to an Arrow IPC file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when creating the file
+    /// * `CodeListError::ArrowError` - If an error occurs when writing the file
+    pub fn save_to_arrow_ipc(&self, file_path: &str) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_arrow_ipc_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the codelist as an Arrow IPC file to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the Arrow IPC data to
+    ///
+    /// # Errors
+    /// * `CodeListError::ArrowError` - If an error occurs when writing
+    pub fn save_to_arrow_ipc_writer<W: Write>(&self, writer: W) -> Result<(), CodeListError> {
+        let batch = self.to_record_batch()?;
+        let mut writer = FileWriter::try_new(writer, &batch.schema())?;
+        writer.write(&batch)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Save the codelist to a Parquet file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when creating the file
+    /// * `CodeListError::ParquetError` - If an error occurs when writing the file
+    #[cfg(feature = "parquet")]
+    pub fn save_to_parquet(&self, file_path: &str) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_parquet_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the codelist as a Parquet file to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the Parquet data to
+    ///
+    /// # Errors
+    /// * `CodeListError::ParquetError` - If an error occurs when writing
+    #[cfg(feature = "parquet")]
+    pub fn save_to_parquet_writer<W: Write + Send>(&self, writer: W) -> Result<(), CodeListError> {
+        let batch = self.to_record_batch()?;
+        let key_value_metadata = self.arrow_key_value_metadata()?
+            .into_iter()
+            .map(|(key, value)| KeyValue::new(key, value))
+            .collect();
+        let properties = WriterProperties::builder()
+            .set_key_value_metadata(Some(key_value_metadata))
+            .build();
+
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+impl CodeListFactory {
+    /// Load a codelist from an Arrow IPC file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the Arrow IPC file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error opening the file
+    /// * `CodeListError::ArrowError` - If there is an error reading the file
+    /// * See `load_codelist_from_record_batches` for errors in the file contents
+    pub fn load_codelist_from_arrow_ipc_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = File::open(file_path)?;
+        let reader = FileReader::try_new(file, None)?;
+        let schema = reader.schema();
+        let mut codelist = self.load_codelist_from_record_batches(&schema, reader)?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from Arrow IPC data read from any reader
+    ///
+    /// The data is read into memory, as the Arrow IPC file format needs random access.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the Arrow IPC data from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * `CodeListError::ArrowError` - If the data is not a valid Arrow IPC file
+    /// * See `load_codelist_from_record_batches` for errors in the data
+    pub fn load_codelist_from_arrow_ipc_reader<R: Read>(&self, mut reader: R) -> Result<CodeList, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let reader = FileReader::try_new(Cursor::new(bytes), None)?;
+        let schema = reader.schema();
+        self.load_codelist_from_record_batches(&schema, reader)
+    }
+
+    /// Load a codelist from a Parquet file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the Parquet file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error opening the file
+    /// * `CodeListError::ParquetError` - If there is an error reading the file
+    /// * See `load_codelist_from_record_batches` for errors in the file contents
+    #[cfg(feature = "parquet")]
+    pub fn load_codelist_from_parquet_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = File::open(file_path)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let schema = builder.schema().clone();
+        let reader = builder.build()?;
+        let mut codelist = self.load_codelist_from_record_batches(&schema, reader)?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from Parquet data read from any reader
+    ///
+    /// The data is read into memory, as the Parquet format needs random access.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the Parquet data from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * `CodeListError::ParquetError` - If the data is not a valid Parquet file
+    /// * See `load_codelist_from_record_batches` for errors in the data
+    #[cfg(feature = "parquet")]
+    pub fn load_codelist_from_parquet_reader<R: Read>(&self, mut reader: R) -> Result<CodeList, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(bytes))?;
+        let schema = builder.schema().clone();
+        let reader = builder.build()?;
+        self.load_codelist_from_record_batches(&schema, reader)
+    }
+
+    /// Load a codelist from Arrow record batches
+    ///
+    /// If the schema metadata contains the codelist type, metadata, logs and options written by
+    /// `CodeList::to_record_batch` they are used, otherwise the factory's own settings are used.
+    /// Fields written by older versions of the library are upgraded as for saved codelist json.
+    /// Code columns that are not strings (such as integer SNOMED codes) are cast to strings.
+    ///
+    /// # Arguments
+    /// * `schema` - The schema of the record batches
+    /// * `batches` - The record batches
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::ArrowError` - If there is an error reading a batch or casting a column
+    /// * `CodeListError::JSONError` - If the stored metadata, logs or options cannot be parsed
+    /// * `CodeListError::InvalidInput` - If the stored schema version is not valid
+    /// * `CodeListError::UnsupportedSchemaVersion` - If the file was written by a newer version of the library
+    /// * `CodeListError::InvalidCodeListType` - If the stored codelist type is not valid
+    /// * `CodeListError::InvalidCodeField` - If the code column is missing
+    /// * `CodeListError::InvalidTermField` - If the term column is missing
+    /// * `CodeListError::EmptyCode` - If a code value is empty or null
+    /// * `CodeListError::EmptyTerm` - If a term value is empty or null
+    pub fn load_codelist_from_record_batches<I>(&self, schema: &Schema, batches: I) -> Result<CodeList, CodeListError>
+    where
+        I: IntoIterator<Item = Result<RecordBatch, ArrowError>>,
+    {
+        let key_value_metadata = schema.metadata();
+        let codelist_type = match key_value_metadata.get(CODELIST_TYPE_KEY) {
+            Some(codelist_type) => CodeListType::from_str(codelist_type)?,
+            None => self.codelist_type.clone(),
+        };
+        let mut stored = migrate_key_value_metadata(key_value_metadata)?;
+        let metadata = match stored.remove("metadata") {
+            Some(metadata) => serde_json::from_value(metadata)?,
+            None => self.metadata.clone(),
+        };
+        let codelist_options = match stored.remove("codelist_options") {
+            Some(codelist_options) => serde_json::from_value(codelist_options)?,
+            None => self.codelist_options.clone(),
+        };
+        let logs: Vec<String> = match stored.remove("logs") {
+            Some(logs) => serde_json::from_value(logs)?,
+            None => Vec::new(),
+        };
+
+        // Match the columns with the loaded options, as the csv and xlsx readers do
+        let reader = CodeListFactory::new(codelist_options.clone(), Metadata::default(), codelist_type.clone());
+        let headers: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+        let (code_idx, term_idx) = reader.code_and_term_columns(headers.iter().copied())?;
+        let comment_idx = headers.iter().position(|h| reader.header_matches(h, &codelist_options.comment_column_name));
+
+        let mut codelist = CodeList::new(codelist_type, metadata, Some(codelist_options));
+        codelist.name = key_value_metadata.get(NAME_KEY).cloned();
+        codelist.id = key_value_metadata.get(ID_KEY).cloned();
+        codelist.logs = logs;
+
+        let mut row_offset = 0;
+        for batch in batches {
+            let batch = batch?;
+            let codes = cast(batch.column(code_idx), &DataType::Utf8)?;
+            let codes = codes.as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| CodeListError::invalid_code_type("Code column could not be read as strings"))?;
+            let terms = cast(batch.column(term_idx), &DataType::Utf8)?;
+            let terms = terms.as_any().downcast_ref::<StringArray>()
+                .ok_or_else(|| CodeListError::invalid_term_type("Term column could not be read as strings"))?;
+            let comments = comment_idx
+                .map(|idx| cast(batch.column(idx), &DataType::Utf8))
+                .transpose()?;
+            let comments = comments.as_ref().and_then(|comments| comments.as_any().downcast_ref::<StringArray>());
+
+            for idx in 0..batch.num_rows() {
+                let row_num = row_offset + idx + 1;
+                let code = if codes.is_null(idx) { "" } else { codes.value(idx).trim() };
+                if code.is_empty() {
+                    return Err(CodeListError::empty_code(format!("Empty code field in row: {}", row_num)));
+                }
+                let term = if terms.is_null(idx) { "" } else { terms.value(idx).trim() };
+                if term.is_empty() {
+                    return Err(CodeListError::empty_term(format!("Empty term field in row: {}", row_num)));
+                }
+                let comment = comments
+                    .filter(|comments| !comments.is_null(idx))
+                    .map(|comments| comments.value(idx).trim().to_string())
+                    .filter(|comment| !comment.is_empty());
+                codelist.add_entry(code.to_string(), term.to_string(), comment)?;
+            }
+            row_offset += batch.num_rows();
+        }
+
+        Ok(codelist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use arrow::array::Int64Array;
+    use tempfile::tempdir;
+
+    // Helper function to create a test codelist with two entries and a log
+    fn create_test_codelist() -> Result<CodeList, CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.set_name("Test Codelist");
+        codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), Some("test comment".to_string()))?;
+        codelist.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+        codelist.add_log("Test log message".to_string());
+        Ok(codelist)
+    }
+
+    #[test]
+    fn test_to_record_batch() -> Result<(), CodeListError> {
+        let batch = create_test_codelist()?.to_record_batch()?;
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(0).name(), "code");
+        assert_eq!(batch.schema().field(1).name(), "term");
+        assert_eq!(batch.schema().field(2).name(), "comment");
+        assert_eq!(batch.schema().metadata().get(CODELIST_TYPE_KEY), Some(&"SNOMED".to_string()));
+
+        let codes = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(codes.value(0), "195967001");
+        assert_eq!(codes.value(1), "22298006");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_ipc_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.arrow");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let original_codelist = create_test_codelist()?;
+        original_codelist.save_to_arrow_ipc(file_path_str)?;
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_arrow_ipc_file(file_path_str)?;
+
+        assert_eq!(loaded_codelist, original_codelist);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.parquet");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let original_codelist = create_test_codelist()?;
+        original_codelist.save_to_parquet(file_path_str)?;
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_parquet_file(file_path_str)?;
+
+        assert_eq!(loaded_codelist, original_codelist);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_load_codelist_from_file_parquet() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.parquet");
+        let file_path_str = file_path.to_str().unwrap();
+
+        create_test_codelist()?.save_to_parquet(file_path_str)?;
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_file(file_path_str)?;
+
+        assert_eq!(loaded_codelist.entries.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_record_batches_without_codelist_metadata() -> Result<(), CodeListError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("code", DataType::Int64, false),
+            Field::new("term", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![
+            Arc::new(Int64Array::from(vec![22298006, 195967001])),
+            Arc::new(StringArray::from(vec!["Myocardial infarction", "Asthma"])),
+        ])?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelist = factory.load_codelist_from_record_batches(&schema, vec![Ok(batch)])?;
+
+        assert_eq!(codelist.entries.len(), 2);
+        assert!(codelist.entries.iter().any(|e| e.code == "22298006" && e.term == "Myocardial infarction" && e.comment.is_none()));
+        assert_eq!(codelist.codelist_type, CodeListType::ICD10);
+        assert_eq!(codelist.metadata, factory.metadata);
+        assert!(codelist.logs.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_record_batches_matches_headers_with_options() -> Result<(), CodeListError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(" Code ", DataType::Utf8, false),
+            Field::new("TERM", DataType::Utf8, false),
+            Field::new("Comment", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![
+            Arc::new(StringArray::from(vec!["A09", "B01"])),
+            Arc::new(StringArray::from(vec!["Gastroenteritis", "Varicella"])),
+            Arc::new(StringArray::from(vec![Some("Common"), None])),
+        ])?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelist = factory.load_codelist_from_record_batches(&schema, vec![Ok(batch)])?;
+
+        assert_eq!(codelist.entries.len(), 2);
+        assert!(codelist.entries.iter().any(|e| e.code == "A09" && e.term == "Gastroenteritis" && e.comment == Some("Common".to_string())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_record_batches_older_schema_version() -> Result<(), CodeListError> {
+        let batch = create_test_codelist()?.to_record_batch()?;
+        let mut key_value_metadata = batch.schema().metadata().clone();
+        // Version 3 files had no metadata id or source version, and could hold free-form versions
+        key_value_metadata.insert(SCHEMA_VERSION_KEY.to_string(), "3".to_string());
+        key_value_metadata.insert(METADATA_KEY.to_string(), r#"{"source": "ManuallyCreated", "authors": ["Caroline Morton"], "version": "v2 draft", "description": null}"#.to_string());
+        let schema = batch.schema().as_ref().clone().with_metadata(key_value_metadata.clone());
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelist = factory.load_codelist_from_record_batches(&schema, vec![Ok(batch.clone())])?;
+        assert_eq!(codelist.entries.len(), 2);
+        assert_eq!(codelist.metadata.version, None);
+        assert_eq!(codelist.metadata.source_version, Some("v2 draft".to_string()));
+        assert_eq!(codelist.metadata.authors, Some(vec!["Caroline Morton".to_string()]));
+        assert_eq!(codelist.logs.last(), Some(&"Moved version v2 draft, which is not a date or semantic version, to the source version".to_string()));
+
+        key_value_metadata.insert(SCHEMA_VERSION_KEY.to_string(), "99".to_string());
+        let schema = schema.with_metadata(key_value_metadata);
+        let error = factory.load_codelist_from_record_batches(&schema, vec![Ok(batch)]).unwrap_err();
+        assert!(matches!(error, CodeListError::UnsupportedSchemaVersion { version, .. } if version == "99"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_record_batches_missing_term_column() -> Result<(), CodeListError> {
+        let schema = Schema::new(vec![Field::new("code", DataType::Utf8, false)]);
+        let error = create_test_codelist_factory(CodeListType::ICD10)
+            .load_codelist_from_record_batches(&schema, Vec::new())
+            .unwrap_err();
+
+        assert!(matches!(error, CodeListError::InvalidTermField { msg } if msg == "Column not found with the header: term"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_record_batches_empty_code() -> Result<(), CodeListError> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("code", DataType::Utf8, true),
+            Field::new("term", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![
+            Arc::new(StringArray::from(vec![Some("A01"), None])),
+            Arc::new(StringArray::from(vec!["Test Disease 1", "Test Disease 2"])),
+        ])?;
+
+        let error = create_test_codelist_factory(CodeListType::ICD10)
+            .load_codelist_from_record_batches(&schema, vec![Ok(batch)])
+            .unwrap_err();
+
+        assert!(matches!(error, CodeListError::EmptyCode { msg } if msg == "Empty code field in row: 2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_ipc_reader_and_writer() -> Result<(), CodeListError> {
+        let original_codelist = create_test_codelist()?;
+        let mut data = Vec::new();
+        original_codelist.save_to_arrow_ipc_writer(&mut data)?;
+
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_reader(data.as_slice(), None)?;
+
+        assert_eq!(loaded_codelist, original_codelist);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_reader_and_writer() -> Result<(), CodeListError> {
+        let original_codelist = create_test_codelist()?;
+        let mut data = Vec::new();
+        original_codelist.save_to_parquet_writer(&mut data)?;
+
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_reader(data.as_slice(), None)?;
+
+        assert_eq!(loaded_codelist, original_codelist);
+
+        Ok(())
+    }
+}
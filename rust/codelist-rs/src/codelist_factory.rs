@@ -1,8 +1,21 @@
 
+use calamine::{Data, HeaderRow, Range, Reader, Xlsx};
+use csv::StringRecord;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use std::collections::HashSet;
+use std::io::{BufReader, Cursor, Read};
+
 use crate::codelist::CodeList;
 use crate::errors::CodeListError;
-use crate::codelist_options::CodeListOptions;
-use crate::metadata::{Metadata, MetadataSource};
+use crate::codelist_format::{skip_utf8_bom, CodeListFormat};
+use crate::compression::{decompress_if_compressed, has_codelist_extension, is_compressed_file, strip_compression_extension};
+use crate::folder_load::FolderLoadOptions;
+use crate::load_report::{LoadIssue, LoadIssueKind, LoadReport};
+use crate::processing::{ProcessingReport, ProcessingStep};
+use crate::schema::is_saved_codelist_json;
+use crate::codelist_options::{CodeListOptions, XlsxSheet};
+use crate::metadata::Metadata;
+use crate::metadata_sidecar::apply_metadata_sidecar;
 use crate::types::CodeListType;
 
 /// Struct to represent a codelist factory, which is used to load codelists from a directory and make sure all codelists are created following the same rules
@@ -11,10 +24,12 @@ use crate::types::CodeListType;
 /// * `codelist_options` - The options for the codelist
 /// * `metadata` - The metadata for the codelist
 /// * `codelist_type` - The type of codelist
+/// * `processing_steps` - The steps `process_codelists` applies to each codelist, in order
 pub struct CodeListFactory {
     pub codelist_options: CodeListOptions,
     pub metadata: Metadata,
     pub codelist_type: CodeListType,
+    pub processing_steps: Vec<ProcessingStep>,
 }
 
 impl CodeListFactory {
@@ -24,8 +39,12 @@ impl CodeListFactory {
     /// * `codelist_options` - The options for the codelist
     /// * `metadata` - The metadata for the codelist
     /// * `codelist_type` - The type of codelist
+    ///
+    /// * The processing steps start as the default pipeline for the codelist options, see
+    ///   `ProcessingStep::default_pipeline`
     pub fn new(codelist_options: CodeListOptions, metadata: Metadata, codelist_type: CodeListType) -> Self {
         CodeListFactory {
+            processing_steps: ProcessingStep::default_pipeline(&codelist_options),
             codelist_options: codelist_options.clone(),
             metadata: metadata.clone(),
             codelist_type: codelist_type.clone(),
@@ -49,20 +68,252 @@ impl CodeListFactory {
     /// * `CodeListError::InvalidTerm` - If the term value is not a valid string
     /// * `CodeListError::EmptyCode` - If the code value is an empty string
     /// * `CodeListError::EmptyTerm` - If the term value is an empty string
+    /// * `CodeListError::InvalidInput` - If the configured delimiter or encoding is invalid, or the file cannot be decoded
+    /// * `CodeListError::JSONError` - If the metadata sidecar file of the csv is not valid metadata JSON
+    /// * `CodeListError::UnsupportedSchemaVersion` - If the metadata sidecar file was written by a newer version of the library
+    ///
+    /// * The metadata is read from the sidecar file next to the csv, such as `asthma.csv.meta.json`
+    ///   for `asthma.csv`, when there is one. Otherwise the factory metadata is used.
+    /// * The delimiter, encoding and header matching are configured in the codelist options. By
+    ///   default the delimiter is detected from the header line, the encoding is detected from any
+    ///   byte order mark (falling back to UTF-8, then Windows-1252) and headers are matched ignoring
+    ///   surrounding whitespace and case. Byte order marks are always stripped.
+    /// * With the `lenient_loading` option, invalid rows are skipped rather than failing the load.
+    ///   Use `load_codelist_from_csv_file_with_report` to see which rows were skipped and why.
     pub fn load_codelist_from_csv_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
-        let mut rdr = csv::Reader::from_path(file_path)?;
+        let (codelist, _) = self.load_codelist_from_csv_file_with_report(file_path)?;
+        Ok(codelist)
+    }
+
+    /// Load a codelist from csv data read from any reader, such as stdin or an in-memory buffer
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the csv data from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_csv_file`
+    pub fn load_codelist_from_csv_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
+        let (codelist, _) = self.load_codelist_from_csv_reader_with_report(reader)?;
+        Ok(codelist)
+    }
+
+    /// Load a codelist from a csv file, along with a report of the rows loaded and any problems
+    ///
+    /// With the `lenient_loading` option, rows with a parse error, a missing value, an empty code
+    /// or an empty term are skipped and recorded in the report, and a log entry records how many
+    /// rows were skipped. Otherwise the first such row fails the load, as in
+    /// `load_codelist_from_csv_file`. Problems with the headers always fail the load.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the csv file
+    ///
+    /// # Returns
+    /// * `Result<(CodeList, LoadReport), CodeListError>` - The codelist and load report, or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_csv_file`
+    pub fn load_codelist_from_csv_file_with_report(&self, file_path: &str) -> Result<(CodeList, LoadReport), CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let (mut codelist, report) = self.load_codelist_from_csv_reader_with_report(file)?;
+        codelist.set_name_from_file_path(file_path);
+        apply_metadata_sidecar(&mut codelist, file_path)?;
+        Ok((codelist, report))
+    }
+
+    /// Load a codelist from csv data read from any reader, along with a load report
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the csv data from
+    ///
+    /// # Returns
+    /// * `Result<(CodeList, LoadReport), CodeListError>` - The codelist and load report, or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_csv_file`
+    pub fn load_codelist_from_csv_reader_with_report<R: Read>(&self, reader: R) -> Result<(CodeList, LoadReport), CodeListError> {
+        let mut rdr = self.csv_reader(reader)?;
         let headers = rdr.headers()?;
         let mut codelist = CodeList::new(self.codelist_type.clone(), self.metadata.clone(), Some(self.codelist_options.clone()));
         
+        let (code_idx, term_idx) = self.code_and_term_columns(headers)?;
+        let code_header = headers.get(code_idx).unwrap_or_default().to_string();
+        let term_header = headers.get(term_idx).unwrap_or_default().to_string();
+        let lenient = self.codelist_options.lenient_loading;
+        let mut report = LoadReport::default();
+
+        let mut record = StringRecord::new();
+        for row in 2.. {
+            // The raw row is recorded for problems that are not in a single value
+            let row_start = rdr.position().byte() as usize;
+            let result = rdr.read_record(&mut record);
+            let row_end = rdr.position().byte() as usize;
+            let raw_row = String::from_utf8_lossy(rdr.get_ref().get_ref().get(row_start..row_end).unwrap_or_default())
+                .trim_matches(['\r', '\n'])
+                .to_string();
+            match result {
+                Ok(true) => report.rows_read += 1,
+                Ok(false) => break,
+                Err(err) if lenient => {
+                    report.rows_read += 1;
+                    report.issues.push(LoadIssue {
+                        row,
+                        column: None,
+                        kind: LoadIssueKind::MalformedRow,
+                        raw_value: raw_row,
+                        message: err.to_string(),
+                    });
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            }
+
+            let mut row_issues = Vec::new();
+            let mut field = |idx: usize, header: &str, empty_kind: LoadIssueKind, empty_message: String| {
+                match record.get(idx) {
+                    Some(raw) if !raw.trim().is_empty() => Some(raw.trim().to_string()),
+                    Some(raw) => {
+                        row_issues.push(LoadIssue { row, column: Some(header.to_string()), kind: empty_kind, raw_value: raw.to_string(), message: empty_message });
+                        None
+                    }
+                    None => {
+                        row_issues.push(LoadIssue {
+                            row,
+                            column: Some(header.to_string()),
+                            kind: LoadIssueKind::MissingValue,
+                            raw_value: raw_row.clone(),
+                            message: format!("Row {}: Cannot access column at index {}.", row, idx),
+                        });
+                        None
+                    }
+                }
+            };
+            let code = field(code_idx, &code_header, LoadIssueKind::EmptyCode, format!("Empty code field in row: {}", row));
+            let term = field(term_idx, &term_header, LoadIssueKind::EmptyTerm, format!("Empty term field in row: {}", row));
+
+            match (code, term) {
+                (Some(code), Some(term)) => {
+                    codelist.add_entry(code, term, None)?;
+                    report.rows_loaded += 1;
+                }
+                _ if lenient => report.issues.append(&mut row_issues),
+                _ => return Err(row_issues[0].to_error()),
+            }
+        }
+
+        if !report.is_clean() {
+            codelist.add_log(format!("Skipped {} of {} rows with problems while loading", report.rows_skipped(), report.rows_read));
+        }
+        Ok((codelist, report))
+    }
+
+    /// Open a csv reader over csv data, following the delimiter and encoding options
+    ///
+    /// The data is read in full so that its encoding and delimiter can be detected.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the csv data from
+    ///
+    /// # Returns
+    /// * `Result<csv::Reader<Cursor<Vec<u8>>>, CodeListError>` - The csv reader or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * `CodeListError::InvalidInput` - If the configured delimiter or encoding is invalid, or the data cannot be decoded
+    pub(crate) fn csv_reader<R: Read>(&self, mut reader: R) -> Result<csv::Reader<Cursor<Vec<u8>>>, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let text = self.decode_csv_bytes(&bytes)?;
+        let delimiter = match self.codelist_options.csv_delimiter {
+            Some(delimiter) if delimiter.is_ascii() => delimiter as u8,
+            Some(delimiter) => return Err(CodeListError::invalid_input(format!("CSV delimiter must be an ASCII character, found: {}", delimiter))),
+            None => sniff_csv_delimiter(&text),
+        };
+
+        Ok(csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(self.codelist_options.lenient_loading)
+            .from_reader(Cursor::new(text.into_bytes())))
+    }
+
+    /// Decode the raw bytes of a csv file into a string, stripping any byte order mark
+    ///
+    /// # Arguments
+    /// * `bytes` - The raw bytes of the csv file
+    ///
+    /// # Returns
+    /// * `Result<String, CodeListError>` - The decoded text or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the configured encoding is unknown or the bytes are not valid in it
+    fn decode_csv_bytes(&self, bytes: &[u8]) -> Result<String, CodeListError> {
+        let encoding = match &self.codelist_options.csv_encoding {
+            Some(label) => Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| CodeListError::invalid_input(format!("Unknown CSV encoding: {}", label)))?,
+            None => match Encoding::for_bom(bytes) {
+                Some((encoding, _)) => encoding,
+                None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+                None => WINDOWS_1252,
+            },
+        };
+
+        // A byte order mark takes precedence over the configured encoding and is removed
+        let (text, encoding, had_errors) = encoding.decode(bytes);
+        if had_errors {
+            return Err(CodeListError::invalid_input(format!("CSV file is not valid {}", encoding.name())));
+        }
+        Ok(text.into_owned())
+    }
+
+    /// Check whether a column header matches an expected column name, following the header
+    /// matching options
+    ///
+    /// # Arguments
+    /// * `header` - The column header found in the file
+    /// * `name` - The expected column name
+    ///
+    /// # Returns
+    /// * `bool` - Whether the header matches
+    pub(crate) fn header_matches(&self, header: &str, name: &str) -> bool {
+        let (header, name) = if self.codelist_options.trim_headers {
+            (header.trim(), name.trim())
+        } else {
+            (header, name)
+        };
+        if self.codelist_options.case_insensitive_headers {
+            header.to_lowercase() == name.to_lowercase()
+        } else {
+            header == name
+        }
+    }
+
+    /// Find the code and term columns among the column headers of a csv file or xlsx sheet
+    ///
+    /// The columns are named by the `code_field_name` and `term_field_name` options and matched
+    /// with `header_matches`.
+    ///
+    /// # Arguments
+    /// * `headers` - The column headers, in column order
+    ///
+    /// # Returns
+    /// * `Result<(usize, usize), CodeListError>` - The indexes of the code and term columns, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidCodeField` - If the code column is missing or duplicated
+    /// * `CodeListError::InvalidTermField` - If the term column is missing or duplicated
+    pub(crate) fn code_and_term_columns<'a>(&self, headers: impl IntoIterator<Item = &'a str>) -> Result<(usize, usize), CodeListError> {
+        let headers: Vec<&str> = headers.into_iter().collect();
         let code_column: Vec<_> = headers.iter()
             .enumerate()
-            .filter(|(_, h)| *h == self.codelist_options.code_field_name)
+            .filter(|(_, h)| self.header_matches(h, &self.codelist_options.code_field_name))
             .collect();
         let term_column: Vec<_> = headers.iter()
             .enumerate()
-            .filter(|(_, h)| *h == self.codelist_options.term_field_name)
+            .filter(|(_, h)| self.header_matches(h, &self.codelist_options.term_field_name))
             .collect();
-        
+
         if code_column.len() > 1 {
             return Err(CodeListError::invalid_code_field(format!("Multiple columns found with the header: {}", self.codelist_options.code_field_name)));
         }
@@ -78,30 +329,134 @@ impl CodeListFactory {
             .map(|(idx, _)| *idx)
             .ok_or_else(|| CodeListError::invalid_term_field(format!("Column not found with the header: {}", self.codelist_options.term_field_name)))?;
 
-        for (row_num, result) in rdr.records().enumerate() {
-            let record = result?;
-            let code = record.get(code_idx)
-                .ok_or_else(|| CodeListError::column_index_out_of_bounds(
-                    format!("Row {}: Cannot access column at index {}.", row_num + 2, code_idx)
-                ))?
-                .trim();
+        Ok((code_idx, term_idx))
+    }
+
+    /// Get the field of a json object whose key matches a column name, as for csv headers
+    ///
+    /// # Arguments
+    /// * `entry` - The json object
+    /// * `name` - The column name, e.g. the `code_field_name` option
+    ///
+    /// # Returns
+    /// * `Option<&serde_json::Value>` - The value of the first matching field, if there is one
+    fn json_field<'a>(&self, entry: &'a serde_json::Value, name: &str) -> Option<&'a serde_json::Value> {
+        entry.as_object()?
+            .iter()
+            .find(|(key, _)| self.header_matches(key, name))
+            .map(|(_, value)| value)
+    }
+
+    /// Load a codelist from an xlsx file
+    ///
+    /// The sheet, the header row and the code, term and comment column names are taken from the
+    /// codelist options, and headers are matched as for csv files (see `header_matches`). Rows
+    /// above the header row (such as notes) and fully empty rows are ignored. The comment column
+    /// is optional.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the xlsx file
+    /// 
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    /// 
+    /// # Errors
+    /// * `CodeListError::XLSXError` - If there is an error reading the workbook
+    /// * `CodeListError::InvalidInput` - If the sheet or header row cannot be found
+    /// * `CodeListError::InvalidCodeField` - If the code column is missing or duplicated
+    /// * `CodeListError::InvalidTermField` - If the term column is missing or duplicated
+    /// * `CodeListError::EmptyCode` - If the code value is empty
+    /// * `CodeListError::EmptyTerm` - If the term value is empty
+    pub fn load_codelist_from_xlsx_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_codelist_from_xlsx_reader(file)?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from an xlsx workbook read from any reader
+    ///
+    /// The workbook is read into memory, as the xlsx format needs random access.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the workbook from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * See `load_codelist_from_xlsx_file` for errors in the workbook contents
+    pub fn load_codelist_from_xlsx_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
+        let range = self.xlsx_sheet_range(reader)?;
+        let header_row = self.codelist_options.xlsx_header_row;
+
+        let mut rows = range.rows();
+        let headers: Vec<String> = rows.next()
+            .ok_or_else(|| CodeListError::invalid_input(format!("No header row found at row: {}", header_row + 1)))?
+            .iter()
+            .map(|cell| cell.to_string().trim().to_string())
+            .collect();
+        let mut codelist = CodeList::new(self.codelist_type.clone(), self.metadata.clone(), Some(self.codelist_options.clone()));
+
+        let (code_idx, term_idx) = self.code_and_term_columns(headers.iter().map(String::as_str))?;
+        let comment_idx = headers.iter().position(|h| self.header_matches(h, &self.codelist_options.comment_column_name));
+
+        for (row_num, row) in rows.enumerate() {
+            if row.iter().all(|cell| cell.to_string().trim().is_empty()) {
+                continue;
+            }
+            // Rows are reported as they are numbered in Excel
+            let excel_row = header_row + row_num + 2;
+            let cell_value = |idx: usize| row.get(idx).map(Data::to_string).unwrap_or_default().trim().to_string();
+
+            let code = cell_value(code_idx);
             if code.is_empty() {
-                return Err(CodeListError::empty_code(format!("Empty code field in row: {}", row_num + 2)));
+                return Err(CodeListError::empty_code(format!("Empty code field in row: {}", excel_row)));
             }
-            let term = record.get(term_idx)
-                .ok_or_else(|| CodeListError::column_index_out_of_bounds(
-                    format!("Row {}: Cannot access column at index {}.", row_num + 2, term_idx)
-                ))?
-                .trim();
+            let term = cell_value(term_idx);
             if term.is_empty() {
-                return Err(CodeListError::empty_term(format!("Empty term field in row: {}", row_num + 2)));
+                return Err(CodeListError::empty_term(format!("Empty term field in row: {}", excel_row)));
             }
-            codelist.add_entry(code.to_string(), term.to_string(), None)?;
+            let comment = comment_idx
+                .map(cell_value)
+                .filter(|comment| !comment.is_empty());
+            codelist.add_entry(code, term, comment)?;
         }
-        
+
         Ok(codelist)
     }
 
+    /// Read the configured sheet of an xlsx workbook, starting at the configured header row
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the workbook from
+    ///
+    /// # Returns
+    /// * `Result<Range<Data>, CodeListError>` - The cells of the sheet or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * `CodeListError::XLSXError` - If there is an error reading the workbook
+    /// * `CodeListError::InvalidInput` - If the sheet cannot be found
+    pub(crate) fn xlsx_sheet_range<R: Read>(&self, mut reader: R) -> Result<Range<Data>, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut workbook = Xlsx::new(Cursor::new(bytes))?;
+        workbook.with_header_row(HeaderRow::Row(self.codelist_options.xlsx_header_row as u32));
+
+        match &self.codelist_options.xlsx_sheet {
+            XlsxSheet::Index(index) => Ok(workbook.worksheet_range_at(*index)
+                .ok_or_else(|| CodeListError::invalid_input(format!("No sheet found at index: {}", index)))??),
+            XlsxSheet::Name(name) => {
+                if !workbook.sheet_names().contains(name) {
+                    return Err(CodeListError::invalid_input(format!("No sheet found with the name: {}", name)));
+                }
+                Ok(workbook.worksheet_range(name)?)
+            }
+        }
+    }
+
     /// Load a codelist from a json file
     ///
     /// # Arguments
@@ -120,19 +475,43 @@ impl CodeListFactory {
     /// * `CodeListError::InvalidCodeType` - If the code value is neither a string nor a number, or if a string code contains invalid UTF-8 characters
     /// * `CodeListError::InvalidTermType` - If the term value is not a string, or if a string term contains invalid UTF-8 characters
     /// * `CodeListError::InvalidInput` - If the JSON is not an array of objects
+    /// * `CodeListError::UnsupportedSchemaVersion` - If the file is a saved codelist with an unsupported schema version
     /// 
-    /// * Assumes that the json file is an array of objects with "code" and "term" fields 
+    /// * Assumes that the json file is an array of objects with code and term fields, named by the
+    ///   `code_field_name` and `term_field_name` options and matched as for csv headers, unless it
+    ///   is a saved codelist (see `schema::is_saved_codelist_json`), which is upgraded to the
+    ///   current schema version if it was written by an older version of the library
+    /// * A leading UTF-8 byte order mark is skipped
     pub fn load_codelist_from_json_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_codelist_from_json_reader(BufReader::new(file))?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from json data read from any reader
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the json data from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_json_file`
+    pub fn load_codelist_from_json_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
         let mut codelist = CodeList::new(self.codelist_type.clone(), self.metadata.clone(), Some(self.codelist_options.clone()));
 
-        let file = std::fs::File::open(file_path)?;
-        let reader = std::io::BufReader::new(file);
-        let json_data: serde_json::Value = serde_json::from_reader(reader)?;
+        let json_data: serde_json::Value = serde_json::from_reader(skip_utf8_bom(reader)?)?;
+
+        if is_saved_codelist_json(&json_data) {
+            return CodeList::from_saved_json(json_data);
+        }
 
         if let Some(entries) = json_data.as_array() {
             for (index, entry) in entries.iter().enumerate() {
 
-                let code_value = entry.get("code")
+                let code_value = self.json_field(entry, &self.codelist_options.code_field_name)
                     .ok_or_else(|| CodeListError::invalid_code_field(
                         format!("No {} field found in json file at index: {}", self.codelist_options.code_field_name, index)
                     ))?;
@@ -154,7 +533,7 @@ impl CodeListFactory {
                     return Err(CodeListError::invalid_code_type(format!("Code at index {} must be a string or number", index)));
                 };
 
-                let term_value = entry.get("term")
+                let term_value = self.json_field(entry, &self.codelist_options.term_field_name)
                     .ok_or_else(|| CodeListError::invalid_term_field(
                         format!("No {} field found in json file at index: {}", self.codelist_options.term_field_name, index)
                     ))?;
@@ -181,7 +560,49 @@ impl CodeListFactory {
         Ok(codelist)
     }
 
-    /// Load a codelist from a file
+    /// Load a codelist from a json file written by `CodeList::save_to_json`
+    ///
+    /// The entries, type, metadata, logs and options are all taken from the file, so the factory's
+    /// own settings are not applied.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the json file
+    /// 
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    /// 
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the json file
+    /// * `CodeListError::JSONError` - If there is an error parsing the json file
+    /// * `CodeListError::InvalidInput` - If the JSON is not an object with a valid `schema_version` field
+    /// * `CodeListError::UnsupportedSchemaVersion` - If the schema version is not supported
+    pub fn load_codelist_from_saved_json_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_codelist_from_saved_json_reader(BufReader::new(file))?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist written by `CodeList::save_to_json` from any reader
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the json data from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_saved_json_file`
+    pub fn load_codelist_from_saved_json_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
+        let json_data: serde_json::Value = serde_json::from_reader(skip_utf8_bom(reader)?)?;
+        CodeList::from_saved_json(json_data)
+    }
+
+    /// Load a codelist from a file, choosing the format from the file extension
+    ///
+    /// Files compressed with gzip or zstd are decompressed, such as `asthma.csv.gz`. The format
+    /// comes from the extension before the compression extension, or is detected from the
+    /// decompressed contents when there is none, such as for `asthma.zst`.
     ///
     /// # Arguments
     /// * `file_path` - The path to the file
@@ -190,17 +611,97 @@ impl CodeListFactory {
     /// * `Result<CodeList, CodeListError>` - The codelist or an error
     /// 
     /// # Errors
-    /// * `CodeListError::InvalidFilePath` - If the file path is not a csv or json file
+    /// * `CodeListError::InvalidFilePath` - If the file path does not have the extension of a
+    ///   supported format or of a compression
+    /// * See `load_codelist_from_file_with_format` for other errors
     pub fn load_codelist_from_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
-        match std::path::Path::new(file_path).extension() {
-            Some(ext) if ext == "csv" => self.load_codelist_from_csv_file(file_path),
-            Some(ext) if ext == "json" => self.load_codelist_from_json_file(file_path),
-            _ => Err(CodeListError::invalid_file_path(format!("File path {} is not a csv or json file", file_path))),
+        if !has_codelist_extension(file_path) {
+            let extensions: Vec<&str> = CodeListFormat::ALL.iter().map(CodeListFormat::extension).collect();
+            let (last, rest) = extensions.split_last().expect("there is always a supported format");
+            return Err(CodeListError::invalid_file_path(format!(
+                "File path {} is not a {} or {} file, or one compressed with gzip or zstd",
+                file_path, rest.join(", "), last,
+            )));
+        }
+        let format = CodeListFormat::from_path(strip_compression_extension(file_path));
+        self.load_codelist_from_file_with_format(file_path, format)
+    }
+
+    /// Load a codelist from a file in a given format, regardless of its extension
+    ///
+    /// Files compressed with gzip or zstd, judging by their extension or their magic bytes, are
+    /// decompressed first.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    /// * `format` - The format of the file, or `None` to detect it from the file contents
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error opening the file
+    /// * See the loader of each format for errors in the file contents
+    pub fn load_codelist_from_file_with_format(&self, file_path: &str, format: Option<CodeListFormat>) -> Result<CodeList, CodeListError> {
+        if is_compressed_file(file_path)? {
+            let mut codelist = self.load_codelist_from_reader(std::fs::File::open(file_path)?, format)?;
+            codelist.set_name_from_file_path(strip_compression_extension(file_path));
+            if format == Some(CodeListFormat::Csv) {
+                apply_metadata_sidecar(&mut codelist, file_path)?;
+            }
+            return Ok(codelist);
+        }
+        match format {
+            Some(CodeListFormat::Csv) => self.load_codelist_from_csv_file(file_path),
+            Some(CodeListFormat::Json) => self.load_codelist_from_json_file(file_path),
+            Some(CodeListFormat::Xlsx) => self.load_codelist_from_xlsx_file(file_path),
+            #[cfg(feature = "arrow")]
+            Some(CodeListFormat::ArrowIpc) => self.load_codelist_from_arrow_ipc_file(file_path),
+            #[cfg(feature = "parquet")]
+            Some(CodeListFormat::Parquet) => self.load_codelist_from_parquet_file(file_path),
+            None => {
+                let mut codelist = self.load_codelist_from_reader(std::fs::File::open(file_path)?, None)?;
+                codelist.set_name_from_file_path(file_path);
+                Ok(codelist)
+            }
+        }
+    }
+
+    /// Load a codelist from any reader, such as stdin, an HTTP body or an in-memory buffer
+    ///
+    /// Data compressed with gzip or zstd is recognised by its magic bytes and decompressed.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the codelist data from
+    /// * `format` - The format of the data, or `None` to detect it from the data
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the data
+    /// * See the loader of each format for errors in the data
+    pub fn load_codelist_from_reader<R: Read>(&self, mut reader: R, format: Option<CodeListFormat>) -> Result<CodeList, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let bytes = decompress_if_compressed(bytes)?;
+        match format.unwrap_or_else(|| CodeListFormat::detect(&bytes)) {
+            CodeListFormat::Csv => self.load_codelist_from_csv_reader(bytes.as_slice()),
+            CodeListFormat::Json => self.load_codelist_from_json_reader(bytes.as_slice()),
+            CodeListFormat::Xlsx => self.load_codelist_from_xlsx_reader(bytes.as_slice()),
+            #[cfg(feature = "arrow")]
+            CodeListFormat::ArrowIpc => self.load_codelist_from_arrow_ipc_reader(bytes.as_slice()),
+            #[cfg(feature = "parquet")]
+            CodeListFormat::Parquet => self.load_codelist_from_parquet_reader(bytes.as_slice()),
         }
     }
 
     /// Load codelists from a folder
     ///
+    /// Supported files directly in the folder are loaded, stopping at the first file that fails.
+    /// Use `load_codelists_from_folder_with_options` for recursion, include and exclude patterns,
+    /// best-effort loading and a result for every file.
+    ///
     /// # Arguments
     /// * `folder_path` - The path to the folder
     /// 
@@ -209,26 +710,10 @@ impl CodeListFactory {
     /// 
     /// # Errors
     /// * `CodeListError::IOError` - If there is an error reading the folder
+    /// * `CodeListError::FileLoadFailed` - If a file fails to load
     pub fn load_codelists_from_folder(&self, folder_path: &str) -> Result<Vec<CodeList>, CodeListError> {
-        let dir = std::fs::read_dir(folder_path)?;
-        let mut codelists: Vec<CodeList> = Vec::new();
-
-        for entry in dir {
-            let entry = entry?;
-            let path = entry.path();
-
-            // Skips if not csv/json
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                if ext == "csv" || ext == "json" {
-                    if let Some(path_str) = path.to_str() {
-                        if let Ok(codelist) = self.load_codelist_from_file(path_str) {
-                            codelists.push(codelist);
-                        }
-                    }
-                }
-            }
-        }
-        Ok(codelists)
+        let report = self.load_codelists_from_folder_with_options(folder_path, &FolderLoadOptions::default())?;
+        Ok(report.into_codelists())
     }
 
     /// Load codelists from a folder or a vector of codelists
@@ -251,17 +736,92 @@ impl CodeListFactory {
         }
     }
 
-    /// Process the codelists
+    /// Process the codelists, applying the factory's processing steps to each in order
     /// 
     /// # Arguments
     /// * `codelists` - The vector of codelists
     /// 
     /// # Returns
-    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists or an error
+    /// * `Result<Vec<(CodeList, ProcessingReport)>, CodeListError>` - Each processed codelist with
+    ///   a report of the steps applied to it, or an error
     /// 
-    /// * To be developed in the future
-    pub fn process_codelists(&self, codelists: Vec<CodeList>) {
-        println!("We will process the codelists here.")
+    /// # Errors
+    /// * See `ProcessingStep::apply`
+    pub fn process_codelists(&self, codelists: Vec<CodeList>) -> Result<Vec<(CodeList, ProcessingReport)>, CodeListError> {
+        codelists.into_iter().map(|mut codelist| {
+            let steps = self.processing_steps.iter()
+                .map(|step| step.apply(&mut codelist))
+                .collect::<Result<Vec<_>, _>>()?;
+            let report = ProcessingReport {
+                name: codelist.name.clone(),
+                steps,
+            };
+            Ok((codelist, report))
+        }).collect()
+    }
+
+    /// Get the file names to save a collection of codelists under
+    ///
+    /// Each name is rendered from the `file_name_template` option with `CodeList::render_file_name`.
+    /// Names that clash with an earlier one, ignoring case, get a "-2", "-3", ... suffix so that no
+    /// codelist overwrites another.
+    ///
+    /// # Arguments
+    /// * `codelists` - The codelists to name
+    /// * `extension` - The file extension, without the leading dot
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, CodeListError>` - The file names, in the order of the codelists, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the template has an unknown or unclosed placeholder
+    pub fn codelist_file_names(&self, codelists: &[CodeList], extension: &str) -> Result<Vec<String>, CodeListError> {
+        let mut used = HashSet::new();
+        let mut file_names = Vec::with_capacity(codelists.len());
+        for (index, codelist) in codelists.iter().enumerate() {
+            let stem = codelist.render_file_name(&self.codelist_options.file_name_template, index + 1)?;
+            let mut file_name = format!("{}.{}", stem, extension);
+            let mut suffix = 2;
+            while !used.insert(file_name.to_lowercase()) {
+                file_name = format!("{}-{}.{}", stem, suffix, extension);
+                suffix += 1;
+            }
+            file_names.push(file_name);
+        }
+        Ok(file_names)
+    }
+
+    /// Get the paths to save a collection of codelists to in a folder
+    ///
+    /// The file names come from `codelist_file_names`. Files already in the folder are replaced
+    /// when saving, unless the `overwrite_existing_files` option is off, in which case no paths
+    /// are returned if any of them exists, so that nothing is saved.
+    ///
+    /// # Arguments
+    /// * `folder_path` - The path to the folder
+    /// * `codelists` - The codelists to save
+    /// * `extension` - The file extension, without the leading dot
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, CodeListError>` - The file paths, in the order of the codelists, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidFilePath` - If a path contains invalid unicode characters, or a
+    ///   file already exists and the `overwrite_existing_files` option is off
+    /// * `CodeListError::InvalidInput` - If the file name template is invalid
+    fn codelist_file_paths(&self, folder_path: &str, codelists: &[CodeList], extension: &str) -> Result<Vec<String>, CodeListError> {
+        self.codelist_file_names(codelists, extension)?
+            .into_iter()
+            .map(|file_name| {
+                let full_path = std::path::Path::new(folder_path).join(file_name);
+                if !self.codelist_options.overwrite_existing_files && full_path.exists() {
+                    return Err(CodeListError::invalid_file_path(format!("File {} already exists", full_path.display())));
+                }
+                full_path.to_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))
+            })
+            .collect()
     }
 
     /// Save the codelists to a json file
@@ -274,16 +834,17 @@ impl CodeListFactory {
     /// * `Result<(), CodeListError>` - The result of the operation
     /// 
     /// # Errors
-    /// * `CodeListError::InvalidFilePath` - If the file path contains invalid unicode characters
+    /// * `CodeListError::InvalidFilePath` - If the file path contains invalid unicode characters,
+    ///   or a file already exists and the `overwrite_existing_files` option is off
+    /// * `CodeListError::InvalidInput` - If the file name template is invalid
     /// 
-    /// * Currently saving files as numbers
+    /// * Files are named with `codelist_file_names`, so by default after the codelist id, falling
+    ///   back to the position of the codelist for codelists without one
+    /// * Files already in the folder are replaced, unless the `overwrite_existing_files` option is off
     pub fn save_codelists_to_json(&self, folder_path: &str, codelists: Vec<CodeList>) -> Result<(), CodeListError> {
-        for (index, codelist) in codelists.iter().enumerate() {
-            let filename = format!("{}.json", index + 1);
-            let full_path = std::path::Path::new(folder_path).join(filename);
-            let path_str = full_path.to_str()
-                .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
-            codelist.save_to_json(path_str)?;
+        let file_paths = self.codelist_file_paths(folder_path, &codelists, "json")?;
+        for (codelist, file_path) in codelists.iter().zip(file_paths) {
+            codelist.save_to_json(&file_path)?;
         }
         Ok(())
     }
@@ -298,45 +859,58 @@ impl CodeListFactory {
     /// * `Result<(), CodeListError>` - The result of the operation
     /// 
     /// # Errors
-    /// * `CodeListError::InvalidFilePath` - If the file path contains invalid unicode characters
+    /// * `CodeListError::InvalidFilePath` - If the file path contains invalid unicode characters,
+    ///   or a file already exists and the `overwrite_existing_files` option is off
+    /// * `CodeListError::InvalidInput` - If the file name template is invalid
     /// 
-    /// * Currently saving files as numbers
+    /// * Files are named as in `save_codelists_to_json`, and files already in the folder are
+    ///   replaced unless the `overwrite_existing_files` option is off
+    /// * Each file gets a metadata sidecar file when the `write_metadata_sidecar` option of the
+    ///   factory or of the codelist is on
     pub fn save_codelists_to_csv(&self, folder_path: &str, codelists: Vec<CodeList>) -> Result<(), CodeListError> {
-        for (index, codelist) in codelists.iter().enumerate() {
-            let filename = format!("{}.csv", index + 1);
-            let full_path = std::path::Path::new(folder_path).join(filename);
-            let path_str = full_path.to_str()
-                .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
-            codelist.save_to_csv(path_str)?;
+        let file_paths = self.codelist_file_paths(folder_path, &codelists, "csv")?;
+        for (codelist, file_path) in codelists.iter().zip(file_paths) {
+            if self.codelist_options.write_metadata_sidecar {
+                codelist.save_to_csv_with_metadata(&file_path)?;
+            } else {
+                codelist.save_to_csv(&file_path)?;
+            }
         }
         Ok(())
     }
 
 }
 
+/// Detect the delimiter of a csv file from its header line
+///
+/// The candidates are comma, tab, pipe and semicolon. The candidate that appears most often in the
+/// first non-empty line is chosen, defaulting to a comma.
+///
+/// # Arguments
+/// * `text` - The text of the csv file
+///
+/// # Returns
+/// * `u8` - The detected delimiter
+pub(crate) fn sniff_csv_delimiter(text: &str) -> u8 {
+    let header_line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    [b',', b'\t', b'|', b';']
+        .into_iter()
+        .map(|delimiter| (delimiter, header_line.bytes().filter(|b| *b == delimiter).count()))
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(delimiter, count)| (*count, *delimiter == b','))
+        .map(|(delimiter, _)| delimiter)
+        .unwrap_or(b',')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::compression::Compression;
+    use crate::metadata::MetadataSource;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
     use std::fs;
     use tempfile::tempdir;
 
-    // Helper function to create test metadata
-    fn create_test_metadata() -> Metadata {
-        Metadata {
-            source: MetadataSource::ManuallyCreated,
-            authors: Some(vec!["Caroline Morton".to_string()]),
-            version: Some("2024-12-19".to_string()),
-            description: Some("A test codelist".to_string()),
-        }
-    }
-
-    fn create_test_codelist_factory() -> CodeListFactory {
-        let metadata = create_test_metadata();
-        let codelist_type = CodeListType::ICD10;
-        let codelist_options = CodeListOptions::default();
-        CodeListFactory::new(codelist_options, metadata, codelist_type)
-    }
-
     fn create_test_codelists(factory: &CodeListFactory) -> Result<Vec<CodeList>, CodeListError> {
         let codelist1 = CodeList::new(CodeListType::ICD10, factory.metadata.clone(), Some(factory.codelist_options.clone()));
         let codelist2 = CodeList::new(CodeListType::ICD10, factory.metadata.clone(), Some(factory.codelist_options.clone()));
@@ -351,14 +925,14 @@ mod tests {
         let codelist_options = CodeListOptions::default();
         let codelist_factory = CodeListFactory::new(codelist_options, metadata, codelist_type);
     
-        assert_eq!(codelist_factory.codelist_options.allow_duplicates, false);
-        assert_eq!(codelist_factory.codelist_options.truncate_to_3_digits, false);
-        assert_eq!(codelist_factory.codelist_options.add_x_codes, false);
+        assert!(!codelist_factory.codelist_options.allow_duplicates);
+        assert!(!codelist_factory.codelist_options.truncate_to_3_digits);
+        assert!(!codelist_factory.codelist_options.add_x_codes);
         assert_eq!(codelist_factory.codelist_options.code_column_name, "code".to_string());
         assert_eq!(codelist_factory.codelist_options.term_column_name, "term".to_string());
         assert_eq!(codelist_factory.metadata.source, MetadataSource::ManuallyCreated);
         assert_eq!(codelist_factory.metadata.authors, Some(vec!["Caroline Morton".to_string()]));
-        assert_eq!(codelist_factory.metadata.version, Some("2024-12-19".to_string()));
+        assert_eq!(codelist_factory.metadata.version, Some("2024-12-19".parse().unwrap()));
         assert_eq!(codelist_factory.metadata.description, Some("A test codelist".to_string()));
         assert_eq!(codelist_factory.codelist_type, CodeListType::ICD10);
     }
@@ -378,26 +952,28 @@ B02,Test Disease 2,Description 2
 C03,Test Disease 3,Description 3";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let result = factory.load_codelist_from_csv_file(file_path_str);
         assert!(result.is_ok());
         let codelist = result?;
         assert_eq!(codelist.entries.len(), 3);
+        assert_eq!(codelist.name, Some("test_codelist".to_string()));
+        assert_eq!(codelist.id, Some("test-codelist".to_string()));
         
         // Test individual entries exist
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "A01" && e.term == "Test Disease 1").is_some(), true);
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "B02" && e.term == "Test Disease 2").is_some(), true);
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "C03" && e.term == "Test Disease 3").is_some(), true);
+        assert!(codelist.entries.iter().find(|e| e.code == "A01" && e.term == "Test Disease 1").is_some());
+        assert!(codelist.entries.iter().find(|e| e.code == "B02" && e.term == "Test Disease 2").is_some());
+        assert!(codelist.entries.iter().find(|e| e.code == "C03" && e.term == "Test Disease 3").is_some());
         
-        assert_eq!(codelist.codelist_options.allow_duplicates, false);
-        assert_eq!(codelist.codelist_options.truncate_to_3_digits, false);
-        assert_eq!(codelist.codelist_options.add_x_codes, false);
+        assert!(!codelist.codelist_options.allow_duplicates);
+        assert!(!codelist.codelist_options.truncate_to_3_digits);
+        assert!(!codelist.codelist_options.add_x_codes);
         assert_eq!(codelist.codelist_options.code_column_name, "code".to_string());
         assert_eq!(codelist.codelist_options.term_column_name, "term".to_string());
         assert_eq!(codelist.metadata.source, MetadataSource::ManuallyCreated);
         assert_eq!(codelist.metadata.authors, Some(vec!["Caroline Morton".to_string()]));
-        assert_eq!(codelist.metadata.version, Some("2024-12-19".to_string()));
+        assert_eq!(codelist.metadata.version, Some("2024-12-19".parse()?));
         assert_eq!(codelist.metadata.description, Some("A test codelist".to_string()));
         assert_eq!(codelist.codelist_type, CodeListType::ICD10);
 
@@ -419,7 +995,7 @@ B02,Test Disease 2,Description 2
 C03,Test Disease 3,Description 3";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
 
@@ -443,7 +1019,7 @@ B02,Test Disease 2,Description 2
 C03,Test Disease 3,Description 3";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
 
@@ -466,7 +1042,7 @@ code,term,description
 B02,Test Disease 2,Description 2";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
     assert!(matches!(error, CodeListError::EmptyCode { msg } if msg.contains("Empty code field in row: 2")));
@@ -488,7 +1064,7 @@ A01,,Description 1
 B02,Test Disease 2,Description 2";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
         assert!(matches!(error, CodeListError::EmptyTerm { msg } if msg.contains("Empty term field in row: 2")));
@@ -509,7 +1085,7 @@ code,code,term
 A01,A01,Test Disease 1";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
         assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg.contains("Multiple columns found with the header: code")));
@@ -530,7 +1106,7 @@ code,term,term
 A01,Test Disease 1,Test Disease 1";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
         assert!(matches!(error, CodeListError::InvalidTermField { msg } if msg.contains("Multiple columns found with the header: term")));
@@ -538,6 +1114,170 @@ A01,Test Disease 1,Test Disease 1";
         Ok(())
     }
 
+    #[test]
+    fn test_load_codelist_from_csv_file_sniffs_delimiter() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        for (name, csv_content) in [
+            ("tab.csv", "code\tterm\tdescription\nA01\tTest Disease 1, with comma\tDescription 1\n"),
+            ("pipe.csv", "code|term|description\nA01|Test Disease 1, with comma|Description 1\n"),
+            ("semicolon.csv", "code;term;description\nA01;Test Disease 1, with comma;Description 1\n"),
+        ] {
+            let file_path = temp_dir.path().join(name);
+            fs::write(&file_path, csv_content)?;
+
+            let codelist = factory.load_codelist_from_csv_file(file_path.to_str().unwrap())?;
+            assert_eq!(codelist.entries.len(), 1);
+            assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1, with comma"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_file_configured_delimiter() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.csv");
+        let file_path_str = file_path.to_str().unwrap();
+
+        // The header line alone would be sniffed as comma separated
+        fs::write(&file_path, "code|term|notes,a,b\nA01|Test Disease 1,n|x\n")?;
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.csv_delimiter = Some('|');
+
+        let codelist = factory.load_codelist_from_csv_file(file_path_str)?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1,n"));
+
+        factory.codelist_options.csv_delimiter = Some('£');
+        let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "CSV delimiter must be an ASCII character, found: £"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_file_strips_utf8_bom() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.csv");
+
+        let mut csv_content = vec![0xEF, 0xBB, 0xBF];
+        csv_content.extend_from_slice("code,term\nA01,Test Disease 1\n".as_bytes());
+        fs::write(&file_path, csv_content)?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let codelist = factory.load_codelist_from_csv_file(file_path.to_str().unwrap())?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_json_file_with_utf8_bom_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A01".to_string(), "Test Disease 1".to_string(), None)?;
+
+        let saved_path = temp_dir.path().join("saved.json");
+        codelist.save_to_json(saved_path.to_str().unwrap())?;
+        let mut saved_content = b"\xEF\xBB\xBF".to_vec();
+        saved_content.extend(fs::read(&saved_path)?);
+        fs::write(&saved_path, &saved_content)?;
+
+        let entries_path = temp_dir.path().join("entries");
+        let mut entries_content = b"\xEF\xBB\xBF".to_vec();
+        entries_content.extend_from_slice(br#"[{"code": "A01", "term": "Test Disease 1"}]"#);
+        fs::write(&entries_path, &entries_content)?;
+
+        let loaded_codelist = factory.load_codelist_from_file(saved_path.to_str().unwrap())?;
+        assert_eq!(loaded_codelist.entries, codelist.entries);
+        assert_eq!(factory.load_codelist_from_saved_json_reader(saved_content.as_slice())?.entries, codelist.entries);
+
+        // Without an extension the format is detected from the contents
+        let loaded_codelist = factory.load_codelist_from_file_with_format(entries_path.to_str().unwrap(), None)?;
+        assert_eq!(loaded_codelist.entries, codelist.entries);
+        let inference = factory.infer_codelist_type_from_file(saved_path.to_str().unwrap())?;
+        assert_eq!(inference.codelist_type, CodeListType::ICD10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_file_utf16_bom() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.csv");
+
+        let mut csv_content = vec![0xFF, 0xFE];
+        csv_content.extend("code\tterm\nA01\tMénière's disease\n".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        fs::write(&file_path, csv_content)?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let codelist = factory.load_codelist_from_csv_file(file_path.to_str().unwrap())?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Ménière's disease"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_file_windows_1252() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.csv");
+        let file_path_str = file_path.to_str().unwrap();
+
+        // 0xE8 is "è" in Windows-1252 and is not valid UTF-8 on its own
+        let mut csv_content = b"code,term\nA01,M\xE9ni\xE8re's disease\n".to_vec();
+        csv_content.extend_from_slice(b"B02,Test Disease 2\n");
+        fs::write(&file_path, csv_content)?;
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let codelist = factory.load_codelist_from_csv_file(file_path_str)?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Ménière's disease"));
+
+        factory.codelist_options.csv_encoding = Some("utf-8".to_string());
+        let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "CSV file is not valid UTF-8"));
+
+        factory.codelist_options.csv_encoding = Some("not-an-encoding".to_string());
+        let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Unknown CSV encoding: not-an-encoding"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_file_header_matching() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.csv");
+        let file_path_str = file_path.to_str().unwrap();
+
+        fs::write(&file_path, " Code , TERM \nA01,Test Disease 1\n")?;
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let codelist = factory.load_codelist_from_csv_file(file_path_str)?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1"));
+
+        factory.codelist_options.case_insensitive_headers = false;
+        let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg == "Column not found with the header: code"));
+
+        factory.codelist_options.case_insensitive_headers = true;
+        factory.codelist_options.trim_headers = false;
+        let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg == "Column not found with the header: code"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sniff_csv_delimiter() {
+        assert_eq!(sniff_csv_delimiter("code,term\n"), b',');
+        assert_eq!(sniff_csv_delimiter("\ncode\tterm\tdescription\n"), b'\t');
+        assert_eq!(sniff_csv_delimiter("code|term\n"), b'|');
+        assert_eq!(sniff_csv_delimiter("code;term\n"), b';');
+        assert_eq!(sniff_csv_delimiter("code\n"), b',');
+        assert_eq!(sniff_csv_delimiter(""), b',');
+    }
+
     #[test]
     fn test_load_codelist_from_csv_file_unequal_columns() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
@@ -551,7 +1291,7 @@ code,term,description
 A01";  // Missing columns
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let error = factory.load_codelist_from_csv_file(file_path_str).unwrap_err();
         assert!(matches!(error, CodeListError::CSVError(_)));
@@ -559,6 +1299,168 @@ A01";  // Missing columns
         Ok(())
     }
     
+    // Helper function to write a workbook with a notes sheet followed by a codelist sheet with two notes rows
+    fn write_test_xlsx_file(file_path: &std::path::Path) -> Result<(), CodeListError> {
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let notes_sheet = workbook.add_worksheet();
+        notes_sheet.set_name("Notes")?;
+        notes_sheet.write_string(0, 0, "Reviewed by the clinical team")?;
+
+        let codelist_sheet = workbook.add_worksheet();
+        codelist_sheet.set_name("Codelist")?;
+        codelist_sheet.write_string(0, 0, "Asthma codelist")?;
+        codelist_sheet.write_string(1, 0, "Version 2")?;
+        codelist_sheet.write_row(2, 0, ["code", "term", "comment"])?;
+        codelist_sheet.write_row(3, 0, ["A01", "Test Disease 1", "Included after review"])?;
+        codelist_sheet.write_row(4, 0, ["B02", "Test Disease 2"])?;
+        codelist_sheet.write_number(5, 0, 22298006)?;
+        codelist_sheet.write_string(5, 1, "Test Disease 3")?;
+        workbook.save(file_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_by_sheet_name() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+        write_test_xlsx_file(&file_path)?;
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Name("Codelist".to_string());
+        factory.codelist_options.xlsx_header_row = 2;
+
+        let codelist = factory.load_codelist_from_xlsx_file(file_path_str)?;
+        assert_eq!(codelist.entries.len(), 3);
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1" && e.comment == Some("Included after review".to_string())));
+        assert!(codelist.entries.iter().any(|e| e.code == "B02" && e.term == "Test Disease 2" && e.comment.is_none()));
+        assert!(codelist.entries.iter().any(|e| e.code == "22298006" && e.term == "Test Disease 3"));
+        assert_eq!(codelist.codelist_type, CodeListType::ICD10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_by_sheet_index() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+        write_test_xlsx_file(&file_path)?;
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Index(1);
+        factory.codelist_options.xlsx_header_row = 2;
+
+        let codelist = factory.load_codelist_from_file(file_path_str)?;
+        assert_eq!(codelist.entries.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_custom_columns() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write_row(0, 0, ["Notes", "ICD10 Code", "Description"])?;
+        sheet.write_row(1, 0, ["", "A01", "Test Disease 1"])?;
+        workbook.save(&file_path)?;
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.code_field_name = " icd10 code ".to_string();
+        factory.codelist_options.term_field_name = "DESCRIPTION".to_string();
+
+        let codelist = factory.load_codelist_from_xlsx_file(file_path_str)?;
+        assert_eq!(codelist.entries.len(), 1);
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_missing_sheet() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+        write_test_xlsx_file(&file_path)?;
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Name("Missing".to_string());
+        let error = factory.load_codelist_from_xlsx_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "No sheet found with the name: Missing"));
+
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Index(5);
+        let error = factory.load_codelist_from_xlsx_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "No sheet found at index: 5"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_invalid_code_column_name() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+        write_test_xlsx_file(&file_path)?;
+
+        // Without the header row offset the notes row is read as the header
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Name("Codelist".to_string());
+        let error = factory.load_codelist_from_xlsx_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg == "Column not found with the header: code"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_xlsx_file_empty_term() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write_row(0, 0, ["code", "term"])?;
+        sheet.write_row(1, 0, ["A01", "Test Disease 1"])?;
+        sheet.write_row(2, 0, ["B02", " "])?;
+        workbook.save(&file_path)?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let error = factory.load_codelist_from_xlsx_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::EmptyTerm { msg } if msg == "Empty term field in row: 3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_xlsx_and_load_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_codelist.xlsx");
+        let file_path_str = file_path.to_str()
+            .ok_or_else(|| CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        let mut original_codelist = CodeList::new(CodeListType::ICD10, factory.metadata.clone(), Some(factory.codelist_options.clone()));
+        original_codelist.add_entry("A01".to_string(), "Test Disease 1".to_string(), Some("test comment".to_string()))?;
+        original_codelist.add_entry("B02".to_string(), "Test Disease 2".to_string(), None)?;
+        original_codelist.save_to_xlsx(file_path_str)?;
+
+        factory.codelist_options.xlsx_sheet = XlsxSheet::Name("Entries".to_string());
+        let loaded_codelist = factory.load_codelist_from_xlsx_file(file_path_str)?;
+        assert_eq!(loaded_codelist.entries, original_codelist.entries);
+
+        Ok(())
+    }
+
     #[test]
     fn test_load_codelist_from_json_file() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
@@ -574,7 +1476,7 @@ A01";  // Missing columns
         ]"#;
 
         fs::write(&file_path, json_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let result = factory.load_codelist_from_json_file(file_path_str);
         assert!(result.is_ok());
@@ -582,18 +1484,18 @@ A01";  // Missing columns
         assert_eq!(codelist.entries.len(), 3);
         
         // Test individual entries exist
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "A01" && e.term == "Test Disease 1").is_some(), true);
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "B02" && e.term == "Test Disease 2").is_some(), true);
-        assert_eq!(codelist.entries.iter().find(|e| e.code == "C03" && e.term == "Test Disease 3").is_some(), true);
+        assert!(codelist.entries.iter().find(|e| e.code == "A01" && e.term == "Test Disease 1").is_some());
+        assert!(codelist.entries.iter().find(|e| e.code == "B02" && e.term == "Test Disease 2").is_some());
+        assert!(codelist.entries.iter().find(|e| e.code == "C03" && e.term == "Test Disease 3").is_some());
         
-        assert_eq!(codelist.codelist_options.allow_duplicates, false);
-        assert_eq!(codelist.codelist_options.truncate_to_3_digits, false);
-        assert_eq!(codelist.codelist_options.add_x_codes, false);
+        assert!(!codelist.codelist_options.allow_duplicates);
+        assert!(!codelist.codelist_options.truncate_to_3_digits);
+        assert!(!codelist.codelist_options.add_x_codes);
         assert_eq!(codelist.codelist_options.code_column_name, "code".to_string());
         assert_eq!(codelist.codelist_options.term_column_name, "term".to_string());
         assert_eq!(codelist.metadata.source, MetadataSource::ManuallyCreated);
         assert_eq!(codelist.metadata.authors, Some(vec!["Caroline Morton".to_string()]));
-        assert_eq!(codelist.metadata.version, Some("2024-12-19".to_string()));
+        assert_eq!(codelist.metadata.version, Some("2024-12-19".parse()?));
         assert_eq!(codelist.metadata.description, Some("A test codelist".to_string()));
         assert_eq!(codelist.codelist_type, CodeListType::ICD10);
 
@@ -603,7 +1505,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_invalid_code_field() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("missing_code.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -621,7 +1523,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_invalid_term_field() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("missing_term.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -634,12 +1536,35 @@ A01";  // Missing columns
         assert!(matches!(error, CodeListError::InvalidTermField { msg } if msg.contains(format!("No {} field found in json file at index: 0", factory.codelist_options.term_field_name).as_str())));
 
         Ok(())
-    }    
+    }
+
+    #[test]
+    fn test_load_codelist_from_json_file_custom_field_names() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.code_field_name = "icd10_code".to_string();
+        factory.codelist_options.term_field_name = "description".to_string();
+
+        let file_path = temp_dir.path().join("custom_fields.json");
+        let json_content = r#"[
+            {"ICD10_Code": "A01", "Description": "Test Disease 1"}
+        ]"#;
+        fs::write(&file_path, json_content)?;
+
+        let codelist = factory.load_codelist_from_json_file(file_path.to_str().unwrap())?;
+        assert!(codelist.entries.iter().any(|e| e.code == "A01" && e.term == "Test Disease 1"));
+
+        factory.codelist_options.case_insensitive_headers = false;
+        let error = factory.load_codelist_from_json_file(file_path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg == "No icd10_code field found in json file at index: 0"));
+
+        Ok(())
+    }
 
     #[test]
     fn test_load_codelist_from_json_file_empty_code() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("empty_code.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -659,7 +1584,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_empty_term() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("empty_term.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -679,7 +1604,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_invalid_code_type() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("invalid_code_type.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -699,7 +1624,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_invalid_term_type() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("invalid_term_type.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -719,7 +1644,7 @@ A01";  // Missing columns
     #[test]
     fn test_load_codelist_from_json_file_invalid_input() -> Result<(), CodeListError> {
         let temp_dir = tempdir()?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
 
         let file_path = temp_dir.path().join("invalid_input.json");
         let file_path_str = file_path.to_str().unwrap();
@@ -733,11 +1658,51 @@ A01";  // Missing columns
         Ok(())
     }
 
+    #[test]
+    fn test_load_codelist_from_saved_json_file() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let file_path = temp_dir.path().join("saved_codelist.json");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let mut original_codelist = CodeList::new(CodeListType::SNOMED, factory.metadata.clone(), None);
+        original_codelist.set_name("Myocardial Infarction");
+        original_codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), Some("test comment".to_string()))?;
+        original_codelist.add_log("Test log message".to_string());
+        original_codelist.save_to_json(file_path_str)?;
+
+        let loaded_codelist = factory.load_codelist_from_saved_json_file(file_path_str)?;
+        assert_eq!(loaded_codelist, original_codelist);
+
+        // The generic loaders recognise the saved format too
+        let loaded_codelist = factory.load_codelist_from_file(file_path_str)?;
+        assert_eq!(loaded_codelist, original_codelist);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_saved_json_file_unsupported_schema_version() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let file_path = temp_dir.path().join("saved_codelist.json");
+        let file_path_str = file_path.to_str().unwrap();
+        let json_content = r#"{"schema_version": 99, "entries": []}"#;
+        fs::write(&file_path, json_content)?;
+
+        let error = factory.load_codelist_from_saved_json_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::UnsupportedSchemaVersion { version, supported } if version == "99" && supported == "5"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_load_codelist_from_file_invalid_file_path() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let error = factory.load_codelist_from_file("invalid_file_path").unwrap_err();
-        assert!(matches!(error, CodeListError::InvalidFilePath { msg } if msg.contains("File path invalid_file_path is not a csv or json file")));
+        assert!(matches!(error, CodeListError::InvalidFilePath { msg } if msg.starts_with("File path invalid_file_path is not a csv, json") && msg.ends_with(" file, or one compressed with gzip or zstd")));
         Ok(())
     }
 
@@ -755,7 +1720,7 @@ A01,Test Disease 1,Description 1
 B02,Test Disease 2,Description 2";
 
         fs::write(&file_path, csv_content)?;
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         
         let result = factory.load_codelist_from_file(file_path_str);
         assert!(result.is_ok());
@@ -767,7 +1732,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_load_codelists_from_folder() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let temp_dir = tempdir()?;
         let temp_dir_path = temp_dir.path();
         let temp_dir_str = temp_dir_path.to_str()
@@ -797,7 +1762,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_load_codelists_with_codelists() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let codelists = create_test_codelists(&factory)?;
         assert_eq!(codelists.len(), 2);
         Ok(())
@@ -805,7 +1770,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_load_codelists_with_folder() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let temp_dir = tempdir()?;
         let temp_dir_path = temp_dir.path();
         
@@ -836,7 +1801,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_load_codelists_no_input() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let error = factory.load_codelists(None, None).unwrap_err();
         assert!(matches!(error, CodeListError::InvalidInput { msg } if msg.contains("Codelist vector or path must be provided")));
         Ok(())
@@ -845,7 +1810,7 @@ B02,Test Disease 2,Description 2";
     #[test]
     fn test_load_codelists_both_input() -> Result<(), CodeListError> {
         // Create test folder
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let temp_dir = tempdir()?;
         let temp_dir_path = temp_dir.path();
         
@@ -882,7 +1847,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_save_codelists_to_csv() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let codelists = create_test_codelists(&factory)?;
         let temp_dir = tempdir()?;
         let temp_dir_path = temp_dir.path();
@@ -899,7 +1864,7 @@ B02,Test Disease 2,Description 2";
 
     #[test]
     fn test_save_codelists_to_json() -> Result<(), CodeListError> {
-        let factory = create_test_codelist_factory();
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
         let codelists = create_test_codelists(&factory)?;
         let temp_dir = tempdir()?;
         let temp_dir_path = temp_dir.path();
@@ -913,4 +1878,209 @@ B02,Test Disease 2,Description 2";
         assert!(json_path2.exists());
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_process_codelists() -> Result<(), CodeListError> {
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.processing_steps.push(ProcessingStep::Truncate);
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.set_name("Typhoid");
+        codelist.add_entry("a01.1".to_string(), "Typhoid meningitis".to_string(), None)?;
+        codelist.add_entry("A01".to_string(), "Typhoid fever".to_string(), None)?;
+
+        let processed = factory.process_codelists(vec![codelist])?;
+        assert_eq!(processed.len(), 1);
+        let (codelist, report) = &processed[0];
+        assert_eq!(report.name, Some("Typhoid".to_string()));
+        assert_eq!(report.steps.iter().map(|step| step.step.as_str()).collect::<Vec<_>>(), vec!["normalise", "deduplicate", "truncate"]);
+        assert_eq!(report.change_count(), 2);
+        assert!(!report.has_issues());
+        assert_eq!(codelist.entries.len(), 2);
+        assert!(codelist.codes().contains(&"A01".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_codelists_uses_names() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let temp_dir = tempdir()?;
+        let temp_dir_str = temp_dir.path().to_str()
+            .ok_or(CodeListError::invalid_file_path("Path contains invalid Unicode characters"))?;
+
+        let asthma_path = temp_dir.path().join("asthma.csv");
+        fs::write(&asthma_path, "code,term\nJ45,Asthma\n")?;
+        let loaded_codelist = factory.load_codelist_from_csv_file(asthma_path.to_str().unwrap())?;
+        let mut renamed_codelist = loaded_codelist.clone();
+        renamed_codelist.set_name("ASTHMA");
+        let unnamed_codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+
+        let output_dir = temp_dir.path().join("output");
+        fs::create_dir(&output_dir)?;
+        let output_dir_str = output_dir.to_str().unwrap();
+        factory.save_codelists_to_json(output_dir_str, vec![loaded_codelist, renamed_codelist, unnamed_codelist])?;
+        assert!(output_dir.join("asthma.json").exists());
+        assert!(output_dir.join("asthma-2.json").exists());
+        assert!(output_dir.join("3.json").exists());
+
+        let reloaded_codelist = factory.load_codelist_from_saved_json_file(output_dir.join("asthma-2.json").to_str().unwrap())?;
+        assert_eq!(reloaded_codelist.name, Some("ASTHMA".to_string()));
+        assert_eq!(reloaded_codelist.id, Some("asthma".to_string()));
+
+        // Existing files are replaced unless overwriting is turned off, in which case nothing is saved
+        fs::write(output_dir.join("asthma-2.json"), "[]")?;
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.overwrite_existing_files = false;
+        let codelists = vec![reloaded_codelist.clone(), reloaded_codelist.clone(), reloaded_codelist];
+        let error = factory.save_codelists_to_json(output_dir_str, codelists.clone()).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidFilePath { msg } if msg.ends_with("asthma.json already exists")));
+        assert_eq!(fs::read_to_string(output_dir.join("asthma-2.json"))?, "[]");
+        factory.codelist_options.overwrite_existing_files = true;
+        factory.save_codelists_to_json(output_dir_str, codelists)?;
+        assert!(factory.load_codelist_from_saved_json_file(output_dir.join("asthma-2.json").to_str().unwrap()).is_ok());
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.file_name_template = "{type}-{bad}".to_string();
+        let error = factory.save_codelists_to_csv(temp_dir_str, create_test_codelists(&factory)?).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Unknown placeholder in file name template: {bad}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_reader_detects_format() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let csv_data = "code,term\nA01,Test Disease 1\n";
+        let codelist = factory.load_codelist_from_reader(csv_data.as_bytes(), None)?;
+        assert_eq!(codelist.entries.len(), 1);
+
+        let json_data = r#"[{"code": "A01", "term": "Test Disease 1"}, {"code": "B02", "term": "Test Disease 2"}]"#;
+        let codelist = factory.load_codelist_from_reader(json_data.as_bytes(), None)?;
+        assert_eq!(codelist.entries.len(), 2);
+
+        let mut xlsx_data = Vec::new();
+        codelist.save_to_xlsx_writer(&mut xlsx_data)?;
+        let loaded_codelist = factory.load_codelist_from_reader(xlsx_data.as_slice(), None)?;
+        assert_eq!(loaded_codelist.entries, codelist.entries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_reader_with_format() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        // Json-looking data is still read as csv when the format is given
+        let error = factory.load_codelist_from_reader("[code,term]\n".as_bytes(), Some(CodeListFormat::Csv)).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeField { msg } if msg == "Column not found with the header: code"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_file_with_format() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("codelist.txt");
+        fs::write(&file_path, "code\tterm\nA01\tTest Disease 1\n")?;
+        let file_path_str = file_path.to_str().unwrap();
+
+        let codelist = factory.load_codelist_from_file_with_format(file_path_str, Some(CodeListFormat::Csv))?;
+        assert_eq!(codelist.entries.len(), 1);
+        let codelist = factory.load_codelist_from_file_with_format(file_path_str, None)?;
+        assert_eq!(codelist.entries.len(), 1);
+        assert!(factory.load_codelist_from_file(file_path_str).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_and_save_compressed_files() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let temp_dir = tempdir()?;
+        let mut original = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        original.set_name("Compressed");
+        original.add_entry("A01".to_string(), "Test Disease 1".to_string(), None)?;
+        original.add_entry("B02".to_string(), "Test Disease 2".to_string(), None)?;
+
+        for file_name in ["compressed.json.gz", "compressed.json.zst", "compressed.zst"] {
+            let file_path = temp_dir.path().join(file_name);
+            let file_path_str = file_path.to_str().unwrap();
+            original.save_to_file_with_format(file_path_str, CodeListFormat::Json)?;
+            assert!(Compression::detect(&fs::read(&file_path)?).is_some());
+
+            let codelist = factory.load_codelist_from_file(file_path_str)?;
+            assert_eq!(codelist.entries, original.entries);
+            assert_eq!(codelist.id, Some("compressed".to_string()));
+        }
+
+        let csv_path = temp_dir.path().join("asthma.csv.gz");
+        original.save_to_csv(csv_path.to_str().unwrap())?;
+        let codelist = factory.load_codelist_from_file(csv_path.to_str().unwrap())?;
+        assert_eq!(codelist.entries, original.entries);
+        assert_eq!(codelist.id, Some("asthma".to_string()));
+
+        // Compression is detected from the magic bytes when the extension does not show it
+        let disguised_path = temp_dir.path().join("disguised.csv");
+        fs::copy(&csv_path, &disguised_path)?;
+        let codelist = factory.load_codelist_from_file(disguised_path.to_str().unwrap())?;
+        assert_eq!(codelist.entries, original.entries);
+        let codelist = factory.load_codelist_from_reader(fs::File::open(&csv_path)?, None)?;
+        assert_eq!(codelist.entries, original.entries);
+
+        let report = factory.load_codelists_from_folder_with_options(temp_dir.path().to_str().unwrap(), &FolderLoadOptions::default())?;
+        // The folder has the same files load_codelist_from_file accepts, including compressed.zst
+        assert_eq!(report.codelists().len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_reader_with_report_lenient() -> Result<(), CodeListError> {
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.lenient_loading = true;
+        let csv_data = "code,term\nA01,Test Disease 1\n  ,Test Disease 2\nC03,\nD04\nE05,Test Disease 5\n";
+
+        let (codelist, report) = factory.load_codelist_from_csv_reader_with_report(csv_data.as_bytes())?;
+
+        assert_eq!(codelist.entries.len(), 2);
+        assert_eq!(report.rows_read, 5);
+        assert_eq!(report.rows_loaded, 2);
+        assert_eq!(report.issues, vec![
+            LoadIssue { row: 3, column: Some("code".to_string()), kind: LoadIssueKind::EmptyCode, raw_value: "  ".to_string(), message: "Empty code field in row: 3".to_string() },
+            LoadIssue { row: 4, column: Some("term".to_string()), kind: LoadIssueKind::EmptyTerm, raw_value: String::new(), message: "Empty term field in row: 4".to_string() },
+            LoadIssue { row: 5, column: Some("term".to_string()), kind: LoadIssueKind::MissingValue, raw_value: "D04".to_string(), message: "Row 5: Cannot access column at index 1.".to_string() },
+        ]);
+        assert_eq!(codelist.logs, vec!["Skipped 3 of 5 rows with problems while loading".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_reader_with_report_lenient_reports_both_columns() -> Result<(), CodeListError> {
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.lenient_loading = true;
+
+        let (codelist, report) = factory.load_codelist_from_csv_reader_with_report(" code,term\n , \n".as_bytes())?;
+
+        assert!(codelist.entries.is_empty());
+        assert_eq!(report.rows_skipped(), 1);
+        assert_eq!(report.issues.iter().map(|issue| issue.kind).collect::<Vec<_>>(), vec![LoadIssueKind::EmptyCode, LoadIssueKind::EmptyTerm]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_csv_reader_with_report_strict() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let (codelist, report) = factory.load_codelist_from_csv_reader_with_report("code,term\nA01,Test Disease 1\n".as_bytes())?;
+        assert_eq!(codelist.entries.len(), 1);
+        assert!(report.is_clean());
+        assert!(codelist.logs.is_empty());
+
+        let error = factory.load_codelist_from_csv_reader_with_report("code,term\nA01,Test Disease 1\n,Test Disease 2\nC03,\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, CodeListError::EmptyCode { msg } if msg == "Empty code field in row: 3"));
+
+        let error = factory.load_codelist_from_csv_reader_with_report("code,term\nA01\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, CodeListError::CSVError(_)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! This file contains the file formats a codelist can be loaded from and saved to
+
+// External imports
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+// Internal imports
+use crate::errors::CodeListError;
+
+/// The UTF-8 byte order mark that some editors write at the start of text files
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Remove a leading UTF-8 byte order mark from file contents
+///
+/// # Arguments
+/// * `bytes` - The contents of the file
+///
+/// # Returns
+/// * `&[u8]` - The contents without the byte order mark
+pub(crate) fn strip_utf8_bom(bytes: &[u8]) -> &[u8] {
+    bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)
+}
+
+/// Wrap a reader so that a leading UTF-8 byte order mark is skipped
+///
+/// # Arguments
+/// * `reader` - The reader to wrap
+///
+/// # Returns
+/// * `std::io::Result<BufReader<R>>` - The buffered reader, positioned after any byte order mark
+pub(crate) fn skip_utf8_bom<R: Read>(reader: R) -> std::io::Result<BufReader<R>> {
+    let mut reader = BufReader::new(reader);
+    if reader.fill_buf()?.starts_with(UTF8_BOM) {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(reader)
+}
+
+/// Enum to represent the file formats a codelist can be loaded from and saved to
+///
+/// # Variants
+/// * `Csv` - A csv file with code and term columns
+/// * `Json` - A json array of code and term objects, or a codelist saved with `save_to_json`
+/// * `Xlsx` - An xlsx workbook
+/// * `ArrowIpc` - An Arrow IPC file (requires the `arrow` feature)
+/// * `Parquet` - A Parquet file (requires the `parquet` feature)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeListFormat {
+    Csv,
+    Json,
+    Xlsx,
+    #[cfg(feature = "arrow")]
+    ArrowIpc,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl CodeListFormat {
+    /// The formats supported with the enabled features
+    pub const ALL: &'static [CodeListFormat] = &[
+        CodeListFormat::Csv,
+        CodeListFormat::Json,
+        CodeListFormat::Xlsx,
+        #[cfg(feature = "arrow")]
+        CodeListFormat::ArrowIpc,
+        #[cfg(feature = "parquet")]
+        CodeListFormat::Parquet,
+    ];
+
+    /// Get the format of a file extension
+    ///
+    /// # Arguments
+    /// * `ext` - The file extension, without the leading dot, in any case
+    ///
+    /// # Returns
+    /// * `Option<CodeListFormat>` - The format, if the extension is supported
+    pub fn from_extension(ext: &str) -> Option<CodeListFormat> {
+        match ext.to_lowercase().as_str() {
+            "csv" => Some(CodeListFormat::Csv),
+            "json" => Some(CodeListFormat::Json),
+            "xlsx" => Some(CodeListFormat::Xlsx),
+            #[cfg(feature = "arrow")]
+            "arrow" => Some(CodeListFormat::ArrowIpc),
+            #[cfg(feature = "parquet")]
+            "parquet" => Some(CodeListFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    /// Get the format of a file path from its extension
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Option<CodeListFormat>` - The format, if the path has a supported extension
+    pub fn from_path(file_path: &str) -> Option<CodeListFormat> {
+        Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(CodeListFormat::from_extension)
+    }
+
+    /// Detect the format of file contents
+    ///
+    /// Xlsx, Arrow IPC and Parquet files are recognised by their leading magic bytes and json by
+    /// its first non-whitespace character. Anything else is treated as csv.
+    ///
+    /// # Arguments
+    /// * `bytes` - The contents of the file, or at least its first few bytes
+    ///
+    /// # Returns
+    /// * `CodeListFormat` - The detected format
+    pub fn detect(bytes: &[u8]) -> CodeListFormat {
+        if bytes.starts_with(b"PK\x03\x04") {
+            return CodeListFormat::Xlsx;
+        }
+        #[cfg(feature = "arrow")]
+        if bytes.starts_with(b"ARROW1") {
+            return CodeListFormat::ArrowIpc;
+        }
+        #[cfg(feature = "parquet")]
+        if bytes.starts_with(b"PAR1") {
+            return CodeListFormat::Parquet;
+        }
+        match strip_utf8_bom(bytes).iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') | Some(b'[') => CodeListFormat::Json,
+            _ => CodeListFormat::Csv,
+        }
+    }
+
+    /// Get the usual file extension of the format
+    ///
+    /// # Returns
+    /// * `&'static str` - The file extension, without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CodeListFormat::Csv => "csv",
+            CodeListFormat::Json => "json",
+            CodeListFormat::Xlsx => "xlsx",
+            #[cfg(feature = "arrow")]
+            CodeListFormat::ArrowIpc => "arrow",
+            #[cfg(feature = "parquet")]
+            CodeListFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl fmt::Display for CodeListFormat {
+    /// Format the format as its file extension
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for CodeListFormat {
+    type Err = CodeListError;
+
+    /// Parse a format from its name or file extension
+    ///
+    /// # Arguments
+    /// * `s` - The name of the format, e.g. "csv"
+    ///
+    /// # Returns
+    /// * `Result<CodeListFormat, CodeListError>` - The format or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the format is not supported
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CodeListFormat::from_extension(s.trim())
+            .ok_or_else(|| CodeListError::invalid_input(format!("Unknown codelist format: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_extension_and_path() {
+        assert_eq!(CodeListFormat::from_extension("csv"), Some(CodeListFormat::Csv));
+        assert_eq!(CodeListFormat::from_extension("JSON"), Some(CodeListFormat::Json));
+        assert_eq!(CodeListFormat::from_extension("txt"), None);
+        assert_eq!(CodeListFormat::from_path("folder/codelist.xlsx"), Some(CodeListFormat::Xlsx));
+        assert_eq!(CodeListFormat::from_path("folder/codelist"), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(CodeListFormat::detect(b"PK\x03\x04rest of zip"), CodeListFormat::Xlsx);
+        assert_eq!(CodeListFormat::detect(b"  \n[{\"code\": \"A01\"}]"), CodeListFormat::Json);
+        assert_eq!(CodeListFormat::detect(b"\xEF\xBB\xBF{\"schema_version\": 1}"), CodeListFormat::Json);
+        assert_eq!(CodeListFormat::detect(b"code,term\nA01,Typhoid\n"), CodeListFormat::Csv);
+        assert_eq!(CodeListFormat::detect(b""), CodeListFormat::Csv);
+    }
+
+    #[test]
+    fn test_from_str_and_display() -> Result<(), CodeListError> {
+        assert_eq!(CodeListFormat::from_str("xlsx")?, CodeListFormat::Xlsx);
+        assert_eq!(CodeListFormat::Csv.to_string(), "csv");
+        let error = CodeListFormat::from_str("docx").unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Unknown codelist format: docx"));
+        Ok(())
+    }
+}
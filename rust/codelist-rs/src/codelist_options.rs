@@ -2,6 +2,17 @@
 
 use serde::{Serialize, Deserialize};
 
+/// Enum to represent which sheet of an xlsx workbook a codelist is read from
+///
+/// # Variants
+/// * `Index` - The zero-based position of the sheet in the workbook
+/// * `Name` - The name of the sheet
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum XlsxSheet {
+    Index(usize),
+    Name(String),
+}
+
 /// Struct to represent a codelist options
 ///
 /// # Fields
@@ -10,15 +21,42 @@ use serde::{Serialize, Deserialize};
 /// * `add_x_codes` - Whether to add x codes to the codelist
 /// * `code_column_name` - The name of the code column
 /// * `term_column_name` - The name of the term column
+/// * `code_field_name` - The name of the code field
+/// * `term_field_name` - The name of the term field
+/// * `comment_column_name` - The name of the optional comment column
+/// * `xlsx_sheet` - The sheet of an xlsx workbook to read the codelist from
+/// * `xlsx_header_row` - The zero-based row of the xlsx sheet that holds the column headers
+/// * `csv_delimiter` - The delimiter of csv files, or `None` to detect it from the header line
+/// * `csv_encoding` - The encoding label of csv files (e.g. "windows-1252"), or `None` to detect it
+/// * `trim_headers` - Whether to ignore whitespace around column headers when matching them
+/// * `case_insensitive_headers` - Whether to ignore case when matching column headers
+/// * `lenient_loading` - Whether to skip invalid rows when loading, rather than failing on the first
+/// * `file_name_template` - The template for file names when saving a collection of codelists, e.g. "{type}_{id}"
+/// * `type_column_name` - The name of an optional column giving the coding system of each row, used when inferring the codelist type
+/// * `overwrite_existing_files` - Whether saving a collection of codelists to a folder may replace files already in it
+/// * `write_metadata_sidecar` - Whether saving to csv also writes the metadata to a sidecar file next to the csv (off by default)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(default)]
 pub struct CodeListOptions {
     pub allow_duplicates: bool,
     pub truncate_to_3_digits: bool,  // ICD10 specific only
     pub add_x_codes: bool,
     pub code_column_name: String, // for csv files
-    pub term_column_name: String, // for csv files          
-    pub code_field_name: String, // for json files
-    pub term_field_name: String, // for json files
+    pub term_column_name: String, // for csv files
+    pub code_field_name: String, // for csv, json and xlsx files
+    pub term_field_name: String, // for csv, json and xlsx files
+    pub comment_column_name: String, // for xlsx files
+    pub xlsx_sheet: XlsxSheet, // for xlsx files
+    pub xlsx_header_row: usize, // for xlsx files
+    pub csv_delimiter: Option<char>, // for csv files
+    pub csv_encoding: Option<String>, // for csv files
+    pub trim_headers: bool, // for csv, json and xlsx files
+    pub case_insensitive_headers: bool, // for csv, json and xlsx files
+    pub lenient_loading: bool, // for csv files
+    pub file_name_template: String, // for saving collections
+    pub type_column_name: Option<String>, // for inferring types
+    pub overwrite_existing_files: bool, // for saving collections
+    pub write_metadata_sidecar: bool, // for csv files
 
 }
 
@@ -36,6 +74,18 @@ impl Default for CodeListOptions {
             term_column_name: "term".to_string(),
             code_field_name: "code".to_string(),
             term_field_name: "term".to_string(),
+            comment_column_name: "comment".to_string(),
+            xlsx_sheet: XlsxSheet::Index(0),
+            xlsx_header_row: 0,
+            csv_delimiter: None,
+            csv_encoding: None,
+            trim_headers: true,
+            case_insensitive_headers: true,
+            lenient_loading: false,
+            file_name_template: "{id}".to_string(),
+            type_column_name: None,
+            overwrite_existing_files: true,
+            write_metadata_sidecar: false,
         }
     }
 }
@@ -47,12 +97,33 @@ mod tests {
     #[test]
     fn test_default() {
         let options = CodeListOptions::default();
-        assert_eq!(options.allow_duplicates, false);
-        assert_eq!(options.truncate_to_3_digits, false);
-        assert_eq!(options.add_x_codes, false);
+        assert!(!options.allow_duplicates);
+        assert!(!options.truncate_to_3_digits);
+        assert!(!options.add_x_codes);
         assert_eq!(options.code_column_name, "code");
         assert_eq!(options.term_column_name, "term");
         assert_eq!(options.code_field_name, "code");
         assert_eq!(options.term_field_name, "term");
+        assert_eq!(options.comment_column_name, "comment");
+        assert_eq!(options.xlsx_sheet, XlsxSheet::Index(0));
+        assert_eq!(options.xlsx_header_row, 0);
+        assert_eq!(options.csv_delimiter, None);
+        assert_eq!(options.csv_encoding, None);
+        assert!(options.trim_headers);
+        assert!(options.case_insensitive_headers);
+        assert!(!options.lenient_loading);
+        assert_eq!(options.file_name_template, "{id}");
+        assert_eq!(options.type_column_name, None);
+        assert!(options.overwrite_existing_files);
+        assert!(!options.write_metadata_sidecar);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deserialize_missing_fields_uses_defaults() -> Result<(), serde_json::Error> {
+        let options: CodeListOptions = serde_json::from_str(r#"{"allow_duplicates": true}"#)?;
+        assert!(options.allow_duplicates);
+        assert_eq!(options.xlsx_sheet, XlsxSheet::Index(0));
+        assert_eq!(options.comment_column_name, "comment");
+        Ok(())
+    }
+}
@@ -0,0 +1,612 @@
+//! This file contains a repository of codelists stored in a SQLite database
+//!
+//! Each version of a codelist is stored as its saved JSON, so it comes back exactly as it was
+//! saved, alongside tables of its authors and codes for querying. Codelists are keyed by their id
+//! and the version in their metadata. Only available with the `sqlite` cargo feature.
+
+// External imports
+use std::str::FromStr;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_format::CodeListFormat;
+use crate::errors::CodeListError;
+use crate::folder_load::FolderLoadOptions;
+use crate::types::CodeListType;
+use crate::version::CodeListVersion;
+
+/// The tables of the repository, created when it is opened
+const REPOSITORY_SCHEMA: &str = "
+PRAGMA foreign_keys = ON;
+CREATE TABLE IF NOT EXISTS codelists (
+    row_id INTEGER PRIMARY KEY,
+    id TEXT NOT NULL,
+    version TEXT NOT NULL,
+    name TEXT,
+    codelist_type TEXT NOT NULL,
+    published INTEGER NOT NULL DEFAULT 0,
+    codelist_json TEXT NOT NULL,
+    UNIQUE (id, version)
+);
+CREATE TABLE IF NOT EXISTS codelist_authors (
+    codelist_row INTEGER NOT NULL REFERENCES codelists (row_id) ON DELETE CASCADE,
+    author TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS codelist_entries (
+    codelist_row INTEGER NOT NULL REFERENCES codelists (row_id) ON DELETE CASCADE,
+    code TEXT NOT NULL,
+    term TEXT NOT NULL,
+    comment TEXT
+);
+CREATE INDEX IF NOT EXISTS codelist_authors_author ON codelist_authors (author COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS codelist_entries_code ON codelist_entries (code);
+";
+
+/// Struct to represent a query for codelists in a repository. Every filter that is set must match.
+///
+/// # Fields
+/// * `code` - Only codelists containing this code
+/// * `codelist_type` - Only codelists of this type
+/// * `author` - Only codelists with this author, ignoring case
+/// * `name_contains` - Only codelists whose name contains this text, ignoring case
+/// * `published` - Only published (or only unpublished) codelists
+/// * `latest_only` - Only the highest version of each codelist
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeListQuery {
+    pub code: Option<String>,
+    pub codelist_type: Option<CodeListType>,
+    pub author: Option<String>,
+    pub name_contains: Option<String>,
+    pub published: Option<bool>,
+    pub latest_only: bool,
+}
+
+/// Struct to represent a codelist version found in a repository
+///
+/// # Fields
+/// * `id` - The id of the codelist
+/// * `version` - The version of the codelist, if it has one
+/// * `name` - The name of the codelist
+/// * `codelist_type` - The type of the codelist
+/// * `published` - Whether the version is published
+/// * `entry_count` - The number of entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeListSummary {
+    pub id: String,
+    pub version: Option<CodeListVersion>,
+    pub name: Option<String>,
+    pub codelist_type: CodeListType,
+    pub published: bool,
+    pub entry_count: usize,
+}
+
+/// Struct to represent a repository of codelists in a SQLite database
+///
+/// # Fields
+/// * `connection` - The connection to the database
+pub struct CodeListRepository {
+    connection: Connection,
+}
+
+impl CodeListRepository {
+    /// Open a repository, creating the database file and its tables if they do not exist
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the database file
+    ///
+    /// # Returns
+    /// * `Result<CodeListRepository, CodeListError>` - The repository or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::SqliteError` - If the database cannot be opened or its tables created
+    pub fn open(file_path: &str) -> Result<CodeListRepository, CodeListError> {
+        Self::from_connection(Connection::open(file_path)?)
+    }
+
+    /// Open a repository that is held in memory, and lost when it is dropped
+    ///
+    /// # Returns
+    /// * `Result<CodeListRepository, CodeListError>` - The repository or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::SqliteError` - If the tables cannot be created
+    pub fn open_in_memory() -> Result<CodeListRepository, CodeListError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Create a repository from a connection, creating its tables if they do not exist
+    ///
+    /// # Arguments
+    /// * `connection` - The connection to the database
+    fn from_connection(connection: Connection) -> Result<CodeListRepository, CodeListError> {
+        connection.execute_batch(REPOSITORY_SCHEMA)?;
+        Ok(CodeListRepository { connection })
+    }
+
+    /// Save a codelist, replacing any codelist with the same id and version
+    ///
+    /// The version is the version in the codelist metadata. A replaced version keeps its published
+    /// flag.
+    ///
+    /// # Arguments
+    /// * `codelist` - The codelist to save
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the codelist has no id
+    /// * `CodeListError::JSONError` - If the codelist cannot be serialized
+    /// * `CodeListError::SqliteError` - If the codelist cannot be written
+    pub fn save(&mut self, codelist: &CodeList) -> Result<(), CodeListError> {
+        let id = codelist.id.as_deref()
+            .ok_or_else(|| CodeListError::invalid_input("Codelists need an id to be saved to a repository"))?;
+        let version = codelist.metadata.version.as_ref().map(ToString::to_string).unwrap_or_default();
+        let mut codelist_json = Vec::new();
+        codelist.save_to_json_writer(&mut codelist_json)?;
+        let codelist_json = String::from_utf8_lossy(&codelist_json);
+
+        let transaction = self.connection.transaction()?;
+        let existing_row: Option<i64> = transaction.query_row(
+            "SELECT row_id FROM codelists WHERE id = ?1 AND version = ?2",
+            params![id, version],
+            |row| row.get(0),
+        ).optional()?;
+        let row_id = match existing_row {
+            Some(row_id) => {
+                transaction.execute(
+                    "UPDATE codelists SET name = ?1, codelist_type = ?2, codelist_json = ?3 WHERE row_id = ?4",
+                    params![codelist.name, codelist.codelist_type.to_string(), codelist_json, row_id],
+                )?;
+                transaction.execute("DELETE FROM codelist_authors WHERE codelist_row = ?1", params![row_id])?;
+                transaction.execute("DELETE FROM codelist_entries WHERE codelist_row = ?1", params![row_id])?;
+                row_id
+            }
+            None => {
+                transaction.execute(
+                    "INSERT INTO codelists (id, version, name, codelist_type, codelist_json) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![id, version, codelist.name, codelist.codelist_type.to_string(), codelist_json],
+                )?;
+                transaction.last_insert_rowid()
+            }
+        };
+        {
+            let mut insert_author = transaction.prepare("INSERT INTO codelist_authors (codelist_row, author) VALUES (?1, ?2)")?;
+            for author in codelist.metadata.authors.iter().flatten() {
+                insert_author.execute(params![row_id, author])?;
+            }
+            let mut insert_entry = transaction.prepare("INSERT INTO codelist_entries (codelist_row, code, term, comment) VALUES (?1, ?2, ?3, ?4)")?;
+            for entry in codelist.entries_sorted_by_code() {
+                insert_entry.execute(params![row_id, entry.code, entry.term, entry.comment])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Get the rows of every version of a codelist, in version order
+    ///
+    /// An unversioned codelist is ordered before any versions.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    ///
+    /// # Returns
+    /// * `Result<Vec<(i64, Option<CodeListVersion>)>, CodeListError>` - The row ids and versions or an error
+    fn version_rows(&self, id: &str) -> Result<Vec<(i64, Option<CodeListVersion>)>, CodeListError> {
+        let mut statement = self.connection.prepare("SELECT row_id, version FROM codelists WHERE id = ?1")?;
+        let rows = statement.query_map(params![id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        let mut version_rows = Vec::new();
+        for row in rows {
+            let (row_id, version) = row?;
+            version_rows.push((row_id, parse_stored_version(&version)?));
+        }
+        version_rows.sort_by(|a, b| a.1.cmp(&b.1));
+        Ok(version_rows)
+    }
+
+    /// Find the row of a codelist version
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    /// * `version` - The version, or `None` for the highest version
+    ///
+    /// # Returns
+    /// * `Result<i64, CodeListError>` - The row id or an error
+    fn row_id(&self, id: &str, version: Option<&str>) -> Result<i64, CodeListError> {
+        let row_id = match version {
+            Some(version) => self.connection.query_row(
+                "SELECT row_id FROM codelists WHERE id = ?1 AND version = ?2",
+                params![id, version],
+                |row| row.get(0),
+            ).optional()?,
+            None => self.version_rows(id)?.last().map(|(row_id, _)| *row_id),
+        };
+        row_id.ok_or_else(|| match version {
+            Some(version) => CodeListError::code_list_not_found(format!("{} version {}", id, version)),
+            None => CodeListError::code_list_not_found(id),
+        })
+    }
+
+    /// Get a codelist
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    /// * `version` - The version, `Some("")` for the unversioned codelist, or `None` for the
+    ///   highest version
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::CodeListNotFound` - If the codelist or version is not in the repository
+    /// * `CodeListError::SqliteError` - If the codelist cannot be read
+    /// * See `CodeList::from_saved_json` for other errors
+    pub fn get(&self, id: &str, version: Option<&str>) -> Result<CodeList, CodeListError> {
+        let row_id = self.row_id(id, version)?;
+        let codelist_json: String = self.connection.query_row(
+            "SELECT codelist_json FROM codelists WHERE row_id = ?1",
+            params![row_id],
+            |row| row.get(0),
+        )?;
+        CodeList::from_saved_json(serde_json::from_str(&codelist_json)?)
+    }
+
+    /// Get the versions of a codelist, from lowest to highest
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeListVersion>, CodeListError>` - The versions, empty for an unversioned codelist, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::SqliteError` - If the versions cannot be read
+    pub fn versions(&self, id: &str) -> Result<Vec<CodeListVersion>, CodeListError> {
+        Ok(self.version_rows(id)?.into_iter().filter_map(|(_, version)| version).collect())
+    }
+
+    /// Mark a codelist version as published or unpublished
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    /// * `version` - The version, or `None` for the highest version
+    /// * `published` - Whether the version is published
+    ///
+    /// # Errors
+    /// * `CodeListError::CodeListNotFound` - If the codelist or version is not in the repository
+    /// * `CodeListError::SqliteError` - If the flag cannot be written
+    pub fn set_published(&mut self, id: &str, version: Option<&str>, published: bool) -> Result<(), CodeListError> {
+        let row_id = self.row_id(id, version)?;
+        self.connection.execute("UPDATE codelists SET published = ?1 WHERE row_id = ?2", params![published, row_id])?;
+        Ok(())
+    }
+
+    /// Delete a codelist version, or every version of a codelist
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    /// * `version` - The version, or `None` for every version
+    ///
+    /// # Returns
+    /// * `Result<usize, CodeListError>` - The number of versions deleted or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::SqliteError` - If the codelist cannot be deleted
+    pub fn delete(&mut self, id: &str, version: Option<&str>) -> Result<usize, CodeListError> {
+        let deleted = match version {
+            Some(version) => self.connection.execute("DELETE FROM codelists WHERE id = ?1 AND version = ?2", params![id, version])?,
+            None => self.connection.execute("DELETE FROM codelists WHERE id = ?1", params![id])?,
+        };
+        Ok(deleted)
+    }
+
+    /// Find the codelist versions that match a query, sorted by id and then version
+    ///
+    /// # Arguments
+    /// * `query` - The query
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeListSummary>, CodeListError>` - The matching versions or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::SqliteError` - If the query fails
+    pub fn search(&self, query: &CodeListQuery) -> Result<Vec<CodeListSummary>, CodeListError> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(code) = &query.code {
+            values.push(code.clone());
+            conditions.push(format!("EXISTS (SELECT 1 FROM codelist_entries AS e WHERE e.codelist_row = c.row_id AND e.code = ?{})", values.len()));
+        }
+        if let Some(codelist_type) = &query.codelist_type {
+            values.push(codelist_type.to_string());
+            conditions.push(format!("c.codelist_type = ?{}", values.len()));
+        }
+        if let Some(author) = &query.author {
+            values.push(author.clone());
+            conditions.push(format!("EXISTS (SELECT 1 FROM codelist_authors AS a WHERE a.codelist_row = c.row_id AND a.author = ?{} COLLATE NOCASE)", values.len()));
+        }
+        if let Some(name_contains) = &query.name_contains {
+            values.push(name_contains.to_lowercase());
+            conditions.push(format!("instr(lower(c.name), ?{}) > 0", values.len()));
+        }
+        if let Some(published) = query.published {
+            conditions.push(format!("c.published = {}", published as i32));
+        }
+
+        let mut sql = "SELECT c.id, c.version, c.name, c.codelist_type, c.published, \
+            (SELECT COUNT(*) FROM codelist_entries AS e WHERE e.codelist_row = c.row_id) \
+            FROM codelists AS c".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+
+        let mut summaries = Vec::new();
+        for row in rows {
+            let (id, version, name, codelist_type, published, entry_count) = row?;
+            summaries.push(CodeListSummary {
+                id,
+                version: parse_stored_version(&version)?,
+                name,
+                codelist_type: CodeListType::from_str(&codelist_type)?,
+                published,
+                entry_count: entry_count as usize,
+            });
+        }
+        summaries.sort_by(|a, b| a.id.cmp(&b.id).then_with(|| a.version.cmp(&b.version)));
+
+        if query.latest_only {
+            // Only keep a version if it is the highest of its codelist, not just the highest match
+            let mut latest = Vec::new();
+            for summary in summaries {
+                let highest = self.version_rows(&summary.id)?.pop().map(|(_, version)| version);
+                if highest.as_ref() == Some(&summary.version) {
+                    latest.push(summary);
+                }
+            }
+            return Ok(latest);
+        }
+        Ok(summaries)
+    }
+
+    /// Load a codelist file with a factory and save it to the repository
+    ///
+    /// The codelist id comes from the file name, unless the file stores one.
+    ///
+    /// # Arguments
+    /// * `factory` - The factory to load the file with
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The imported codelist or an error
+    ///
+    /// # Errors
+    /// * See `CodeListFactory::load_codelist_from_file` and `save` for errors
+    pub fn import_file(&mut self, factory: &CodeListFactory, file_path: &str) -> Result<CodeList, CodeListError> {
+        let codelist = factory.load_codelist_from_file(file_path)?;
+        self.save(&codelist)?;
+        Ok(codelist)
+    }
+
+    /// Load a folder of codelist files with a factory and save the loaded codelists to the repository
+    ///
+    /// # Arguments
+    /// * `factory` - The factory to load the files with
+    /// * `folder_path` - The path to the folder
+    /// * `options` - The options for walking the folder
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The imported codelists or an error
+    ///
+    /// # Errors
+    /// * See `CodeListFactory::load_codelists_from_folder_with_options` and `save` for errors
+    pub fn import_folder(&mut self, factory: &CodeListFactory, folder_path: &str, options: &FolderLoadOptions) -> Result<Vec<CodeList>, CodeListError> {
+        let codelists = factory.load_codelists_from_folder_with_options(folder_path, options)?.into_codelists();
+        for codelist in &codelists {
+            self.save(codelist)?;
+        }
+        Ok(codelists)
+    }
+
+    /// Save a codelist from the repository to a file, in the format of its extension
+    ///
+    /// # Arguments
+    /// * `id` - The id of the codelist
+    /// * `version` - The version, or `None` for the highest version
+    /// * `file_path` - The path to the file to save the codelist to
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidFilePath` - If the file extension is not a supported format
+    /// * See `get` and `CodeList::save_to_file_with_format` for other errors
+    pub fn export_file(&self, id: &str, version: Option<&str>, file_path: &str) -> Result<(), CodeListError> {
+        let format = CodeListFormat::from_path(file_path)
+            .ok_or_else(|| CodeListError::invalid_file_path(format!("Unsupported file extension: {}", file_path)))?;
+        self.get(id, version)?.save_to_file_with_format(file_path, format)
+    }
+}
+
+/// Parse a version as it is stored in the repository, where an unversioned codelist has an empty version
+///
+/// # Arguments
+/// * `version` - The stored version
+///
+/// # Returns
+/// * `Result<Option<CodeListVersion>, CodeListError>` - The version, if there is one, or an error
+fn parse_stored_version(version: &str) -> Result<Option<CodeListVersion>, CodeListError> {
+    if version.is_empty() {
+        Ok(None)
+    } else {
+        version.parse().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use crate::codelist_options::CodeListOptions;
+    use crate::metadata::Metadata;
+    use tempfile::tempdir;
+
+    fn create_test_codelist(name: &str, codelist_type: CodeListType, author: &str, version: Option<&str>, codes: &[&str]) -> Result<CodeList, CodeListError> {
+        let metadata = Metadata {
+            authors: Some(vec![author.to_string()]),
+            version: version.map(|version| version.parse().unwrap()),
+            ..create_test_metadata()
+        };
+        let mut codelist = CodeList::new(codelist_type, metadata, None);
+        codelist.set_name(name);
+        for code in codes {
+            codelist.add_entry(code.to_string(), format!("Term for {}", code), None)?;
+        }
+        Ok(codelist)
+    }
+
+    fn create_test_repository() -> Result<CodeListRepository, CodeListError> {
+        let mut repository = CodeListRepository::open_in_memory()?;
+        repository.save(&create_test_codelist("Diabetes", CodeListType::ICD10, "Caroline Morton", Some("1.0.0"), &["E10", "E11"])?)?;
+        repository.save(&create_test_codelist("Diabetes", CodeListType::ICD10, "Caroline Morton", Some("2.0.0"), &["E10", "E11", "E11.9"])?)?;
+        repository.save(&create_test_codelist("Asthma", CodeListType::SNOMED, "Emma Bagshaw", None, &["195967001"])?)?;
+        repository.save(&create_test_codelist("Hypertension", CodeListType::ICD10, "Emma Bagshaw", Some("2024-01-31"), &["I10", "E11.9"])?)?;
+        Ok(repository)
+    }
+
+    #[test]
+    fn test_save_and_get() -> Result<(), CodeListError> {
+        let repository = create_test_repository()?;
+
+        let latest = repository.get("diabetes", None)?;
+        assert_eq!(latest.metadata.version, Some("2.0.0".parse()?));
+        assert_eq!(latest.entries.len(), 3);
+        assert_eq!(latest.name, Some("Diabetes".to_string()));
+        assert_eq!(repository.get("diabetes", Some("1.0.0"))?.entries.len(), 2);
+        assert_eq!(repository.get("asthma", Some(""))?.codelist_type, CodeListType::SNOMED);
+        assert_eq!(repository.versions("diabetes")?, vec!["1.0.0".parse()?, "2.0.0".parse()?]);
+        assert!(repository.versions("asthma")?.is_empty());
+
+        let error = repository.get("diabetes", Some("3.0.0")).unwrap_err();
+        assert!(matches!(error, CodeListError::CodeListNotFound { id } if id == "diabetes version 3.0.0"));
+        let error = repository.get("copd", None).unwrap_err();
+        assert!(matches!(error, CodeListError::CodeListNotFound { id } if id == "copd"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_latest_is_highest_version() -> Result<(), CodeListError> {
+        let mut repository = create_test_repository()?;
+        repository.save(&create_test_codelist("Diabetes", CodeListType::ICD10, "Caroline Morton", Some("1.0.1"), &["E10"])?)?;
+
+        assert_eq!(repository.get("diabetes", None)?.metadata.version, Some("2.0.0".parse()?));
+        assert_eq!(repository.versions("diabetes")?, vec!["1.0.0".parse()?, "1.0.1".parse()?, "2.0.0".parse()?]);
+        let latest = repository.search(&CodeListQuery { code: Some("E10".to_string()), latest_only: true, ..CodeListQuery::default() })?;
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].version, Some("2.0.0".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_replaces_version() -> Result<(), CodeListError> {
+        let mut repository = create_test_repository()?;
+        repository.set_published("diabetes", Some("1.0.0"), true)?;
+
+        let mut codelist = create_test_codelist("Diabetes", CodeListType::ICD10, "Caroline Morton", Some("1.0.0"), &["E10"])?;
+        codelist.add_log("Replaced".to_string());
+        repository.save(&codelist)?;
+
+        assert_eq!(repository.versions("diabetes")?, vec!["1.0.0".parse()?, "2.0.0".parse()?]);
+        let replaced = repository.get("diabetes", Some("1.0.0"))?;
+        assert_eq!(replaced.entries.len(), 1);
+        assert_eq!(replaced.logs, vec!["Replaced"]);
+        let published = repository.search(&CodeListQuery { published: Some(true), ..CodeListQuery::default() })?;
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].entry_count, 1);
+
+        let error = repository.save(&CodeList::new(CodeListType::ICD10, Metadata::default(), None)).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Codelists need an id to be saved to a repository"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search() -> Result<(), CodeListError> {
+        let mut repository = create_test_repository()?;
+
+        let containing = repository.search(&CodeListQuery { code: Some("E11.9".to_string()), ..CodeListQuery::default() })?;
+        let found: Vec<(&str, Option<String>)> = containing.iter().map(|summary| (summary.id.as_str(), summary.version.as_ref().map(ToString::to_string))).collect();
+        assert_eq!(found, vec![("diabetes", Some("2.0.0".to_string())), ("hypertension", Some("2024-01-31".to_string()))]);
+
+        repository.set_published("hypertension", None, true)?;
+        repository.set_published("asthma", None, true)?;
+        let published_icd10 = repository.search(&CodeListQuery {
+            codelist_type: Some(CodeListType::ICD10),
+            author: Some("emma bagshaw".to_string()),
+            published: Some(true),
+            ..CodeListQuery::default()
+        })?;
+        assert_eq!(published_icd10, vec![CodeListSummary {
+            id: "hypertension".to_string(),
+            version: Some("2024-01-31".parse()?),
+            name: Some("Hypertension".to_string()),
+            codelist_type: CodeListType::ICD10,
+            published: true,
+            entry_count: 2,
+        }]);
+
+        let latest = repository.search(&CodeListQuery { name_contains: Some("BETES".to_string()), latest_only: true, ..CodeListQuery::default() })?;
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].version, Some("2.0.0".parse()?));
+        assert_eq!(repository.search(&CodeListQuery::default())?.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete() -> Result<(), CodeListError> {
+        let mut repository = create_test_repository()?;
+        assert_eq!(repository.delete("diabetes", Some("2.0.0"))?, 1);
+        assert_eq!(repository.get("diabetes", None)?.metadata.version, Some("1.0.0".parse()?));
+        assert_eq!(repository.delete("diabetes", None)?, 1);
+        assert!(repository.versions("diabetes")?.is_empty());
+        let containing = repository.search(&CodeListQuery { code: Some("E10".to_string()), ..CodeListQuery::default() })?;
+        assert!(containing.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_and_export_files() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let folder = temp_dir.path().join("codelists");
+        std::fs::create_dir(&folder)?;
+        std::fs::write(folder.join("Heart Failure.csv"), "code,term\nI50,Heart failure\nI50.0,Congestive heart failure\n")?;
+        std::fs::write(folder.join("stroke.csv"), "code,term\nI63,Cerebral infarction\n")?;
+        let database_path = temp_dir.path().join("codelists.sqlite");
+        let factory = CodeListFactory::new(CodeListOptions::default(), Metadata { version: None, ..create_test_metadata() }, CodeListType::ICD10);
+
+        let mut repository = CodeListRepository::open(database_path.to_str().unwrap())?;
+        let imported = repository.import_folder(&factory, folder.to_str().unwrap(), &FolderLoadOptions::default())?;
+        assert_eq!(imported.len(), 2);
+        drop(repository);
+
+        let repository = CodeListRepository::open(database_path.to_str().unwrap())?;
+        assert_eq!(repository.get("heart-failure", None)?.entries.len(), 2);
+
+        let export_path = temp_dir.path().join("stroke.json");
+        repository.export_file("stroke", None, export_path.to_str().unwrap())?;
+        let exported = factory.load_codelist_from_file(export_path.to_str().unwrap())?;
+        assert_eq!(exported.entries, repository.get("stroke", None)?.entries);
+
+        let error = repository.export_file("stroke", None, temp_dir.path().join("stroke.txt").to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidFilePath { .. }));
+        Ok(())
+    }
+}
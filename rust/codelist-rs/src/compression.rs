@@ -0,0 +1,339 @@
+//! This file contains the transparent gzip and zstd compression of codelist files
+//!
+//! Files are compressed when saved to a path ending in a compression extension, such as
+//! `codelist.csv.gz`. When loading, compression is detected from the extension or from the
+//! magic bytes at the start of the file, and the format from the extension before it.
+
+// External imports
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+// Internal imports
+use crate::codelist_format::CodeListFormat;
+use crate::errors::CodeListError;
+
+/// The magic bytes at the start of a gzip stream
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+
+/// The magic bytes at the start of a zstd frame
+const ZSTD_MAGIC: &[u8] = b"\x28\xB5\x2F\xFD";
+
+/// The largest size, in bytes, that compressed data is decompressed to, so that a small
+/// compressed file cannot fill memory
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+/// Enum to represent the compression of a codelist file
+///
+/// # Variants
+/// * `Gzip` - gzip, with the `.gz` extension
+/// * `Zstd` - Zstandard, with the `.zst` extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl fmt::Display for Compression {
+    /// Format the compression as its name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Compression {
+    /// Get the compression of a file extension
+    ///
+    /// # Arguments
+    /// * `ext` - The file extension, without the leading dot, in any case
+    ///
+    /// # Returns
+    /// * `Option<Compression>` - The compression, if the extension is a compression extension
+    pub fn from_extension(ext: &str) -> Option<Compression> {
+        match ext.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Get the compression of a file path from its extension
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Option<Compression>` - The compression, if the path ends in a compression extension
+    pub fn from_path(file_path: &str) -> Option<Compression> {
+        Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Compression::from_extension)
+    }
+
+    /// Detect the compression of file contents from their magic bytes
+    ///
+    /// # Arguments
+    /// * `bytes` - The contents of the file, or at least its first four bytes
+    ///
+    /// # Returns
+    /// * `Option<Compression>` - The compression, if the contents are compressed
+    pub fn detect(bytes: &[u8]) -> Option<Compression> {
+        if bytes.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Get the usual file extension of the compression
+    ///
+    /// # Returns
+    /// * `&'static str` - The file extension, without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Decompress data, up to `MAX_DECOMPRESSED_SIZE` bytes
+    ///
+    /// # Arguments
+    /// * `bytes` - The compressed data
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, CodeListError>` - The decompressed data or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If the data is not valid for the compression
+    /// * `CodeListError::InvalidInput` - If the data decompresses to more than `MAX_DECOMPRESSED_SIZE` bytes
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, CodeListError> {
+        self.decompress_with_limit(bytes, MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Decompress data, up to a size limit
+    ///
+    /// # Arguments
+    /// * `bytes` - The compressed data
+    /// * `limit` - The largest size, in bytes, to decompress to
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, CodeListError>` - The decompressed data or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If the data is not valid for the compression
+    /// * `CodeListError::InvalidInput` - If the data decompresses to more than `limit` bytes
+    fn decompress_with_limit(&self, bytes: &[u8], limit: u64) -> Result<Vec<u8>, CodeListError> {
+        let mut decompressed = Vec::new();
+        // Read one byte past the limit, so that data of exactly the limit is accepted
+        match self {
+            Compression::Gzip => MultiGzDecoder::new(bytes).take(limit + 1).read_to_end(&mut decompressed)?,
+            Compression::Zstd => zstd::stream::read::Decoder::new(bytes)?.take(limit + 1).read_to_end(&mut decompressed)?,
+        };
+        if decompressed.len() as u64 > limit {
+            return Err(CodeListError::invalid_input(format!("The {} data decompresses to more than {} bytes", self, limit)));
+        }
+        Ok(decompressed)
+    }
+}
+
+/// Remove a compression extension from a file path, so that the extension before it gives the format
+///
+/// # Arguments
+/// * `file_path` - The path to the file, e.g. "codelists/asthma.csv.gz"
+///
+/// # Returns
+/// * `&str` - The path without the compression extension, e.g. "codelists/asthma.csv"
+pub fn strip_compression_extension(file_path: &str) -> &str {
+    match (Compression::from_path(file_path), file_path.rfind('.')) {
+        (Some(_), Some(dot)) => &file_path[..dot],
+        _ => file_path,
+    }
+}
+
+/// Whether a file path has the extension of a codelist file that can be loaded
+///
+/// That is a codelist format extension, such as `asthma.csv`, or a compression extension with or
+/// without a format extension before it, such as `asthma.csv.gz` or `asthma.zst`, whose format is
+/// then detected from the decompressed contents.
+///
+/// # Arguments
+/// * `file_path` - The path to the file
+///
+/// # Returns
+/// * `bool` - True if the file can be loaded by its extension
+pub(crate) fn has_codelist_extension(file_path: &str) -> bool {
+    CodeListFormat::from_path(strip_compression_extension(file_path)).is_some() || Compression::from_path(file_path).is_some()
+}
+
+/// Decompress data if its magic bytes show that it is compressed
+///
+/// # Arguments
+/// * `bytes` - The data
+///
+/// # Returns
+/// * `Result<Vec<u8>, CodeListError>` - The decompressed data, or the data unchanged, or an error
+///
+/// # Errors
+/// * `CodeListError::IOError` - If the data is not valid for its compression
+pub fn decompress_if_compressed(bytes: Vec<u8>) -> Result<Vec<u8>, CodeListError> {
+    match Compression::detect(&bytes) {
+        Some(compression) => compression.decompress(&bytes),
+        None => Ok(bytes),
+    }
+}
+
+/// Whether a file is compressed, judging by its extension or its magic bytes
+///
+/// # Arguments
+/// * `file_path` - The path to the file
+///
+/// # Returns
+/// * `Result<bool, CodeListError>` - True if the file is compressed, or an error
+///
+/// # Errors
+/// * `CodeListError::IOError` - If the file cannot be read
+pub(crate) fn is_compressed_file(file_path: &str) -> Result<bool, CodeListError> {
+    if Compression::from_path(file_path).is_some() {
+        return Ok(true);
+    }
+    let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+    File::open(file_path)?.take(ZSTD_MAGIC.len() as u64).read_to_end(&mut magic)?;
+    Ok(Compression::detect(&magic).is_some())
+}
+
+/// Enum to represent a file being written, compressed when its path ends in a compression extension
+///
+/// The file must be finished with `finish`, which writes the end of the compressed stream.
+///
+/// # Variants
+/// * `Plain` - An uncompressed file
+/// * `Gzip` - A gzip compressed file
+/// * `Zstd` - A zstd compressed file
+pub(crate) enum OutputFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutputFile {
+    /// Create a file, compressed according to the extension of its path
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Result<OutputFile, CodeListError>` - The file writer or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If the file cannot be created
+    pub(crate) fn create(file_path: &str) -> Result<OutputFile, CodeListError> {
+        let file = BufWriter::new(File::create(file_path)?);
+        Ok(match Compression::from_path(file_path) {
+            None => OutputFile::Plain(file),
+            Some(Compression::Gzip) => OutputFile::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+            Some(Compression::Zstd) => OutputFile::Zstd(zstd::stream::write::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+        })
+    }
+
+    /// Finish the file, writing the end of any compressed stream and flushing it
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    pub(crate) fn finish(self) -> Result<(), CodeListError> {
+        let mut file = match self {
+            OutputFile::Plain(file) => file,
+            OutputFile::Gzip(encoder) => encoder.finish()?,
+            OutputFile::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputFile::Plain(file) => file.write(buf),
+            OutputFile::Gzip(encoder) => encoder.write(buf),
+            OutputFile::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Plain(file) => file.flush(),
+            OutputFile::Gzip(encoder) => encoder.flush(),
+            OutputFile::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_compression_from_path() {
+        assert_eq!(Compression::from_path("codelists/asthma.csv.gz"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_path("codelists/asthma.json.ZST"), Some(Compression::Zstd));
+        assert_eq!(Compression::from_path("codelists/asthma.csv"), None);
+        assert_eq!(strip_compression_extension("codelists/asthma.csv.gz"), "codelists/asthma.csv");
+        assert_eq!(strip_compression_extension("codelists/asthma.zst"), "codelists/asthma");
+        assert_eq!(strip_compression_extension("codelists/asthma.csv"), "codelists/asthma.csv");
+        assert!(has_codelist_extension("codelists/asthma.csv.gz"));
+        assert!(has_codelist_extension("codelists/asthma.zst"));
+        assert!(!has_codelist_extension("codelists/asthma.txt"));
+    }
+
+    #[test]
+    fn test_file_writer_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        for (file_name, compression) in [("a.csv", None), ("a.csv.gz", Some(Compression::Gzip)), ("a.zst", Some(Compression::Zstd))] {
+            let file_path = temp_dir.path().join(file_name);
+            let file_path = file_path.to_str().unwrap();
+            let mut writer = OutputFile::create(file_path)?;
+            writer.write_all(b"code,term\nA01,Typhoid\n")?;
+            writer.finish()?;
+
+            let bytes = std::fs::read(file_path)?;
+            assert_eq!(Compression::detect(&bytes), compression);
+            assert_eq!(is_compressed_file(file_path)?, compression.is_some());
+            assert_eq!(decompress_if_compressed(bytes)?, b"code,term\nA01,Typhoid\n");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_invalid_data() {
+        let error = Compression::Gzip.decompress(b"\x1F\x8Bnot gzip").unwrap_err();
+        assert!(matches!(error, CodeListError::IOError(_)));
+    }
+
+    #[test]
+    fn test_decompress_size_limit() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("large.csv.zst");
+        let file_path = file_path.to_str().unwrap();
+        let mut writer = OutputFile::create(file_path)?;
+        writer.write_all(&[b'a'; 1000])?;
+        writer.finish()?;
+        let bytes = std::fs::read(file_path)?;
+
+        assert_eq!(Compression::Zstd.decompress_with_limit(&bytes, 1000)?.len(), 1000);
+        let error = Compression::Zstd.decompress_with_limit(&bytes, 999).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "The zstd data decompresses to more than 999 bytes"));
+        Ok(())
+    }
+}
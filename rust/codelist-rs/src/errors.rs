@@ -3,6 +3,8 @@
 use std::io;
 use serde_json;
 use csv;
+use calamine;
+use rust_xlsxwriter;
 
 /// Enum to represent the different types of errors that can occur in the codelist library
 /// 
@@ -11,6 +13,18 @@ use csv;
 /// * `IOError` - An error that occurs when there is an error reading or writing to a file
 /// * `EntryNotFound` - An error that occurs when an entry is not found in the codelist
 /// * `CSVError` - An error that occurs when there is an error serializing or deserializing CSV
+/// * `XLSXError` - An error that occurs when there is an error reading an xlsx workbook
+/// * `XLSXWriteError` - An error that occurs when there is an error writing an xlsx workbook
+/// * `ArrowError` - An error that occurs when there is an error reading or writing Arrow data
+/// * `ParquetError` - An error that occurs when there is an error reading or writing a Parquet file
+/// * `UnsupportedSchemaVersion` - An error that occurs when a saved codelist file was written with a schema version this library cannot read
+/// * `FileLoadFailed` - An error that occurs when a file in a folder cannot be loaded, wrapping the error for that file
+/// * `AmbiguousCodeListType` - An error that occurs when the type of a codelist cannot be inferred with enough confidence
+/// * `CodeListNotFound` - An error that occurs when a codelist is not found in a repository
+/// * `SqliteError` - An error that occurs when there is an error reading or writing a SQLite repository
+/// * `FingerprintMismatch` - An error that occurs when a file in a bundle does not match the fingerprint in its manifest
+/// * `ZipError` - An error that occurs when there is an error reading or writing a zip archive
+/// * `InvalidVersion` - An error that occurs when a codelist version is not a date or a semantic version
 
 #[derive(Debug, thiserror::Error, thiserror_ext::Construct)]
 pub enum CodeListError {
@@ -53,6 +67,25 @@ pub enum CodeListError {
     #[error("Comment for CodeEntry with code {code} and term {term} does not exist. Please use add comment instead if you are trying to add a comment.")]
     CodeEntryCommentDoesNotExist { code: String, term: String },
 
+    #[error("Unsupported codelist schema version: {version}. Supported version: {supported}")]
+    UnsupportedSchemaVersion { version: String, supported: String },
+
+    #[error("Ambiguous codelist type: {msg}")]
+    AmbiguousCodeListType { msg: String },
+
+    #[error("Codelist not found: {id}")]
+    CodeListNotFound { id: String },
+
+    #[error("Fingerprint mismatch for {path}: expected {expected}, found {actual}")]
+    FingerprintMismatch { path: String, expected: String, actual: String },
+
+    #[error("Invalid codelist version: {version}. Versions must be a date (YYYY-MM-DD) or a semantic version (MAJOR.MINOR.PATCH)")]
+    InvalidVersion { version: String },
+
+    #[error("Failed to load {path}: {source}")]
+    #[construct(skip)]
+    FileLoadFailed { path: String, source: Box<CodeListError> },
+
     #[error("JSON error: {0}")]
     #[construct(skip)]
     JSONError(#[from] serde_json::Error),
@@ -64,4 +97,31 @@ pub enum CodeListError {
     #[error("CSV error: {0}")]
     #[construct(skip)]
     CSVError(#[from] csv::Error),
+
+    #[error("XLSX error: {0}")]
+    #[construct(skip)]
+    XLSXError(#[from] calamine::XlsxError),
+
+    #[error("XLSX write error: {0}")]
+    #[construct(skip)]
+    XLSXWriteError(#[from] rust_xlsxwriter::XlsxError),
+
+    #[error("Zip error: {0}")]
+    #[construct(skip)]
+    ZipError(#[from] zip::result::ZipError),
+
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    #[construct(skip)]
+    ArrowError(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    #[construct(skip)]
+    ParquetError(#[from] parquet::errors::ParquetError),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    #[construct(skip)]
+    SqliteError(#[from] rusqlite::Error),
 }
\ No newline at end of file
@@ -0,0 +1,532 @@
+//! This file contains the conversion between codelists and FHIR R4 terminology resources
+//!
+//! Codelists are exported as FHIR `ValueSet` resources, either as an intensional definition
+//! (`compose.include.concept`) or as an expansion (`expansion.contains`). `ValueSet` resources in
+//! either form, and `CodeSystem` resources, can be imported.
+
+// External imports
+use std::io::{Read, Write};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Value};
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_options::CodeListOptions;
+use crate::errors::CodeListError;
+use crate::metadata::{format_timestamp, Metadata, MetadataSource};
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+
+/// Canonical FHIR system URI for SNOMED CT
+pub const SNOMED_SYSTEM_URI: &str = "http://snomed.info/sct";
+/// Canonical FHIR system URI for ICD-10
+pub const ICD10_SYSTEM_URI: &str = "http://hl7.org/fhir/sid/icd-10";
+/// FHIR system URI for OPCS-4, as used by NHS England
+pub const OPCS_SYSTEM_URI: &str = "http://fhir.nhs.uk/Id/opcs-4";
+
+/// Enum to represent how the codes of a FHIR ValueSet are written
+///
+/// # Variants
+/// * `Compose` - The codes are listed in `compose.include.concept`
+/// * `Expansion` - The codes are listed in `expansion.contains`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FhirValueSetLayout {
+    Compose,
+    Expansion,
+}
+
+/// Get the FHIR system URI of a codelist type
+///
+/// # Arguments
+/// * `codelist_type` - The type of codelist
+///
+/// # Returns
+/// * `&'static str` - The system URI
+pub fn fhir_system_uri(codelist_type: &CodeListType) -> &'static str {
+    match codelist_type {
+        CodeListType::ICD10 => ICD10_SYSTEM_URI,
+        CodeListType::SNOMED => SNOMED_SYSTEM_URI,
+        CodeListType::OPCS => OPCS_SYSTEM_URI,
+    }
+}
+
+/// Get the codelist type of a FHIR system URI
+///
+/// Besides the canonical URIs, national variants such as `http://hl7.org/fhir/sid/icd-10-uk`
+/// are recognised.
+///
+/// # Arguments
+/// * `system` - The system URI
+///
+/// # Returns
+/// * `Result<CodeListType, CodeListError>` - The codelist type or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidCodeListType` - If the system is not ICD10, SNOMED or OPCS
+pub fn fhir_system_to_type(system: &str) -> Result<CodeListType, CodeListError> {
+    let normalised = system.trim().trim_end_matches('/').to_lowercase();
+    if normalised == SNOMED_SYSTEM_URI {
+        Ok(CodeListType::SNOMED)
+    } else if normalised.starts_with(ICD10_SYSTEM_URI) {
+        Ok(CodeListType::ICD10)
+    } else if normalised.ends_with("opcs-4") || normalised.ends_with("opcs4") {
+        Ok(CodeListType::OPCS)
+    } else {
+        Err(CodeListError::invalid_code_list_type(system))
+    }
+}
+
+/// Collect the (system, code, display) triples of nested FHIR concepts
+///
+/// # Arguments
+/// * `concepts` - The concepts, which may contain nested concepts under `child_key`
+/// * `system` - The system of the concepts, if it is not given on each concept
+/// * `child_key` - The key holding nested concepts (`contains` or `concept`)
+/// * `found` - The triples found so far
+fn collect_fhir_concepts(concepts: &[Value], system: Option<&str>, child_key: &str, found: &mut Vec<(Option<String>, String, Option<String>)>) {
+    for concept in concepts {
+        let concept_system = concept.get("system").and_then(Value::as_str).or(system);
+        if let Some(code) = concept.get("code").and_then(Value::as_str) {
+            let display = concept.get("display").and_then(Value::as_str).map(str::to_string);
+            found.push((concept_system.map(str::to_string), code.to_string(), display));
+        }
+        if let Some(children) = concept.get(child_key).and_then(Value::as_array) {
+            collect_fhir_concepts(children, concept_system, child_key, found);
+        }
+    }
+}
+
+/// Parse a FHIR `dateTime`, which may be a full timestamp or just a date, as UTC
+///
+/// # Arguments
+/// * `value` - The FHIR dateTime, e.g. "2024-12-19T09:30:00Z" or "2024-12-19"
+///
+/// # Returns
+/// * `Option<DateTime<Utc>>` - The timestamp, with dates taken as midnight, or None if it cannot be parsed
+fn parse_fhir_date_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date_time| date_time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)).map(|date_time| date_time.and_utc()))
+}
+
+impl CodeList {
+    /// Convert the codelist to a FHIR R4 ValueSet resource
+    ///
+    /// The version and description come from the metadata and the authors are joined into the
+    /// publisher. The title, intended use and licence are written as the title, purpose and
+    /// copyright, the metadata id as the identifier and the last update or creation time as the
+    /// date. Codes are sorted and terms are written as the display.
+    ///
+    /// # Arguments
+    /// * `layout` - Whether to list the codes in the compose or the expansion
+    ///
+    /// # Returns
+    /// * `Value` - The ValueSet JSON
+    pub fn to_fhir_value_set(&self, layout: FhirValueSetLayout) -> Value {
+        let system = fhir_system_uri(&self.codelist_type);
+        let entries = self.entries_sorted_by_code();
+
+        let mut value_set = json!({
+            "resourceType": "ValueSet",
+            "status": "draft",
+        });
+        if let Some(id) = &self.id {
+            value_set["id"] = json!(id);
+        }
+        if let Some(id) = &self.metadata.id {
+            value_set["identifier"] = json!([{"value": id}]);
+        }
+        if let Some(title) = self.metadata.title.as_ref().or(self.name.as_ref()) {
+            value_set["title"] = json!(title);
+        }
+        if let Some(version) = self.metadata.version_label() {
+            value_set["version"] = json!(version);
+        }
+        if let Some(authors) = self.metadata.authors.as_ref().filter(|authors| !authors.is_empty()) {
+            value_set["publisher"] = json!(authors.join(", "));
+        }
+        if let Some(description) = &self.metadata.description {
+            value_set["description"] = json!(description);
+        }
+        if let Some(date) = self.metadata.updated.or(self.metadata.created) {
+            value_set["date"] = json!(format_timestamp(&date));
+        }
+        if let Some(intended_use) = &self.metadata.intended_use {
+            value_set["purpose"] = json!(intended_use);
+        }
+        if let Some(licence) = &self.metadata.licence {
+            value_set["copyright"] = json!(licence);
+        }
+
+        match layout {
+            FhirValueSetLayout::Compose => {
+                let concepts: Vec<Value> = entries.iter()
+                    .map(|entry| json!({"code": entry.code, "display": entry.term}))
+                    .collect();
+                value_set["compose"] = json!({"include": [{"system": system, "concept": concepts}]});
+            }
+            FhirValueSetLayout::Expansion => {
+                let contains: Vec<Value> = entries.iter()
+                    .map(|entry| json!({"system": system, "code": entry.code, "display": entry.term}))
+                    .collect();
+                value_set["expansion"] = json!({
+                    "timestamp": format_timestamp(&Utc::now()),
+                    "total": contains.len(),
+                    "contains": contains,
+                });
+            }
+        }
+        value_set
+    }
+
+    /// Save the codelist as a FHIR R4 ValueSet json file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the ValueSet to
+    /// * `layout` - Whether to list the codes in the compose or the expansion
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the ValueSet
+    pub fn save_to_fhir_value_set(&self, file_path: &str, layout: FhirValueSetLayout) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_fhir_value_set_writer(&mut file, layout)?;
+        file.finish()
+    }
+
+    /// Write the codelist as a FHIR R4 ValueSet to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the ValueSet json to
+    /// * `layout` - Whether to list the codes in the compose or the expansion
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the ValueSet
+    pub fn save_to_fhir_value_set_writer<W: Write>(&self, mut writer: W, layout: FhirValueSetLayout) -> Result<(), CodeListError> {
+        serde_json::to_writer_pretty(&mut writer, &self.to_fhir_value_set(layout))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Create a codelist from a FHIR R4 ValueSet or CodeSystem resource
+    ///
+    /// Codes are read from `compose.include.concept` and `expansion.contains` of a ValueSet, or
+    /// from `concept` of a CodeSystem, including nested concepts. All codes must belong to a single
+    /// ICD10, SNOMED or OPCS system. The version, publisher and description of the resource are
+    /// used in place of the given metadata where present. A version that is not a date or
+    /// semantic version is not kept, and is noted in the logs.
+    ///
+    /// # Arguments
+    /// * `resource` - The ValueSet or CodeSystem JSON
+    /// * `metadata` - The metadata to use for fields the resource does not have
+    /// * `codelist_options` - The options for the codelist
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the resource is not a ValueSet or CodeSystem, has no codes or mixes systems
+    /// * `CodeListError::InvalidCodeListType` - If the system is not ICD10, SNOMED or OPCS
+    /// * `CodeListError::EmptyTerm` - If a code has no display
+    pub fn from_fhir_resource(resource: &Value, metadata: Metadata, codelist_options: Option<CodeListOptions>) -> Result<CodeList, CodeListError> {
+        let resource_type = resource.get("resourceType").and_then(Value::as_str).unwrap_or_default();
+        let mut found = Vec::new();
+        match resource_type {
+            "ValueSet" => {
+                let includes = resource.pointer("/compose/include").and_then(Value::as_array).cloned().unwrap_or_default();
+                for include in &includes {
+                    let system = include.get("system").and_then(Value::as_str);
+                    let concepts = include.get("concept").and_then(Value::as_array).cloned().unwrap_or_default();
+                    collect_fhir_concepts(&concepts, system, "concept", &mut found);
+                }
+                let contains = resource.pointer("/expansion/contains").and_then(Value::as_array).cloned().unwrap_or_default();
+                collect_fhir_concepts(&contains, None, "contains", &mut found);
+            }
+            "CodeSystem" => {
+                let system = resource.get("url").and_then(Value::as_str);
+                let concepts = resource.get("concept").and_then(Value::as_array).cloned().unwrap_or_default();
+                collect_fhir_concepts(&concepts, system, "concept", &mut found);
+            }
+            other => return Err(CodeListError::invalid_input(format!("FHIR resource must be a ValueSet or CodeSystem, found: {}", other))),
+        }
+
+        let mut systems: Vec<&str> = found.iter().map(|(system, _, _)| system.as_deref().unwrap_or_default()).collect();
+        systems.sort();
+        systems.dedup();
+        let system = match systems.as_slice() {
+            [] => return Err(CodeListError::invalid_input(format!("FHIR {} contains no codes", resource_type))),
+            [system] => *system,
+            _ => return Err(CodeListError::invalid_input(format!("FHIR {} contains codes from multiple systems: {}", resource_type, systems.join(", ")))),
+        };
+        let codelist_type = fhir_system_to_type(system)?;
+
+        let mut metadata = Metadata {
+            source: MetadataSource::LoadedFromFile,
+            ..metadata
+        };
+        if let Some(version) = resource.get("version").and_then(Value::as_str) {
+            metadata.version = version.parse().ok();
+            metadata.source_version = Some(version.to_string());
+        }
+        if let Some(publisher) = resource.get("publisher").and_then(Value::as_str) {
+            metadata.authors = Some(publisher.split(',').map(|author| author.trim().to_string()).filter(|author| !author.is_empty()).collect());
+        }
+        if let Some(description) = resource.get("description").and_then(Value::as_str) {
+            metadata.description = Some(description.to_string());
+        }
+        if let Some(title) = resource.get("title").and_then(Value::as_str) {
+            metadata.title = Some(title.to_string());
+        }
+        if let Some(date) = resource.get("date").and_then(Value::as_str).and_then(parse_fhir_date_time) {
+            metadata.updated = Some(date);
+        }
+        if let Some(purpose) = resource.get("purpose").and_then(Value::as_str) {
+            metadata.intended_use = Some(purpose.to_string());
+        }
+        if let Some(copyright) = resource.get("copyright").and_then(Value::as_str) {
+            metadata.licence = Some(copyright.to_string());
+        }
+        // A ValueSet url is its canonical identifier, whereas a CodeSystem url is the code system
+        let canonical_url = (resource_type == "ValueSet").then(|| resource.get("url")).flatten();
+        if let Some(id) = resource.pointer("/identifier/0/value").or(canonical_url).and_then(Value::as_str) {
+            metadata.id = Some(id.to_string());
+        }
+
+        let mut codelist = CodeList::new(codelist_type, metadata, codelist_options);
+        if let Some(name) = resource.get("title").or_else(|| resource.get("name")).and_then(Value::as_str) {
+            codelist.set_name(name);
+        }
+        if let Some(id) = resource.get("id").and_then(Value::as_str) {
+            codelist.id = Some(id.to_string());
+        }
+        for (_, code, display) in found {
+            let term = display.unwrap_or_default();
+            if term.trim().is_empty() {
+                return Err(CodeListError::empty_term(format!("No display for code {} in FHIR {}", code, resource_type)));
+            }
+            codelist.add_entry(code.trim().to_string(), term.trim().to_string(), None)?;
+        }
+
+        let name = resource.get("url").or_else(|| resource.get("id")).and_then(Value::as_str).unwrap_or("without url");
+        codelist.add_log(format!("Converted from FHIR {} {}", resource_type, name));
+        Ok(codelist)
+    }
+}
+
+impl CodeListFactory {
+    /// Load a codelist from a FHIR R4 ValueSet or CodeSystem json file
+    ///
+    /// The codelist type is taken from the system of the codes rather than the factory. See
+    /// `CodeList::from_fhir_resource` for how the resource is read.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the json file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the file
+    /// * `CodeListError::JSONError` - If there is an error parsing the file
+    /// * See `CodeList::from_fhir_resource` for errors in the resource contents
+    pub fn load_codelist_from_fhir_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_codelist_from_fhir_reader(std::io::BufReader::new(file))?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from a FHIR R4 ValueSet or CodeSystem read from any reader
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the resource json from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_fhir_file`
+    pub fn load_codelist_from_fhir_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
+        let resource: Value = serde_json::from_reader(reader)?;
+        CodeList::from_fhir_resource(&resource, self.metadata.clone(), Some(self.codelist_options.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use tempfile::tempdir;
+
+    // Helper function to create a test codelist with two entries
+    fn create_test_codelist() -> Result<CodeList, CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), None)?;
+        codelist.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+        Ok(codelist)
+    }
+
+    #[test]
+    fn test_to_fhir_value_set_compose() -> Result<(), CodeListError> {
+        let value_set = create_test_codelist()?.to_fhir_value_set(FhirValueSetLayout::Compose);
+
+        assert_eq!(value_set, json!({
+            "resourceType": "ValueSet",
+            "status": "draft",
+            "version": "2024-12-19",
+            "publisher": "Caroline Morton",
+            "description": "A test codelist",
+            "compose": {"include": [{
+                "system": "http://snomed.info/sct",
+                "concept": [
+                    {"code": "195967001", "display": "Asthma"},
+                    {"code": "22298006", "display": "Myocardial infarction"}
+                ]
+            }]}
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_fhir_value_set_expansion() -> Result<(), CodeListError> {
+        let value_set = create_test_codelist()?.to_fhir_value_set(FhirValueSetLayout::Expansion);
+
+        assert!(value_set.get("compose").is_none());
+        assert!(value_set.pointer("/expansion/timestamp").and_then(Value::as_str).is_some());
+        assert_eq!(value_set.pointer("/expansion/total"), Some(&json!(2)));
+        assert_eq!(value_set.pointer("/expansion/contains/0"), Some(&json!({
+            "system": "http://snomed.info/sct", "code": "195967001", "display": "Asthma"
+        })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fhir_value_set_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = CodeListFactory::new(CodeListOptions::default(), create_test_metadata(), CodeListType::ICD10);
+        let mut original_codelist = create_test_codelist()?;
+        original_codelist.metadata.updated = Some("2025-01-02T16:00:00Z".parse().unwrap());
+        original_codelist.metadata.licence = Some("CC-BY-4.0".to_string());
+        original_codelist.metadata.intended_use = Some("Cardiovascular outcomes".to_string());
+        original_codelist.metadata.id = Some("https://doi.org/10.1000/182".to_string());
+
+        for layout in [FhirValueSetLayout::Compose, FhirValueSetLayout::Expansion] {
+            let file_path = temp_dir.path().join("valueset.json");
+            let file_path_str = file_path.to_str().unwrap();
+            original_codelist.save_to_fhir_value_set(file_path_str, layout)?;
+
+            let loaded_codelist = factory.load_codelist_from_fhir_file(file_path_str)?;
+            assert_eq!(loaded_codelist.entries, original_codelist.entries);
+            assert_eq!(loaded_codelist.codelist_type, CodeListType::SNOMED);
+            assert_eq!(loaded_codelist.metadata.version, original_codelist.metadata.version);
+            assert_eq!(loaded_codelist.metadata.authors, original_codelist.metadata.authors);
+            assert_eq!(loaded_codelist.metadata.description, original_codelist.metadata.description);
+            assert_eq!(loaded_codelist.metadata.updated, original_codelist.metadata.updated);
+            assert_eq!(loaded_codelist.metadata.licence, original_codelist.metadata.licence);
+            assert_eq!(loaded_codelist.metadata.intended_use, original_codelist.metadata.intended_use);
+            assert_eq!(loaded_codelist.metadata.id, original_codelist.metadata.id);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_fhir_resource_value_set() -> Result<(), CodeListError> {
+        let value_set = json!({
+            "resourceType": "ValueSet",
+            "url": "https://example.org/fhir/ValueSet/diabetes",
+            "version": "3",
+            "title": "Diabetes",
+            "date": "2024-12-19",
+            "copyright": "CC-BY-4.0",
+            "publisher": "Caroline Morton, Emma Bagshaw",
+            "compose": {"include": [{
+                "system": "http://hl7.org/fhir/sid/icd-10-uk",
+                "concept": [{"code": "E11", "display": "Type 2 diabetes mellitus"}]
+            }]},
+            "expansion": {"contains": [
+                {"system": "http://hl7.org/fhir/sid/icd-10-uk", "code": "E11", "display": "Type 2 diabetes mellitus", "contains": [
+                    {"system": "http://hl7.org/fhir/sid/icd-10-uk", "code": "E11.9", "display": "Type 2 diabetes mellitus without complications"}
+                ]}
+            ]}
+        });
+
+        let codelist = CodeList::from_fhir_resource(&value_set, create_test_metadata(), None)?;
+
+        assert_eq!(codelist.codelist_type, CodeListType::ICD10);
+        assert_eq!(codelist.entries.len(), 2);
+        assert!(codelist.entries.iter().any(|e| e.code == "E11.9"));
+        assert_eq!(codelist.metadata.version, None);
+        assert_eq!(codelist.metadata.source_version, Some("3".to_string()));
+        assert_eq!(codelist.metadata.authors, Some(vec!["Caroline Morton".to_string(), "Emma Bagshaw".to_string()]));
+        assert_eq!(codelist.metadata.description, Some("A test codelist".to_string()));
+        assert_eq!(codelist.metadata.source, MetadataSource::LoadedFromFile);
+        assert_eq!(codelist.metadata.title, Some("Diabetes".to_string()));
+        assert_eq!(codelist.metadata.updated, Some("2024-12-19T00:00:00Z".parse().unwrap()));
+        assert_eq!(codelist.metadata.licence, Some("CC-BY-4.0".to_string()));
+        assert_eq!(codelist.metadata.id, Some("https://example.org/fhir/ValueSet/diabetes".to_string()));
+        assert_eq!(codelist.logs, vec!["Converted from FHIR ValueSet https://example.org/fhir/ValueSet/diabetes".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_fhir_resource_code_system() -> Result<(), CodeListError> {
+        let code_system = json!({
+            "resourceType": "CodeSystem",
+            "url": "http://fhir.nhs.uk/Id/opcs-4",
+            "concept": [{"code": "K40", "display": "Saphenous vein graft replacement of coronary artery", "concept": [
+                {"code": "K40.1", "display": "Saphenous vein graft replacement of one coronary artery"}
+            ]}]
+        });
+
+        let codelist = CodeList::from_fhir_resource(&code_system, create_test_metadata(), None)?;
+
+        assert_eq!(codelist.codelist_type, CodeListType::OPCS);
+        assert_eq!(codelist.entries.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_fhir_resource_errors() {
+        let error = CodeList::from_fhir_resource(&json!({"resourceType": "Patient"}), create_test_metadata(), None).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "FHIR resource must be a ValueSet or CodeSystem, found: Patient"));
+
+        let error = CodeList::from_fhir_resource(&json!({"resourceType": "ValueSet"}), create_test_metadata(), None).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "FHIR ValueSet contains no codes"));
+
+        let mixed = json!({"resourceType": "ValueSet", "compose": {"include": [
+            {"system": "http://snomed.info/sct", "concept": [{"code": "22298006", "display": "Myocardial infarction"}]},
+            {"system": "http://hl7.org/fhir/sid/icd-10", "concept": [{"code": "I21", "display": "Acute myocardial infarction"}]}
+        ]}});
+        let error = CodeList::from_fhir_resource(&mixed, create_test_metadata(), None).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg.starts_with("FHIR ValueSet contains codes from multiple systems")));
+
+        let no_display = json!({"resourceType": "ValueSet", "compose": {"include": [
+            {"system": "http://snomed.info/sct", "concept": [{"code": "22298006"}]}
+        ]}});
+        let error = CodeList::from_fhir_resource(&no_display, create_test_metadata(), None).unwrap_err();
+        assert!(matches!(error, CodeListError::EmptyTerm { msg } if msg == "No display for code 22298006 in FHIR ValueSet"));
+
+        let unknown_system = json!({"resourceType": "ValueSet", "compose": {"include": [
+            {"system": "http://loinc.org", "concept": [{"code": "1234-5", "display": "Test"}]}
+        ]}});
+        let error = CodeList::from_fhir_resource(&unknown_system, create_test_metadata(), None).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeListType { name } if name == "http://loinc.org"));
+    }
+
+    #[test]
+    fn test_fhir_system_to_type() {
+        assert!(matches!(fhir_system_to_type("http://snomed.info/sct"), Ok(CodeListType::SNOMED)));
+        assert!(matches!(fhir_system_to_type("http://hl7.org/fhir/sid/icd-10"), Ok(CodeListType::ICD10)));
+        assert!(matches!(fhir_system_to_type("http://hl7.org/fhir/sid/icd-10-uk"), Ok(CodeListType::ICD10)));
+        assert!(matches!(fhir_system_to_type("http://fhir.nhs.uk/Id/opcs-4"), Ok(CodeListType::OPCS)));
+        assert!(fhir_system_to_type("http://loinc.org").is_err());
+    }
+}
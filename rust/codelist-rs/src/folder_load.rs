@@ -0,0 +1,597 @@
+//! This file contains folder loading for the codelist factory
+//!
+//! Every file found in a folder gets a result: loaded, skipped with a reason, or failed with an
+//! error, so that no codelist silently goes missing. Folders can be walked recursively and files
+//! chosen with include and exclude glob patterns. With the `parallel` cargo feature, files can
+//! also be loaded concurrently.
+
+// External imports
+use std::fmt;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::compression::has_codelist_extension;
+use crate::metadata_sidecar::is_metadata_sidecar_file;
+use crate::errors::CodeListError;
+
+/// Struct to represent the options for loading codelists from a folder
+///
+/// # Fields
+/// * `recursive` - Whether to load files in subfolders
+/// * `include` - Glob patterns of files to load; if empty, all supported files are loaded
+/// * `exclude` - Glob patterns of files and folders to leave out
+/// * `fail_fast` - Whether to stop at the first file that fails to load, rather than recording the failure and carrying on
+/// * `infer_type` - Whether to infer the type of each codelist from its file, rather than using the factory's type
+///
+/// * Patterns containing a `/` are matched against the path relative to the folder, other
+///   patterns against the file or folder name. `*` matches within a path segment, `**` matches
+///   across segments and `?` matches a single character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderLoadOptions {
+    pub recursive: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub fail_fast: bool,
+    pub infer_type: bool,
+}
+
+impl Default for FolderLoadOptions {
+    /// Default implementation for FolderLoadOptions
+    ///
+    /// # Returns
+    /// * `FolderLoadOptions` - Non-recursive, with no patterns, stopping at the first failure and using the factory's type
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            fail_fast: true,
+            infer_type: false,
+        }
+    }
+}
+
+/// Enum to represent why a file was not loaded
+///
+/// # Variants
+/// * `Directory` - The path is a folder and recursion is off
+/// * `UnsupportedExtension` - The file extension is not a supported codelist format or compression
+/// * `NotIncluded` - The path does not match any include pattern
+/// * `Excluded` - The path matches an exclude pattern
+/// * `MetadataSidecar` - The file is the metadata sidecar of a codelist file next to it, read along with that file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Directory,
+    UnsupportedExtension,
+    NotIncluded,
+    Excluded { pattern: String },
+    MetadataSidecar,
+}
+
+impl fmt::Display for SkipReason {
+    /// Format the reason as a short description
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Directory => write!(f, "is a folder and recursion is off"),
+            SkipReason::UnsupportedExtension => write!(f, "is not a supported codelist file"),
+            SkipReason::NotIncluded => write!(f, "does not match any include pattern"),
+            SkipReason::Excluded { pattern } => write!(f, "matches exclude pattern {}", pattern),
+            SkipReason::MetadataSidecar => write!(f, "is a metadata sidecar file"),
+        }
+    }
+}
+
+/// Enum to represent what happened to a file when loading a folder
+///
+/// # Variants
+/// * `Loaded` - The file was loaded into a codelist
+/// * `Skipped` - The file was not loaded, for the given reason
+/// * `Failed` - Loading the file failed with the given error
+#[derive(Debug)]
+pub enum FileLoadOutcome {
+    Loaded(Box<CodeList>),
+    Skipped(SkipReason),
+    Failed(CodeListError),
+}
+
+/// Struct to represent the result of loading one file from a folder
+///
+/// # Fields
+/// * `path` - The path to the file
+/// * `outcome` - What happened to the file
+#[derive(Debug)]
+pub struct FileLoadResult {
+    pub path: String,
+    pub outcome: FileLoadOutcome,
+}
+
+/// Struct to represent the results of loading a folder, one per file, sorted by path
+///
+/// # Fields
+/// * `results` - The result for each file
+#[derive(Debug, Default)]
+pub struct FolderLoadReport {
+    pub results: Vec<FileLoadResult>,
+}
+
+impl FolderLoadReport {
+    /// Get the codelists that were loaded
+    ///
+    /// # Returns
+    /// * `Vec<&CodeList>` - The loaded codelists
+    pub fn codelists(&self) -> Vec<&CodeList> {
+        self.results.iter()
+            .filter_map(|result| match &result.outcome {
+                FileLoadOutcome::Loaded(codelist) => Some(codelist.as_ref()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Take the codelists that were loaded, dropping the other results
+    ///
+    /// # Returns
+    /// * `Vec<CodeList>` - The loaded codelists
+    pub fn into_codelists(self) -> Vec<CodeList> {
+        self.results.into_iter()
+            .filter_map(|result| match result.outcome {
+                FileLoadOutcome::Loaded(codelist) => Some(*codelist),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the files that were skipped, with the reason
+    ///
+    /// # Returns
+    /// * `Vec<(&str, &SkipReason)>` - The paths and reasons
+    pub fn skipped(&self) -> Vec<(&str, &SkipReason)> {
+        self.results.iter()
+            .filter_map(|result| match &result.outcome {
+                FileLoadOutcome::Skipped(reason) => Some((result.path.as_str(), reason)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the files that failed to load, with the error
+    ///
+    /// # Returns
+    /// * `Vec<(&str, &CodeListError)>` - The paths and errors
+    pub fn failures(&self) -> Vec<(&str, &CodeListError)> {
+        self.results.iter()
+            .filter_map(|result| match &result.outcome {
+                FileLoadOutcome::Failed(error) => Some((result.path.as_str(), error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether any file failed to load
+    ///
+    /// # Returns
+    /// * `bool` - True if there is at least one failure
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|result| matches!(result.outcome, FileLoadOutcome::Failed(_)))
+    }
+}
+
+/// Check whether a path matches a glob pattern
+///
+/// Patterns containing a `/` are matched against the whole relative path, other patterns against
+/// its last segment. `*` matches within a segment, `**` matches across segments (including none,
+/// when written as `**/`) and `?` matches a single character other than `/`.
+///
+/// # Arguments
+/// * `pattern` - The glob pattern
+/// * `relative_path` - The path relative to the folder being loaded, with `/` separators
+///
+/// # Returns
+/// * `bool` - Whether the path matches
+pub fn glob_matches(pattern: &str, relative_path: &str) -> bool {
+    let target = if pattern.contains('/') {
+        relative_path
+    } else {
+        relative_path.rsplit('/').next().unwrap_or(relative_path)
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    glob_match_chars(&pattern, &target)
+}
+
+/// Match a glob pattern against a path, character by character
+fn glob_match_chars(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            if let ['/', after @ ..] = rest {
+                if glob_match_chars(after, path) {
+                    return true;
+                }
+            }
+            (0..=path.len()).any(|i| glob_match_chars(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| glob_match_chars(rest, &path[i..])),
+        ['?', rest @ ..] => matches!(path, [c, ..] if *c != '/') && glob_match_chars(rest, &path[1..]),
+        [c, rest @ ..] => path.first() == Some(c) && glob_match_chars(rest, &path[1..]),
+    }
+}
+
+impl CodeListFactory {
+    /// Load codelists from a folder, returning a result for every file found
+    ///
+    /// Files are visited in path order. Supported files that pass the include and exclude patterns
+    /// are loaded with `load_codelist_from_file`, or with `load_codelist_from_file_inferring_type`
+    /// when `infer_type` is set. With `fail_fast`, the first file that fails stops
+    /// the load with an error; otherwise the failure is recorded and the remaining files are loaded.
+    ///
+    /// # Arguments
+    /// * `folder_path` - The path to the folder
+    /// * `options` - The options for walking the folder
+    ///
+    /// # Returns
+    /// * `Result<FolderLoadReport, CodeListError>` - The result for each file, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading a folder
+    /// * `CodeListError::FileLoadFailed` - With `fail_fast`, if a file fails to load
+    pub fn load_codelists_from_folder_with_options(&self, folder_path: &str, options: &FolderLoadOptions) -> Result<FolderLoadReport, CodeListError> {
+        let mut report = FolderLoadReport::default();
+        self.load_folder_into_report(Path::new(folder_path), options, &mut report)?;
+        Ok(report)
+    }
+
+    /// Load codelists from a folder, loading files concurrently
+    ///
+    /// The folder is walked first, then the files to load are loaded on the rayon thread pool.
+    /// The report and errors are the same as from `load_codelists_from_folder_with_options`:
+    /// results are in path order, and with `fail_fast` the first file in path order that fails
+    /// gives the error, even if a folder after it cannot be read. Once a file has failed, files
+    /// after it are no longer loaded, though some may already have been.
+    ///
+    /// # Arguments
+    /// * `folder_path` - The path to the folder
+    /// * `options` - The options for walking the folder
+    ///
+    /// # Returns
+    /// * `Result<FolderLoadReport, CodeListError>` - The result for each file, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading a folder
+    /// * `CodeListError::FileLoadFailed` - With `fail_fast`, if a file fails to load
+    #[cfg(feature = "parallel")]
+    pub fn load_codelists_from_folder_parallel(&self, folder_path: &str, options: &FolderLoadOptions) -> Result<FolderLoadReport, CodeListError> {
+        let mut planned = Vec::new();
+        // A folder that cannot be read ends the walk, but files found before it come first
+        let walked = walk_folder(Path::new(folder_path), "", options, &mut |path, skip_reason| {
+            planned.push((path, skip_reason));
+            Ok(())
+        });
+
+        let first_failure = AtomicUsize::new(usize::MAX);
+        let results: Vec<Option<Result<FileLoadResult, CodeListError>>> = planned.into_par_iter()
+            .enumerate()
+            .map(|(index, (path, skip_reason))| {
+                let path_str = path.to_string_lossy().into_owned();
+                let outcome = match skip_reason {
+                    Some(reason) => Ok(FileLoadOutcome::Skipped(reason)),
+                    // A file before this one has failed, so its error is returned instead
+                    None if index > first_failure.load(Ordering::Relaxed) => return None,
+                    None => load_outcome(&path_str, self.load_folder_file(&path, options), options),
+                };
+                if outcome.is_err() {
+                    first_failure.fetch_min(index, Ordering::Relaxed);
+                }
+                Some(outcome.map(|outcome| FileLoadResult { path: path_str, outcome }))
+            })
+            .collect();
+
+        // Files are only left unloaded after a failure, which is returned before reaching them
+        let mut report = FolderLoadReport::default();
+        for result in results.into_iter().flatten() {
+            report.results.push(result?);
+        }
+        walked?;
+        Ok(report)
+    }
+
+    /// Load the files of a folder, and of its subfolders when recursive, into a report
+    ///
+    /// # Arguments
+    /// * `folder` - The folder to load
+    /// * `options` - The options for walking the folder
+    /// * `report` - The report to add results to
+    fn load_folder_into_report(&self, folder: &Path, options: &FolderLoadOptions, report: &mut FolderLoadReport) -> Result<(), CodeListError> {
+        walk_folder(folder, "", options, &mut |path, skip_reason| {
+            let path_str = path.to_string_lossy().into_owned();
+            let outcome = match skip_reason {
+                Some(reason) => FileLoadOutcome::Skipped(reason),
+                None => load_outcome(&path_str, self.load_folder_file(&path, options), options)?,
+            };
+            report.results.push(FileLoadResult { path: path_str, outcome });
+            Ok(())
+        })
+    }
+
+    /// Load a file found in a folder
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file
+    /// * `options` - The options for loading the folder
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    fn load_folder_file(&self, path: &Path, options: &FolderLoadOptions) -> Result<CodeList, CodeListError> {
+        match path.to_str() {
+            Some(path_utf8) if options.infer_type => self.load_codelist_from_file_inferring_type(path_utf8).map(|(codelist, _)| codelist),
+            Some(path_utf8) => self.load_codelist_from_file(path_utf8),
+            None => Err(CodeListError::invalid_file_path("Path contains invalid Unicode characters")),
+        }
+    }
+}
+
+/// Walk a folder, and its subfolders when recursive, in path order, deciding which files to load
+///
+/// # Arguments
+/// * `folder` - The folder to walk
+/// * `relative_folder` - The path of the folder relative to the top folder, empty for the top folder
+/// * `options` - The options for walking the folder
+/// * `visit` - Called with each path found, and the reason it is skipped or `None` if it is to be loaded
+fn walk_folder<F>(folder: &Path, relative_folder: &str, options: &FolderLoadOptions, visit: &mut F) -> Result<(), CodeListError>
+where
+    F: FnMut(PathBuf, Option<SkipReason>) -> Result<(), CodeListError>,
+{
+    let mut paths = std::fs::read_dir(folder)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let relative_path = if relative_folder.is_empty() { name } else { format!("{}/{}", relative_folder, name) };
+        let excluded_by = options.exclude.iter().find(|pattern| glob_matches(pattern, &relative_path));
+
+        let skip_reason = if path.is_dir() {
+            match excluded_by {
+                Some(pattern) => Some(SkipReason::Excluded { pattern: pattern.clone() }),
+                None if options.recursive => {
+                    walk_folder(&path, &relative_path, options, visit)?;
+                    continue;
+                }
+                None => Some(SkipReason::Directory),
+            }
+        } else if is_metadata_sidecar_file(&path.to_string_lossy()) {
+            Some(SkipReason::MetadataSidecar)
+        } else if !has_codelist_extension(&path.to_string_lossy()) {
+            Some(SkipReason::UnsupportedExtension)
+        } else if let Some(pattern) = excluded_by {
+            Some(SkipReason::Excluded { pattern: pattern.clone() })
+        } else if !options.include.is_empty() && !options.include.iter().any(|pattern| glob_matches(pattern, &relative_path)) {
+            Some(SkipReason::NotIncluded)
+        } else {
+            None
+        };
+        visit(path, skip_reason)?;
+    }
+    Ok(())
+}
+
+/// Turn the result of loading a file into its outcome
+///
+/// # Arguments
+/// * `path` - The path to the file
+/// * `loaded` - The result of loading the file
+/// * `options` - The options for loading the folder
+///
+/// # Returns
+/// * `Result<FileLoadOutcome, CodeListError>` - The outcome, or with `fail_fast` the error for a failed file
+fn load_outcome(path: &str, loaded: Result<CodeList, CodeListError>, options: &FolderLoadOptions) -> Result<FileLoadOutcome, CodeListError> {
+    match loaded {
+        Ok(codelist) => Ok(FileLoadOutcome::Loaded(Box::new(codelist))),
+        Err(error) if options.fail_fast => Err(CodeListError::FileLoadFailed { path: path.to_string(), source: Box::new(error) }),
+        Err(error) => Ok(FileLoadOutcome::Failed(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_codelist_factory;
+    use crate::types::CodeListType;
+    use std::fs;
+    use tempfile::tempdir;
+
+    // Helper function to create a folder with good, bad and unsupported files and a subfolder
+    fn create_test_folder(dir: &Path) -> Result<(), CodeListError> {
+        fs::write(dir.join("asthma.csv"), "code,term\nJ45,Asthma\n")?;
+        fs::write(dir.join("broken.csv"), "code,term\n,Asthma\n")?;
+        fs::write(dir.join("notes.txt"), "Some notes")?;
+        fs::write(dir.join("asthma.csv.meta.json"), r#"{"schema_version": 5, "metadata": {"source": "ManuallyCreated", "authors": ["Emma Bagshaw"]}}"#)?;
+        fs::create_dir(dir.join("archive"))?;
+        fs::write(dir.join("archive").join("old_asthma.json"), r#"[{"code": "J45", "term": "Asthma"}]"#)?;
+        Ok(())
+    }
+
+    // Helper function to get the file name and outcome kind of each result
+    fn outcomes(report: &FolderLoadReport) -> Vec<(String, String)> {
+        report.results.iter()
+            .map(|result| {
+                let name = Path::new(&result.path).file_name().unwrap().to_string_lossy().into_owned();
+                let outcome = match &result.outcome {
+                    FileLoadOutcome::Loaded(_) => "loaded".to_string(),
+                    FileLoadOutcome::Skipped(reason) => format!("skipped: {}", reason),
+                    FileLoadOutcome::Failed(_) => "failed".to_string(),
+                };
+                (name, outcome)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.csv", "asthma.csv"));
+        assert!(glob_matches("*.csv", "archive/asthma.csv"));
+        assert!(!glob_matches("*.csv", "asthma.json"));
+        assert!(glob_matches("asthma_?.csv", "asthma_1.csv"));
+        assert!(glob_matches("archive/*", "archive/asthma.csv"));
+        assert!(!glob_matches("archive/*", "archive/2020/asthma.csv"));
+        assert!(glob_matches("archive/**", "archive/2020/asthma.csv"));
+        assert!(glob_matches("**/asthma.csv", "asthma.csv"));
+        assert!(glob_matches("**/asthma.csv", "archive/2020/asthma.csv"));
+        assert!(!glob_matches("*/asthma.csv", "asthma.csv"));
+    }
+
+    #[test]
+    fn test_load_codelists_from_folder_best_effort() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_folder(temp_dir.path())?;
+        let options = FolderLoadOptions { fail_fast: false, ..FolderLoadOptions::default() };
+
+        let report = create_test_codelist_factory(CodeListType::ICD10).load_codelists_from_folder_with_options(temp_dir.path().to_str().unwrap(), &options)?;
+
+        assert_eq!(outcomes(&report), vec![
+            ("archive".to_string(), "skipped: is a folder and recursion is off".to_string()),
+            ("asthma.csv".to_string(), "loaded".to_string()),
+            ("asthma.csv.meta.json".to_string(), "skipped: is a metadata sidecar file".to_string()),
+            ("broken.csv".to_string(), "failed".to_string()),
+            ("notes.txt".to_string(), "skipped: is not a supported codelist file".to_string()),
+        ]);
+        assert_eq!(report.codelists().len(), 1);
+        assert_eq!(report.codelists()[0].metadata.authors, Some(vec!["Emma Bagshaw".to_string()]));
+        assert!(report.has_failures());
+        let failures = report.failures();
+        assert!(failures[0].0.ends_with("broken.csv"));
+        assert!(matches!(failures[0].1, CodeListError::EmptyCode { msg } if msg == "Empty code field in row: 2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelists_from_folder_fail_fast() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_folder(temp_dir.path())?;
+
+        let error = create_test_codelist_factory(CodeListType::ICD10)
+            .load_codelists_from_folder_with_options(temp_dir.path().to_str().unwrap(), &FolderLoadOptions::default())
+            .unwrap_err();
+
+        assert!(matches!(&error, CodeListError::FileLoadFailed { path, source } if path.ends_with("broken.csv") && matches!(**source, CodeListError::EmptyCode { .. })));
+        assert!(error.to_string().ends_with("broken.csv: Empty code: Empty code field in row: 2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelists_from_folder_recursive_with_patterns() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_folder(temp_dir.path())?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let folder = temp_dir.path().to_str().unwrap();
+
+        let options = FolderLoadOptions { recursive: true, exclude: vec!["broken.*".to_string()], ..FolderLoadOptions::default() };
+        let report = factory.load_codelists_from_folder_with_options(folder, &options)?;
+        assert_eq!(outcomes(&report), vec![
+            ("old_asthma.json".to_string(), "loaded".to_string()),
+            ("asthma.csv".to_string(), "loaded".to_string()),
+            ("asthma.csv.meta.json".to_string(), "skipped: is a metadata sidecar file".to_string()),
+            ("broken.csv".to_string(), "skipped: matches exclude pattern broken.*".to_string()),
+            ("notes.txt".to_string(), "skipped: is not a supported codelist file".to_string()),
+        ]);
+
+        let options = FolderLoadOptions { recursive: true, include: vec!["archive/**".to_string()], ..FolderLoadOptions::default() };
+        let report = factory.load_codelists_from_folder_with_options(folder, &options)?;
+        assert_eq!(report.codelists().len(), 1);
+        assert_eq!(report.skipped().len(), 4);
+
+        let options = FolderLoadOptions { recursive: true, exclude: vec!["archive".to_string(), "broken.csv".to_string()], ..FolderLoadOptions::default() };
+        let report = factory.load_codelists_from_folder_with_options(folder, &options)?;
+        assert_eq!(report.codelists().len(), 1);
+        assert!(matches!(report.skipped()[0].1, SkipReason::Excluded { pattern } if pattern == "archive"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_mixed_folder_inferring_types() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        fs::write(temp_dir.path().join("icd10.csv"), "icd10_code,term\nI21,Acute myocardial infarction\nI21.0,Anterior wall\n")?;
+        fs::write(temp_dir.path().join("opcs.csv"), "opcs_code,term\nK40,Saphenous vein graft\n")?;
+        fs::write(temp_dir.path().join("snomed.csv"), "code,term\n22298006,Myocardial infarction\n")?;
+
+        let options = FolderLoadOptions { infer_type: true, ..FolderLoadOptions::default() };
+        let report = create_test_codelist_factory(CodeListType::ICD10).load_codelists_from_folder_with_options(temp_dir.path().to_str().unwrap(), &options)?;
+
+        let types: Vec<CodeListType> = report.codelists().iter().map(|codelist| codelist.codelist_type.clone()).collect();
+        assert_eq!(types, vec![CodeListType::ICD10, CodeListType::OPCS, CodeListType::SNOMED]);
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_load_codelists_from_folder_parallel_matches_sequential() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_folder(temp_dir.path())?;
+        for i in 0..20 {
+            fs::write(temp_dir.path().join(format!("codelist_{:02}.csv", i)), format!("code,term\nJ{},Asthma\n", 20 + i))?;
+        }
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let folder = temp_dir.path().to_str().unwrap();
+
+        let options = FolderLoadOptions { recursive: true, fail_fast: false, ..FolderLoadOptions::default() };
+        let sequential = factory.load_codelists_from_folder_with_options(folder, &options)?;
+        let parallel = factory.load_codelists_from_folder_parallel(folder, &options)?;
+        assert_eq!(outcomes(&parallel), outcomes(&sequential));
+        assert_eq!(parallel.codelists(), sequential.codelists());
+        assert_eq!(parallel.codelists().len(), 22);
+
+        let error = factory.load_codelists_from_folder_parallel(folder, &FolderLoadOptions::default()).unwrap_err();
+        assert!(matches!(&error, CodeListError::FileLoadFailed { path, .. } if path.ends_with("broken.csv")));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_load_codelists_from_folder_parallel_matches_sequential_errors() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_folder(temp_dir.path())?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let folder = temp_dir.path().to_str().unwrap();
+        let same_error = |options: &FolderLoadOptions| -> CodeListError {
+            let sequential = factory.load_codelists_from_folder_with_options(folder, options).unwrap_err();
+            let parallel = factory.load_codelists_from_folder_parallel(folder, options).unwrap_err();
+            assert_eq!(parallel.to_string(), sequential.to_string());
+            parallel
+        };
+
+        let error = same_error(&FolderLoadOptions { recursive: true, ..FolderLoadOptions::default() });
+        assert!(matches!(&error, CodeListError::FileLoadFailed { path, .. } if path.ends_with("broken.csv")));
+        let missing = factory.load_codelists_from_folder_parallel(temp_dir.path().join("missing").to_str().unwrap(), &FolderLoadOptions::default()).unwrap_err();
+        assert!(matches!(missing, CodeListError::IOError(_)));
+
+        // A folder after broken.csv that cannot be read, unless permissions are not enforced (as for root)
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let locked = temp_dir.path().join("locked");
+            fs::create_dir(&locked)?;
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+            if fs::read_dir(&locked).is_err() {
+                let error = same_error(&FolderLoadOptions { recursive: true, ..FolderLoadOptions::default() });
+                assert!(matches!(&error, CodeListError::FileLoadFailed { path, .. } if path.ends_with("broken.csv")));
+                let error = same_error(&FolderLoadOptions { recursive: true, fail_fast: false, ..FolderLoadOptions::default() });
+                assert!(matches!(error, CodeListError::IOError(_)));
+            }
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+}
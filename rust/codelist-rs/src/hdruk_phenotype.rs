@@ -0,0 +1,470 @@
+//! This file contains the HDR UK Phenotype Library importer and exporter for the codelist
+//!
+//! A phenotype in the HDR UK Phenotype Library (https://phenotypes.healthdatagateway.org) bundles
+//! one or more concepts, each of which is a list of codes in a single coding system. Each concept
+//! maps onto one `CodeList`, with its `Metadata` filled from the phenotype record.
+
+// External imports
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_options::CodeListOptions;
+use crate::errors::CodeListError;
+use crate::metadata::{Metadata, MetadataSource};
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+
+/// Struct to represent a phenotype record from the HDR UK Phenotype Library
+///
+/// # Fields
+/// * `phenotype_id` - The phenotype ID, e.g. "PH1"
+/// * `phenotype_version_id` - The version of the phenotype
+/// * `name` - The name of the phenotype
+/// * `author` - The authors of the phenotype, separated by commas
+/// * `definition` - The definition of the phenotype
+/// * `concepts` - The concepts (codelists) in the phenotype
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HdrUkPhenotype {
+    pub phenotype_id: String,
+    #[serde(default, deserialize_with = "deserialize_optional_id", skip_serializing_if = "Option::is_none")]
+    pub phenotype_version_id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+    pub concepts: Vec<HdrUkConcept>,
+}
+
+/// Struct to represent a concept within an HDR UK phenotype
+///
+/// # Fields
+/// * `concept_id` - The concept ID, e.g. "C123"
+/// * `concept_version_id` - The version of the concept
+/// * `concept_name` - The name of the concept
+/// * `coding_system` - The name of the coding system, e.g. "ICD10 codes"
+/// * `codes` - The codes in the concept
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HdrUkConcept {
+    #[serde(default, deserialize_with = "deserialize_optional_id", skip_serializing_if = "Option::is_none")]
+    pub concept_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_id", skip_serializing_if = "Option::is_none")]
+    pub concept_version_id: Option<String>,
+    pub concept_name: String,
+    pub coding_system: String,
+    pub codes: Vec<HdrUkCode>,
+}
+
+/// Struct to represent a code within an HDR UK concept
+///
+/// # Fields
+/// * `code` - The code
+/// * `description` - The description of the code
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HdrUkCode {
+    pub code: String,
+    pub description: String,
+}
+
+/// Deserialize an optional ID that the Phenotype Library may give as either a number or a string
+fn deserialize_optional_id<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(id)) => Ok(Some(id)),
+        Some(serde_json::Value::Number(id)) => Ok(Some(id.to_string())),
+        Some(other) => Err(serde::de::Error::custom(format!("Expected a string or number ID, found: {}", other))),
+    }
+}
+
+/// Get the codelist type of a Phenotype Library coding system name
+///
+/// # Arguments
+/// * `coding_system` - The coding system name, e.g. "SNOMED CT codes"
+///
+/// # Returns
+/// * `Result<CodeListType, CodeListError>` - The codelist type or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidCodeListType` - If the coding system is not ICD10, SNOMED or OPCS
+pub fn hdruk_coding_system_to_type(coding_system: &str) -> Result<CodeListType, CodeListError> {
+    let normalised = coding_system.to_lowercase().replace(['-', ' '], "");
+    if normalised.starts_with("icd10") {
+        Ok(CodeListType::ICD10)
+    } else if normalised.starts_with("snomed") {
+        Ok(CodeListType::SNOMED)
+    } else if normalised.starts_with("opcs") {
+        Ok(CodeListType::OPCS)
+    } else {
+        Err(CodeListError::invalid_code_list_type(coding_system))
+    }
+}
+
+/// Get the Phenotype Library coding system name of a codelist type
+///
+/// # Arguments
+/// * `codelist_type` - The type of codelist
+///
+/// # Returns
+/// * `&'static str` - The coding system name
+pub fn hdruk_coding_system_name(codelist_type: &CodeListType) -> &'static str {
+    match codelist_type {
+        CodeListType::ICD10 => "ICD10 codes",
+        CodeListType::SNOMED => "SNOMED CT codes",
+        CodeListType::OPCS => "OPCS4 codes",
+    }
+}
+
+impl HdrUkPhenotype {
+    /// Convert the phenotype into one codelist per concept
+    ///
+    /// The metadata of each codelist is filled from the phenotype record: the authors from the
+    /// phenotype author, the version and source version from the phenotype version, the description from the
+    /// phenotype definition and the title from the phenotype and concept names. A log entry
+    /// records the phenotype and concept IDs.
+    ///
+    /// # Arguments
+    /// * `codelist_options` - The options to give each codelist
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidCodeListType` - If a concept uses an unsupported coding system
+    /// * `CodeListError::EmptyCode` - If a code is empty
+    /// * `CodeListError::EmptyTerm` - If a code description is empty
+    pub fn to_codelists(&self, codelist_options: &CodeListOptions) -> Result<Vec<CodeList>, CodeListError> {
+        let authors = self.author.as_ref().map(|author| {
+            author.split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect::<Vec<String>>()
+        });
+
+        self.concepts.iter().map(|concept| {
+            let codelist_type = hdruk_coding_system_to_type(&concept.coding_system)?;
+            let metadata = Metadata {
+                title: Some(format!("{} - {}", self.name, concept.concept_name)),
+                source_version: self.phenotype_version_id.clone(),
+                ..Metadata::new(
+                    MetadataSource::LoadedFromFile,
+                    authors.clone(),
+                    self.phenotype_version_id.as_deref().and_then(|version| version.parse().ok()),
+                    self.definition.clone(),
+                )
+            };
+            let mut codelist = CodeList::new(codelist_type, metadata, Some(codelist_options.clone()));
+            codelist.set_name(&concept.concept_name);
+            if let Some(concept_id) = &concept.concept_id {
+                codelist.id = Some(concept_id.clone());
+            }
+            for code in &concept.codes {
+                codelist.add_entry(code.code.trim().to_string(), code.description.trim().to_string(), None)?;
+            }
+            codelist.add_log(format!(
+                "Loaded from HDR UK phenotype {} (version: {}), concept {} (version: {})",
+                self.phenotype_id,
+                self.phenotype_version_id.as_deref().unwrap_or("unknown"),
+                concept.concept_id.as_deref().unwrap_or("unknown"),
+                concept.concept_version_id.as_deref().unwrap_or("unknown"),
+            ));
+            Ok(codelist)
+        }).collect()
+    }
+
+    /// Create a phenotype from a collection of codelists, one concept per codelist
+    ///
+    /// The author is the combined authors of the codelists and the concept names are taken from
+    /// the codelist names, or their titles if they have no name. The version is the version the
+    /// codelists share, or the source version of codelists without one, and the definition is their description if they all have the same one.
+    /// Concept IDs are left empty for the Phenotype Library to assign.
+    ///
+    /// # Arguments
+    /// * `phenotype_id` - The phenotype ID
+    /// * `name` - The name of the phenotype
+    /// * `codelists` - The codelists in the phenotype
+    ///
+    /// # Returns
+    /// * `Result<HdrUkPhenotype, CodeListError>` - The phenotype or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the codelists have different versions
+    pub fn from_codelists(phenotype_id: &str, name: &str, codelists: &[CodeList]) -> Result<HdrUkPhenotype, CodeListError> {
+        let mut authors: Vec<&String> = Vec::new();
+        for author in codelists.iter().filter_map(|codelist| codelist.metadata.authors.as_ref()).flatten() {
+            if !authors.contains(&author) {
+                authors.push(author);
+            }
+        }
+
+        let mut versions = codelists.iter().filter_map(|codelist| codelist.metadata.version_label());
+        let phenotype_version_id = versions.next();
+        if let Some(other) = versions.find(|version| Some(version) != phenotype_version_id.as_ref()) {
+            return Err(CodeListError::invalid_input(format!(
+                "Codelists in a phenotype must share a version, found: {} and {}",
+                phenotype_version_id.unwrap_or_default(),
+                other,
+            )));
+        }
+
+        let mut descriptions = codelists.iter().map(|codelist| codelist.metadata.description.as_ref());
+        let first_description = descriptions.next().flatten();
+        let definition = descriptions.all(|description| description == first_description)
+            .then(|| first_description.cloned())
+            .flatten();
+
+        let concepts = codelists.iter().enumerate().map(|(index, codelist)| HdrUkConcept {
+            concept_id: None,
+            concept_version_id: None,
+            concept_name: codelist.name.clone()
+                .or_else(|| codelist.metadata.title.clone())
+                .unwrap_or_else(|| format!("Concept {}", index + 1)),
+            coding_system: hdruk_coding_system_name(&codelist.codelist_type).to_string(),
+            codes: codelist.entries_sorted_by_code().into_iter().map(|entry| HdrUkCode {
+                code: entry.code.clone(),
+                description: entry.term.clone(),
+            }).collect(),
+        }).collect();
+
+        Ok(HdrUkPhenotype {
+            phenotype_id: phenotype_id.to_string(),
+            phenotype_version_id,
+            name: name.to_string(),
+            author: (!authors.is_empty()).then(|| authors.iter().map(|author| author.as_str()).collect::<Vec<&str>>().join(", ")),
+            definition,
+            concepts,
+        })
+    }
+}
+
+impl CodeListFactory {
+    /// Load the codelists of an HDR UK phenotype from a json file
+    ///
+    /// Each concept in the phenotype becomes one codelist, with the factory's options. The
+    /// codelist type and metadata come from the phenotype record rather than the factory.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the phenotype json file
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the file
+    /// * `CodeListError::JSONError` - If the file is not a valid phenotype record
+    /// * See `HdrUkPhenotype::to_codelists` for errors in the phenotype contents
+    pub fn load_codelists_from_hdruk_phenotype_file(&self, file_path: &str) -> Result<Vec<CodeList>, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        self.load_codelists_from_hdruk_phenotype_reader(std::io::BufReader::new(file))
+    }
+
+    /// Load the codelists of an HDR UK phenotype json record read from any reader
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the phenotype json from
+    ///
+    /// # Returns
+    /// * `Result<Vec<CodeList>, CodeListError>` - The codelists or an error
+    ///
+    /// # Errors
+    /// * See `load_codelists_from_hdruk_phenotype_file`
+    pub fn load_codelists_from_hdruk_phenotype_reader<R: std::io::Read>(&self, reader: R) -> Result<Vec<CodeList>, CodeListError> {
+        let phenotype: HdrUkPhenotype = serde_json::from_reader(reader)?;
+        phenotype.to_codelists(&self.codelist_options)
+    }
+
+    /// Save codelists as a single HDR UK phenotype json file
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the phenotype to
+    /// * `phenotype_id` - The phenotype ID
+    /// * `name` - The name of the phenotype
+    /// * `codelists` - The codelists in the phenotype
+    ///
+    /// # Returns
+    /// * `Result<(), CodeListError>` - The result of the operation
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the phenotype
+    /// * `CodeListError::InvalidInput` - If the codelists have different versions
+    pub fn save_codelists_to_hdruk_phenotype_file(&self, file_path: &str, phenotype_id: &str, name: &str, codelists: Vec<CodeList>) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_codelists_to_hdruk_phenotype_writer(&mut file, phenotype_id, name, codelists)?;
+        file.finish()
+    }
+
+    /// Write codelists as a single HDR UK phenotype json record to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the phenotype json to
+    /// * `phenotype_id` - The phenotype ID
+    /// * `name` - The name of the phenotype
+    /// * `codelists` - The codelists in the phenotype
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the phenotype
+    /// * `CodeListError::InvalidInput` - If the codelists have different versions
+    pub fn save_codelists_to_hdruk_phenotype_writer<W: std::io::Write>(&self, mut writer: W, phenotype_id: &str, name: &str, codelists: Vec<CodeList>) -> Result<(), CodeListError> {
+        let phenotype = HdrUkPhenotype::from_codelists(phenotype_id, name, &codelists)?;
+        serde_json::to_writer_pretty(&mut writer, &phenotype)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const TEST_PHENOTYPE_JSON: &str = r#"{
+        "phenotype_id": "PH1",
+        "phenotype_version_id": 2,
+        "name": "Myocardial infarction",
+        "author": "Caroline Morton, Emma Bagshaw",
+        "definition": "Acute myocardial infarction in primary and secondary care",
+        "concepts": [
+            {
+                "concept_id": "C10",
+                "concept_version_id": 4,
+                "concept_name": "MI hospital diagnosis",
+                "coding_system": "ICD10 codes",
+                "codes": [
+                    {"code": "I21", "description": "Acute myocardial infarction"},
+                    {"code": "I22", "description": "Subsequent myocardial infarction"}
+                ]
+            },
+            {
+                "concept_id": "C11",
+                "concept_version_id": 1,
+                "concept_name": "MI primary care",
+                "coding_system": "SNOMED CT codes",
+                "codes": [
+                    {"code": "22298006", "description": "Myocardial infarction"}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_load_codelists_from_hdruk_phenotype_file() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("PH1.json");
+        fs::write(&file_path, TEST_PHENOTYPE_JSON)?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelists = factory.load_codelists_from_hdruk_phenotype_file(file_path.to_str().unwrap())?;
+
+        assert_eq!(codelists.len(), 2);
+        assert_eq!(codelists[0].codelist_type, CodeListType::ICD10);
+        assert_eq!(codelists[0].entries.len(), 2);
+        assert!(codelists[0].entries.iter().any(|e| e.code == "I21" && e.term == "Acute myocardial infarction"));
+        assert_eq!(codelists[0].metadata, Metadata {
+            title: Some("Myocardial infarction - MI hospital diagnosis".to_string()),
+            source_version: Some("2".to_string()),
+            ..Metadata::new(
+                MetadataSource::LoadedFromFile,
+                Some(vec!["Caroline Morton".to_string(), "Emma Bagshaw".to_string()]),
+                None,
+                Some("Acute myocardial infarction in primary and secondary care".to_string()),
+            )
+        });
+        assert_eq!(codelists[0].logs, vec!["Loaded from HDR UK phenotype PH1 (version: 2), concept C10 (version: 4)".to_string()]);
+        assert_eq!(codelists[1].codelist_type, CodeListType::SNOMED);
+        assert_eq!(codelists[1].entries.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelists_from_hdruk_phenotype_file_unsupported_coding_system() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("PH2.json");
+        fs::write(&file_path, r#"{
+            "phenotype_id": "PH2",
+            "name": "Asthma",
+            "concepts": [
+                {"concept_name": "Asthma", "coding_system": "Read codes v2", "codes": [{"code": "H33..", "description": "Asthma"}]}
+            ]
+        }"#)?;
+
+        let error = create_test_codelist_factory(CodeListType::ICD10).load_codelists_from_hdruk_phenotype_file(file_path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeListType { name } if name == "Read codes v2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_codelists_to_hdruk_phenotype_file_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("PH1.json");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let mut codelist1 = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist1.add_entry("I21".to_string(), "Acute myocardial infarction".to_string(), None)?;
+        let mut codelist2 = CodeList::new(CodeListType::OPCS, create_test_metadata(), None);
+        codelist2.add_entry("K40".to_string(), "Saphenous vein graft replacement of coronary artery".to_string(), None)?;
+        factory.save_codelists_to_hdruk_phenotype_file(file_path_str, "PH1", "Myocardial infarction", vec![codelist1.clone(), codelist2])?;
+
+        let phenotype: HdrUkPhenotype = serde_json::from_str(&fs::read_to_string(file_path_str)?)?;
+        assert_eq!(phenotype.author, Some("Caroline Morton".to_string()));
+        assert_eq!(phenotype.phenotype_version_id, Some("2024-12-19".to_string()));
+        assert_eq!(phenotype.definition, Some("A test codelist".to_string()));
+        assert_eq!(phenotype.concepts[0].concept_name, "Concept 1");
+        assert_eq!(phenotype.concepts[1].coding_system, "OPCS4 codes");
+
+        let codelists = factory.load_codelists_from_hdruk_phenotype_file(file_path_str)?;
+        assert_eq!(codelists.len(), 2);
+        assert_eq!(codelists[0].entries, codelist1.entries);
+        assert_eq!(codelists[1].codelist_type, CodeListType::OPCS);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdruk_phenotype_round_trip_keeps_definition_and_concept_names() -> Result<(), CodeListError> {
+        let phenotype: HdrUkPhenotype = serde_json::from_str(TEST_PHENOTYPE_JSON)?;
+        let codelists = phenotype.to_codelists(&CodeListOptions::default())?;
+
+        let exported = HdrUkPhenotype::from_codelists("PH1", "Myocardial infarction", &codelists)?;
+        assert_eq!(exported.definition, phenotype.definition);
+        assert_eq!(exported.concepts[0].concept_name, "MI hospital diagnosis");
+        assert_eq!(exported.concepts[1].concept_name, "MI primary care");
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_codelists_with_different_versions() -> Result<(), CodeListError> {
+        let codelist1 = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        let mut codelist2 = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        let unversioned = CodeList::new(CodeListType::ICD10, Metadata::default(), None);
+
+        let phenotype = HdrUkPhenotype::from_codelists("PH1", "Diabetes", &[codelist1.clone(), unversioned.clone(), codelist2.clone()])?;
+        assert_eq!(phenotype.phenotype_version_id, Some("2024-12-19".to_string()));
+        assert_eq!(phenotype.definition, None);
+
+        codelist2.metadata.version = Some("1.0.0".parse()?);
+        let error = HdrUkPhenotype::from_codelists("PH1", "Diabetes", &[codelist1, unversioned, codelist2]).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Codelists in a phenotype must share a version, found: 2024-12-19 and 1.0.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdruk_coding_system_to_type() {
+        assert!(matches!(hdruk_coding_system_to_type("ICD10 codes"), Ok(CodeListType::ICD10)));
+        assert!(matches!(hdruk_coding_system_to_type("ICD-10"), Ok(CodeListType::ICD10)));
+        assert!(matches!(hdruk_coding_system_to_type("SNOMED CT codes"), Ok(CodeListType::SNOMED)));
+        assert!(matches!(hdruk_coding_system_to_type("OPCS4 codes"), Ok(CodeListType::OPCS)));
+        assert!(hdruk_coding_system_to_type("Read codes v2").is_err());
+    }
+}
@@ -4,5 +4,28 @@ pub mod errors;
 pub mod types;
 pub mod code_entry;
 pub mod metadata;
+pub mod version;
+pub mod metadata_sidecar;
 pub mod codelist_factory;
 pub mod codelist_options;
+pub mod codelist_format;
+pub mod compression;
+pub mod load_report;
+pub mod folder_load;
+pub mod bundle;
+pub mod processing;
+pub mod type_inference;
+pub mod schema;
+pub mod opencodelists;
+pub mod hdruk_phenotype;
+pub mod fhir;
+pub mod omop;
+pub mod sql;
+pub mod snippet;
+pub mod report;
+#[cfg(feature = "arrow")]
+pub mod codelist_arrow;
+#[cfg(feature = "sqlite")]
+pub mod codelist_repository;
+#[cfg(test)]
+pub(crate) mod test_utils;
@@ -0,0 +1,127 @@
+//! This file contains the load report, which records the problems found while loading a codelist
+
+// External imports
+use serde::Serialize;
+
+// Internal imports
+use crate::errors::CodeListError;
+
+/// Enum to represent the kind of problem found in a row
+///
+/// # Variants
+/// * `MalformedRow` - The row could not be parsed
+/// * `MissingValue` - The row has no value in the column
+/// * `EmptyCode` - The code is empty
+/// * `EmptyTerm` - The term is empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LoadIssueKind {
+    MalformedRow,
+    MissingValue,
+    EmptyCode,
+    EmptyTerm,
+}
+
+/// Struct to represent a problem found in a row while loading a codelist
+///
+/// # Fields
+/// * `row` - The row number, counting the header as row 1
+/// * `column` - The header of the column the problem is in, if it is in a single column
+/// * `kind` - The kind of problem
+/// * `raw_value` - The value as it appears in the file, before trimming, or the whole row as it
+///   appears in the file for a malformed row or a missing value
+/// * `message` - A description of the problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoadIssue {
+    pub row: usize,
+    pub column: Option<String>,
+    pub kind: LoadIssueKind,
+    pub raw_value: String,
+    pub message: String,
+}
+
+impl LoadIssue {
+    /// Convert the issue to the error strict loading fails with
+    ///
+    /// # Returns
+    /// * `CodeListError` - The error
+    pub fn to_error(&self) -> CodeListError {
+        match self.kind {
+            LoadIssueKind::MalformedRow => CodeListError::invalid_input(self.message.clone()),
+            LoadIssueKind::MissingValue => CodeListError::column_index_out_of_bounds(self.message.clone()),
+            LoadIssueKind::EmptyCode => CodeListError::empty_code(self.message.clone()),
+            LoadIssueKind::EmptyTerm => CodeListError::empty_term(self.message.clone()),
+        }
+    }
+}
+
+/// Struct to represent the outcome of loading a codelist
+///
+/// # Fields
+/// * `rows_read` - The number of data rows read, excluding the header
+/// * `rows_loaded` - The number of rows loaded into the codelist
+/// * `issues` - The problems found, in row order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LoadReport {
+    pub rows_read: usize,
+    pub rows_loaded: usize,
+    pub issues: Vec<LoadIssue>,
+}
+
+impl LoadReport {
+    /// Whether the file loaded without any problems
+    ///
+    /// # Returns
+    /// * `bool` - True if there are no issues
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Get the number of rows skipped because of problems
+    ///
+    /// # Returns
+    /// * `usize` - The number of skipped rows
+    pub fn rows_skipped(&self) -> usize {
+        self.rows_read - self.rows_loaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_codelist_factory;
+    use crate::types::CodeListType;
+
+    #[test]
+    fn test_load_issue_to_error() {
+        let issue = LoadIssue {
+            row: 3,
+            column: Some("code".to_string()),
+            kind: LoadIssueKind::EmptyCode,
+            raw_value: " ".to_string(),
+            message: "Empty code field in row: 3".to_string(),
+        };
+        assert!(matches!(issue.to_error(), CodeListError::EmptyCode { msg } if msg == "Empty code field in row: 3"));
+    }
+
+    #[test]
+    fn test_load_report_counts() -> Result<(), CodeListError> {
+        let report = LoadReport {
+            rows_read: 5,
+            rows_loaded: 3,
+            issues: vec![],
+        };
+        assert!(report.is_clean());
+        assert_eq!(report.rows_skipped(), 2);
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.lenient_loading = true;
+        let csv_data = "code;term\r\nA01;Test Disease 1\r\n\t;Test Disease 2\r\nC03; \r\n\"D04\"\r\n";
+        let (_, report) = factory.load_codelist_from_csv_reader_with_report(csv_data.as_bytes())?;
+        assert!(!report.is_clean());
+        assert_eq!(report.rows_read, 4);
+        assert_eq!(report.rows_skipped(), 3);
+        let raw_values: Vec<&str> = report.issues.iter().map(|issue| issue.raw_value.as_str()).collect();
+        assert_eq!(raw_values, vec!["\t", " ", "\"D04\""]);
+        Ok(())
+    }
+}
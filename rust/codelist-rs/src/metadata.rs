@@ -1,8 +1,13 @@
 //! The file contains the metadata for the codelist
 
 // External imports
+use std::fmt;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 
+// Internal imports
+use crate::version::CodeListVersion;
+
 
 /// Metadata Source Enum
 ///
@@ -15,31 +20,82 @@ pub enum MetadataSource {
 }
 
 /// Metadata Source
-impl MetadataSource {
-    /// Convert the metadata source to a string
-    pub fn to_string(&self) -> String {
-        match self {
-            MetadataSource::LoadedFromFile => "Loaded from file".to_string(),
-            MetadataSource::MappedFromAnotherCodelist => "Mapped from another codelist".to_string(),
-            MetadataSource::ManuallyCreated => "Manually created".to_string(),
-        }
+impl fmt::Display for MetadataSource {
+    /// Format the metadata source as a string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            MetadataSource::LoadedFromFile => "Loaded from file",
+            MetadataSource::MappedFromAnotherCodelist => "Mapped from another codelist",
+            MetadataSource::ManuallyCreated => "Manually created",
+        };
+        write!(f, "{}", s)
     }
 }
 
 
+/// Format a metadata timestamp, such as `created`, as RFC 3339 in UTC to the second
+///
+/// This is the format timestamps are written in outside of saved json, such as in xlsx files,
+/// reports and FHIR resources, e.g. "2024-12-19T09:30:00Z".
+///
+/// # Arguments
+/// * `timestamp` - The timestamp
+///
+/// # Returns
+/// * `String` - The formatted timestamp
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
 /// Struct to represent the metadata of a codelist
 ///
+/// Metadata can be built with `Metadata::new` and the `with_` methods, e.g.
+/// `Metadata::new(MetadataSource::ManuallyCreated, None, None, None).with_title("Asthma")`.
+///
 /// # Fields
 /// * `source` - The source of the codelist
 /// * `authors` - The authors of the codelist
-/// * `version` - The version of the codelist
+/// * `version` - The version of the codelist, a date or a semantic version
+/// * `source_version` - The version given by the source the codelist was imported from, kept as
+///   given, e.g. an HDR UK phenotype version "2" or an OpenCodelists tag or hash
 /// * `description` - The description of the codelist
+/// * `id` - A stable identifier for the codelist given by its publisher, such as a DOI or a
+///   FHIR canonical url, kept across versions and renames (unlike `CodeList::id`, which is made
+///   from the name)
+/// * `title` - The human readable title of the codelist
+/// * `created` - When the codelist was created
+/// * `updated` - When the codelist was last updated
+/// * `licence` - The licence the codelist is published under, e.g. "CC-BY-4.0"
+/// * `terminology` - The terminology edition the codelist was built against, e.g. "SNOMED CT UK Clinical Edition"
+/// * `terminology_release` - The release of the terminology the codelist was built against, e.g. "39.0.0"
+/// * `references` - References for the codelist, such as DOIs or URLs of papers that use it
+/// * `intended_use` - What the codelist is intended to be used for
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Metadata {
     pub source: MetadataSource,
     pub authors: Option<Vec<String>>,
-    pub version: Option<String>, /// @emma we can enforce this to be something with a date format
+    pub version: Option<CodeListVersion>,
+    pub source_version: Option<String>,
     pub description: Option<String>,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub licence: Option<String>,
+    pub terminology: Option<String>,
+    pub terminology_release: Option<String>,
+    pub references: Option<Vec<String>>,
+    pub intended_use: Option<String>,
+}
+
+impl Default for Metadata {
+    /// Default implementation for Metadata
+    ///
+    /// # Returns
+    /// * `Metadata` - Metadata for a manually created codelist, with nothing else recorded
+    fn default() -> Self {
+        Metadata::new(MetadataSource::ManuallyCreated, None, None, None)
+    }
 }
 
 impl Metadata {
@@ -50,15 +106,145 @@ impl Metadata {
     /// * `authors` - The authors of the codelist
     /// * `version` - The version of the codelist
     /// * `description` - The description of the codelist
-    pub fn new(source: MetadataSource, authors: Option<Vec<String>>, version: Option<String>, description: Option<String>) -> Metadata {
+    ///
+    /// The other fields start unset.
+    pub fn new(source: MetadataSource, authors: Option<Vec<String>>, version: Option<CodeListVersion>, description: Option<String>) -> Metadata {
         Metadata {
             source,
             authors,
             version,
+            source_version: None,
             description,
+            id: None,
+            title: None,
+            created: None,
+            updated: None,
+            licence: None,
+            terminology: None,
+            terminology_release: None,
+            references: None,
+            intended_use: None,
         }
     }
 
+    /// Set the version given by the source the codelist was imported from
+    ///
+    /// # Arguments
+    /// * `source_version` - The version as given by the source
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the source version set
+    pub fn with_source_version(mut self, source_version: impl Into<String>) -> Metadata {
+        self.source_version = Some(source_version.into());
+        self
+    }
+
+    /// Get the version to write when exporting the codelist
+    ///
+    /// # Returns
+    /// * `Option<String>` - The codelist version, or else the version given by the source
+    pub fn version_label(&self) -> Option<String> {
+        self.version.as_ref().map(ToString::to_string).or_else(|| self.source_version.clone())
+    }
+
+    /// Set the stable identifier of the codelist
+    ///
+    /// # Arguments
+    /// * `id` - The identifier, such as a DOI
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the identifier set
+    pub fn with_id(mut self, id: impl Into<String>) -> Metadata {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the human readable title of the codelist
+    ///
+    /// # Arguments
+    /// * `title` - The title
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the title set
+    pub fn with_title(mut self, title: impl Into<String>) -> Metadata {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set when the codelist was created
+    ///
+    /// # Arguments
+    /// * `created` - The creation time
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the creation time set
+    pub fn with_created(mut self, created: DateTime<Utc>) -> Metadata {
+        self.created = Some(created);
+        self
+    }
+
+    /// Set when the codelist was last updated
+    ///
+    /// # Arguments
+    /// * `updated` - The update time
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the update time set
+    pub fn with_updated(mut self, updated: DateTime<Utc>) -> Metadata {
+        self.updated = Some(updated);
+        self
+    }
+
+    /// Set the licence the codelist is published under
+    ///
+    /// # Arguments
+    /// * `licence` - The licence, e.g. "CC-BY-4.0"
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the licence set
+    pub fn with_licence(mut self, licence: impl Into<String>) -> Metadata {
+        self.licence = Some(licence.into());
+        self
+    }
+
+    /// Set the terminology the codelist was built against
+    ///
+    /// # Arguments
+    /// * `terminology` - The terminology edition, e.g. "SNOMED CT UK Clinical Edition"
+    /// * `release` - The release of the terminology, e.g. "39.0.0", if known
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the terminology set
+    pub fn with_terminology(mut self, terminology: impl Into<String>, release: Option<String>) -> Metadata {
+        self.terminology = Some(terminology.into());
+        self.terminology_release = release;
+        self
+    }
+
+    /// Set the references for the codelist
+    ///
+    /// # Arguments
+    /// * `references` - The references, such as DOIs or URLs
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the references set
+    pub fn with_references(mut self, references: Vec<String>) -> Metadata {
+        self.references = Some(references);
+        self
+    }
+
+    /// Set what the codelist is intended to be used for
+    ///
+    /// # Arguments
+    /// * `intended_use` - The intended use
+    ///
+    /// # Returns
+    /// * `Metadata` - The metadata with the intended use set
+    pub fn with_intended_use(mut self, intended_use: impl Into<String>) -> Metadata {
+        self.intended_use = Some(intended_use.into());
+        self
+    }
+
     /// Add an author to the metadata
     ///
     /// # Arguments
@@ -100,12 +286,36 @@ impl Metadata {
         self.description = None;
     }
 
+    /// Add a reference to the metadata
+    ///
+    /// # Arguments
+    /// * `reference` - The reference to add, such as a DOI or URL
+    pub fn add_reference(&mut self, reference: String) {
+        if let Some(references) = &mut self.references {
+            references.push(reference);
+        } else {
+            self.references = Some(vec![reference]);
+        }
+    }
+
+    /// Remove a reference from the metadata
+    ///
+    /// # Arguments
+    /// * `reference` - The reference to remove
+    pub fn remove_reference(&mut self, reference: String) {
+        if let Some(references) = &mut self.references {
+            if let Some(index) = references.iter().position(|x| x == &reference) {
+                references.remove(index);
+            }
+        }
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::CodeListError;
 
     #[test]
     fn test_metadata_source_to_string() {
@@ -119,43 +329,47 @@ mod tests {
         let metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string(), "Author 2".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
 
         assert_eq!(metadata.source, MetadataSource::LoadedFromFile);
         assert_eq!(metadata.authors, Some(vec!["Author 1".to_string(), "Author 2".to_string()]));
-        assert_eq!(metadata.version, Some("1.0.0".to_string()));
+        assert_eq!(metadata.version, Some("1.0.0".parse().unwrap()));
         assert_eq!(metadata.description, Some("This is a codelist".to_string()));
     }
 
     #[test]
     fn test_metadata_with_no_authors() {
-        let metadata = Metadata {
+        let _metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: None,
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
     }
 
     #[test]
     fn test_metadata_with_no_version() {
-        let metadata = Metadata {
+        let _metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string(), "Author 2".to_string()]),
             version: None,
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
     }
 
     #[test]
     fn test_metadata_with_no_description() {
-        let metadata = Metadata {
+        let _metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string(), "Author 2".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: None,
+            ..Metadata::default()
         };
     }
 
@@ -164,8 +378,9 @@ mod tests {
         let mut metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
 
         metadata.add_author("Author 2".to_string());
@@ -178,8 +393,9 @@ mod tests {
         let mut metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string(), "Author 2".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
 
         metadata.remove_author("Author 2".to_string());
@@ -191,8 +407,9 @@ mod tests {
         let mut metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
 
         metadata.add_description("This is a new description".to_string());
@@ -204,14 +421,66 @@ mod tests {
         let mut metadata = Metadata {
             source: MetadataSource::LoadedFromFile,
             authors: Some(vec!["Author 1".to_string()]),
-            version: Some("1.0.0".to_string()),
+            version: Some("1.0.0".parse().unwrap()),
             description: Some("This is a codelist".to_string()),
+            ..Metadata::default()
         };
 
         metadata.remove_description();
         assert_eq!(metadata.description, None);
     }
 
+    #[test]
+    fn test_add_and_remove_reference() {
+        let mut metadata = Metadata::default();
 
+        metadata.add_reference("https://doi.org/10.1000/182".to_string());
+        metadata.add_reference("https://example.org/paper".to_string());
+        assert_eq!(metadata.references, Some(vec!["https://doi.org/10.1000/182".to_string(), "https://example.org/paper".to_string()]));
 
+        metadata.remove_reference("https://doi.org/10.1000/182".to_string());
+        assert_eq!(metadata.references, Some(vec!["https://example.org/paper".to_string()]));
+    }
+
+    #[test]
+    fn test_rich_metadata_round_trip() -> Result<(), serde_json::Error> {
+        let metadata = Metadata::default()
+            .with_id("https://doi.org/10.1000/182")
+            .with_title("Type 2 diabetes")
+            .with_created("2024-12-19T09:30:00Z".parse().unwrap())
+            .with_updated("2025-01-02T16:00:00Z".parse().unwrap())
+            .with_licence("CC-BY-4.0")
+            .with_terminology("SNOMED CT UK Clinical Edition", Some("39.0.0".to_string()))
+            .with_references(vec!["https://doi.org/10.1000/182".to_string()])
+            .with_intended_use("Identifying people with type 2 diabetes in primary care records");
+        assert_eq!(metadata.title, Some("Type 2 diabetes".to_string()));
+        assert_eq!(metadata.terminology_release, Some("39.0.0".to_string()));
+
+        let json = serde_json::to_value(&metadata)?;
+        assert_eq!(json["id"], "https://doi.org/10.1000/182");
+        assert_eq!(json["created"], "2024-12-19T09:30:00Z");
+        assert_eq!(json["terminology_release"], "39.0.0");
+        assert_eq!(serde_json::from_value::<Metadata>(json)?, metadata);
+
+        let older: Metadata = serde_json::from_str(r#"{"source": "LoadedFromFile", "authors": null, "version": null, "description": null}"#)?;
+        assert_eq!(older, Metadata::new(MetadataSource::LoadedFromFile, None, None, None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_label() -> Result<(), CodeListError> {
+        let metadata = Metadata::default().with_source_version("2");
+        assert_eq!(metadata.version_label(), Some("2".to_string()));
+
+        let metadata = Metadata { version: Some("1.0.0".parse()?), ..metadata };
+        assert_eq!(metadata.version_label(), Some("1.0.0".to_string()));
+        assert_eq!(Metadata::default().version_label(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let timestamp: DateTime<Utc> = "2024-12-19T09:30:00.250+01:00".parse().unwrap();
+        assert_eq!(format_timestamp(&timestamp), "2024-12-19T08:30:00Z");
+    }
 }
@@ -0,0 +1,357 @@
+//! This file contains the metadata sidecar files that carry the metadata of csv codelists
+//!
+//! Csv files only hold the entries, so the metadata can be written to a sidecar file next to the
+//! csv, named after the whole file name: `asthma.csv.meta.json` for `asthma.csv` and
+//! `asthma.csv.gz.meta.json` for `asthma.csv.gz`. Sidecar files are written by
+//! `save_to_csv_with_metadata`, or by `save_to_csv` when the `write_metadata_sidecar` option is
+//! on. Loading a csv file from a path reads its sidecar, if there is one, in place of the factory
+//! metadata.
+//!
+//! A sidecar file holds the metadata along with the schema version of saved codelist files, as
+//! `{"schema_version": 5, "metadata": {...}}`, so sidecar files written by older versions of the
+//! library are upgraded with the same migrations. With the `yaml` feature, sidecar files can also
+//! be YAML, such as `asthma.csv.meta.yaml`. When a csv has both, the json sidecar is read.
+
+// External imports
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+
+// Internal imports
+use crate::codelist::{CodeList, CODELIST_SCHEMA_VERSION};
+use crate::errors::CodeListError;
+use crate::metadata::{Metadata, MetadataSource};
+use crate::schema::migrate_saved_json;
+
+/// Enum to represent the file formats of metadata sidecar files
+///
+/// # Variants
+/// * `Json` - A json file with a `.meta.json` extension
+/// * `Yaml` - A YAML file with a `.meta.yaml` or `.meta.yml` extension (requires the `yaml` feature)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataSidecarFormat {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl MetadataSidecarFormat {
+    /// The formats supported with the enabled features, in the order sidecar files are looked for
+    pub const ALL: &'static [MetadataSidecarFormat] = &[
+        MetadataSidecarFormat::Json,
+        #[cfg(feature = "yaml")]
+        MetadataSidecarFormat::Yaml,
+    ];
+
+    /// Get the extensions of sidecar files in the format
+    ///
+    /// # Returns
+    /// * `&'static [&'static str]` - The extensions, without the leading dot, the first being the one written
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            MetadataSidecarFormat::Json => &["meta.json"],
+            #[cfg(feature = "yaml")]
+            MetadataSidecarFormat::Yaml => &["meta.yaml", "meta.yml"],
+        }
+    }
+}
+
+/// Get the path of the metadata sidecar file of a codelist file
+///
+/// # Arguments
+/// * `file_path` - The path to the codelist file, e.g. "codelists/asthma.csv.gz"
+/// * `format` - The format of the sidecar file
+///
+/// # Returns
+/// * `PathBuf` - The path to the sidecar file, e.g. "codelists/asthma.csv.gz.meta.json"
+pub fn metadata_sidecar_path(file_path: &str, format: MetadataSidecarFormat) -> PathBuf {
+    PathBuf::from(format!("{}.{}", file_path, format.extensions()[0]))
+}
+
+/// Find the metadata sidecar file of a codelist file
+///
+/// # Arguments
+/// * `file_path` - The path to the codelist file
+///
+/// # Returns
+/// * `Option<(PathBuf, MetadataSidecarFormat)>` - The path and format of the sidecar file, if there is one
+fn find_metadata_sidecar(file_path: &str) -> Option<(PathBuf, MetadataSidecarFormat)> {
+    MetadataSidecarFormat::ALL.iter()
+        .flat_map(|format| format.extensions().iter().map(move |ext| (PathBuf::from(format!("{}.{}", file_path, ext)), *format)))
+        .find(|(sidecar_path, _)| sidecar_path.is_file())
+}
+
+/// Whether a file is the metadata sidecar file of a codelist file next to it
+///
+/// Only the name is not enough, as a codelist can itself be named something like
+/// `asthma.meta.json`, so the codelist file the sidecar is named after must also exist.
+///
+/// # Arguments
+/// * `file_path` - The path to the file
+///
+/// # Returns
+/// * `bool` - True if the file name ends in a sidecar extension after the name of an existing file
+pub fn is_metadata_sidecar_file(file_path: &str) -> bool {
+    MetadataSidecarFormat::ALL.iter()
+        .flat_map(|format| format.extensions().iter())
+        .any(|ext| {
+            let suffix_start = file_path.len().saturating_sub(ext.len() + 1);
+            let has_suffix = file_path.get(suffix_start..)
+                .is_some_and(|suffix| suffix.eq_ignore_ascii_case(&format!(".{}", ext)));
+            has_suffix && suffix_start > 0 && Path::new(&file_path[..suffix_start]).is_file()
+        })
+}
+
+/// Get the metadata from the parsed contents of a sidecar file, upgrading older schema versions
+///
+/// # Arguments
+/// * `json_data` - The parsed sidecar file
+///
+/// # Returns
+/// * `Result<Metadata, CodeListError>` - The metadata or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidInput` - If the sidecar has no valid `schema_version` field or metadata object
+/// * `CodeListError::UnsupportedSchemaVersion` - If the sidecar was written by a newer version of the library
+/// * `CodeListError::JSONError` - If the metadata is not valid
+fn metadata_from_sidecar_json(json_data: Value) -> Result<Metadata, CodeListError> {
+    let (mut upgraded, _) = migrate_saved_json(json_data)?;
+    let metadata = upgraded.get_mut("metadata")
+        .filter(|metadata| metadata.is_object())
+        .map(Value::take)
+        .ok_or_else(|| CodeListError::invalid_input("Metadata sidecar file must have a metadata object"))?;
+    Ok(serde_json::from_value(metadata)?)
+}
+
+/// Read the metadata sidecar file of a codelist file, if there is one
+///
+/// # Arguments
+/// * `file_path` - The path to the codelist file
+///
+/// # Returns
+/// * `Result<Option<Metadata>, CodeListError>` - The metadata, `None` if there is no sidecar file, or an error
+///
+/// # Errors
+/// * `CodeListError::IOError` - If the sidecar file exists but cannot be read
+/// * `CodeListError::JSONError` - If the sidecar file is not valid metadata JSON
+/// * `CodeListError::InvalidInput` - If the sidecar file has no schema version or metadata, or is not valid YAML
+/// * `CodeListError::UnsupportedSchemaVersion` - If the sidecar file was written by a newer version of the library
+pub fn read_metadata_sidecar(file_path: &str) -> Result<Option<Metadata>, CodeListError> {
+    find_metadata_sidecar(file_path)
+        .map(|(sidecar_path, format)| read_metadata_sidecar_file(&sidecar_path, format))
+        .transpose()
+}
+
+/// Read the metadata from a sidecar file
+///
+/// # Arguments
+/// * `sidecar_path` - The path to the sidecar file
+/// * `format` - The format of the sidecar file
+///
+/// # Returns
+/// * `Result<Metadata, CodeListError>` - The metadata or an error
+///
+/// # Errors
+/// * See `read_metadata_sidecar`
+fn read_metadata_sidecar_file(sidecar_path: &Path, format: MetadataSidecarFormat) -> Result<Metadata, CodeListError> {
+    let reader = BufReader::new(std::fs::File::open(sidecar_path)?);
+    let json_data: Value = match format {
+        MetadataSidecarFormat::Json => serde_json::from_reader(reader)?,
+        #[cfg(feature = "yaml")]
+        MetadataSidecarFormat::Yaml => serde_yaml_ng::from_reader(reader)
+            .map_err(|err| CodeListError::invalid_input(format!("Invalid YAML in {}: {}", sidecar_path.display(), err)))?,
+    };
+    metadata_from_sidecar_json(json_data)
+}
+
+/// Write the metadata sidecar file of a codelist file
+///
+/// # Arguments
+/// * `file_path` - The path to the codelist file
+/// * `metadata` - The metadata to write
+/// * `format` - The format of the sidecar file
+///
+/// # Errors
+/// * `CodeListError::IOError` - If the sidecar file cannot be written
+/// * `CodeListError::JSONError` - If an error occurs when serialising the metadata
+/// * `CodeListError::InvalidInput` - If an error occurs when serialising the metadata as YAML
+pub fn write_metadata_sidecar(file_path: &str, metadata: &Metadata, format: MetadataSidecarFormat) -> Result<(), CodeListError> {
+    let sidecar = json!({
+        "schema_version": CODELIST_SCHEMA_VERSION,
+        "metadata": metadata,
+    });
+    let mut writer = BufWriter::new(std::fs::File::create(metadata_sidecar_path(file_path, format))?);
+    match format {
+        MetadataSidecarFormat::Json => serde_json::to_writer_pretty(&mut writer, &sidecar)?,
+        #[cfg(feature = "yaml")]
+        MetadataSidecarFormat::Yaml => serde_yaml_ng::to_writer(&mut writer, &sidecar)
+            .map_err(|err| CodeListError::invalid_input(format!("Could not write metadata as YAML: {}", err)))?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Use the metadata from the sidecar file of a codelist file loaded from a path, if there is one
+///
+/// # Arguments
+/// * `codelist` - The codelist loaded from the file
+/// * `file_path` - The path to the codelist file
+///
+/// # Errors
+/// * See `read_metadata_sidecar`
+pub(crate) fn apply_metadata_sidecar(codelist: &mut CodeList, file_path: &str) -> Result<(), CodeListError> {
+    if let Some((sidecar_path, format)) = find_metadata_sidecar(file_path) {
+        let metadata = read_metadata_sidecar_file(&sidecar_path, format)?;
+        codelist.metadata = Metadata { source: MetadataSource::LoadedFromFile, ..metadata };
+        codelist.add_log(format!("Loaded metadata from {}", sidecar_path.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use crate::folder_load::{FolderLoadOptions, SkipReason};
+    use crate::types::CodeListType;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_metadata_sidecar_path() -> Result<(), CodeListError> {
+        assert_eq!(metadata_sidecar_path("codelists/asthma.csv", MetadataSidecarFormat::Json), PathBuf::from("codelists/asthma.csv.meta.json"));
+        assert_eq!(metadata_sidecar_path("codelists/asthma.csv.gz", MetadataSidecarFormat::Json), PathBuf::from("codelists/asthma.csv.gz.meta.json"));
+
+        let temp_dir = tempdir()?;
+        let csv_path = temp_dir.path().join("asthma.csv");
+        std::fs::write(&csv_path, "code,term\nJ45,Asthma\n")?;
+        assert!(is_metadata_sidecar_file(&format!("{}.meta.json", csv_path.display())));
+        assert!(is_metadata_sidecar_file(&format!("{}.META.JSON", csv_path.display())));
+        // Without the codelist file it is named after, the file is not a sidecar
+        assert!(!is_metadata_sidecar_file(&temp_dir.path().join("copd.csv.meta.json").to_string_lossy()));
+        assert!(!is_metadata_sidecar_file(&temp_dir.path().join("asthma.json").to_string_lossy()));
+        assert!(!is_metadata_sidecar_file(".meta.json"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_csv_writes_sidecar_only_when_asked() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("E11".to_string(), "Type 2 diabetes mellitus".to_string(), None)?;
+
+        let file_path = temp_dir.path().join("diabetes.csv");
+        let file_path = file_path.to_str().unwrap();
+        codelist.save_to_csv(file_path)?;
+        assert!(!metadata_sidecar_path(file_path, MetadataSidecarFormat::Json).exists());
+
+        codelist.codelist_options.write_metadata_sidecar = true;
+        codelist.save_to_csv(file_path)?;
+        assert!(metadata_sidecar_path(file_path, MetadataSidecarFormat::Json).is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_round_trip_with_sidecar() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("E11".to_string(), "Type 2 diabetes mellitus".to_string(), None)?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        for file_name in ["diabetes.csv", "diabetes.csv.gz"] {
+            let file_path = temp_dir.path().join(file_name);
+            let file_path = file_path.to_str().unwrap();
+            codelist.save_to_csv_with_metadata(file_path)?;
+            assert!(metadata_sidecar_path(file_path, MetadataSidecarFormat::Json).is_file());
+
+            let loaded = factory.load_codelist_from_file(file_path)?;
+            assert_eq!(loaded.metadata, Metadata { source: MetadataSource::LoadedFromFile, ..create_test_metadata() });
+            assert!(loaded.logs.last().unwrap().ends_with(&format!("{}.meta.json", file_name)));
+        }
+
+        let sidecar: Value = serde_json::from_str(&std::fs::read_to_string(temp_dir.path().join("diabetes.csv.meta.json"))?)?;
+        assert_eq!(sidecar["schema_version"], CODELIST_SCHEMA_VERSION);
+        assert_eq!(sidecar["metadata"]["authors"], json!(["Caroline Morton"]));
+
+        let loaded = factory.load_codelist_from_csv_reader(std::fs::File::open(temp_dir.path().join("diabetes.csv"))?)?;
+        assert_eq!(loaded.metadata, factory.metadata);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_without_or_with_invalid_sidecar() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("asthma.csv");
+        let file_path = file_path.to_str().unwrap();
+        let sidecar_path = metadata_sidecar_path(file_path, MetadataSidecarFormat::Json);
+        std::fs::write(file_path, "code,term\nJ45,Asthma\n")?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        assert_eq!(read_metadata_sidecar(file_path)?, None);
+        assert_eq!(factory.load_codelist_from_csv_file(file_path)?.metadata, factory.metadata);
+
+        std::fs::write(&sidecar_path, r#"{"schema_version": 5, "metadata": {"source": "ManuallyCreated", "version": "draft"}}"#)?;
+        let error = factory.load_codelist_from_csv_file(file_path).unwrap_err();
+        assert!(matches!(error, CodeListError::JSONError(_)));
+
+        std::fs::write(&sidecar_path, r#"{"schema_version": 99, "metadata": {"source": "ManuallyCreated"}}"#)?;
+        let error = factory.load_codelist_from_csv_file(file_path).unwrap_err();
+        assert!(matches!(error, CodeListError::UnsupportedSchemaVersion { version, .. } if version == "99"));
+
+        std::fs::write(&sidecar_path, r#"{"schema_version": 5}"#)?;
+        let error = factory.load_codelist_from_csv_file(file_path).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Metadata sidecar file must have a metadata object"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sidecar_from_older_schema_version() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("asthma.csv");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "code,term\nJ45,Asthma\n")?;
+        std::fs::write(metadata_sidecar_path(file_path, MetadataSidecarFormat::Json), r#"{"schema_version": 4, "metadata": {"source": "ManuallyCreated", "authors": ["Emma Bagshaw"], "version": "1.0.0", "description": null}}"#)?;
+
+        let metadata = read_metadata_sidecar(file_path)?.unwrap();
+        assert_eq!(metadata.authors, Some(vec!["Emma Bagshaw".to_string()]));
+        assert_eq!(metadata.version, Some("1.0.0".parse()?));
+        assert_eq!(metadata.id, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_folder_loads_codelist_named_like_a_sidecar() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        std::fs::write(temp_dir.path().join("asthma.csv"), "code,term\nJ45,Asthma\n")?;
+        std::fs::write(temp_dir.path().join("asthma.csv.meta.json"), r#"{"schema_version": 5, "metadata": {"source": "ManuallyCreated"}}"#)?;
+        std::fs::write(temp_dir.path().join("copd.meta.json"), r#"[{"code": "J44", "term": "COPD"}]"#)?;
+
+        let report = create_test_codelist_factory(CodeListType::ICD10).load_codelists_from_folder_with_options(temp_dir.path().to_str().unwrap(), &FolderLoadOptions::default())?;
+        assert_eq!(report.codelists().len(), 2);
+        assert_eq!(report.skipped().len(), 1);
+        assert!(report.skipped()[0].0.ends_with("asthma.csv.meta.json"));
+        assert_eq!(*report.skipped()[0].1, SkipReason::MetadataSidecar);
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_sidecar() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("asthma.csv");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "code,term\nJ45,Asthma\n")?;
+
+        write_metadata_sidecar(file_path, &create_test_metadata(), MetadataSidecarFormat::Yaml)?;
+        assert!(metadata_sidecar_path(file_path, MetadataSidecarFormat::Yaml).ends_with("asthma.csv.meta.yaml"));
+        assert!(is_metadata_sidecar_file(&format!("{}.meta.yaml", file_path)));
+        assert_eq!(read_metadata_sidecar(file_path)?, Some(create_test_metadata()));
+
+        // Hand-written sidecars can use the short extension
+        std::fs::remove_file(metadata_sidecar_path(file_path, MetadataSidecarFormat::Yaml))?;
+        std::fs::write(format!("{}.meta.yml", file_path), "schema_version: 5\nmetadata:\n  source: ManuallyCreated\n  authors:\n    - Emma Bagshaw\n")?;
+        let codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_csv_file(file_path)?;
+        assert_eq!(codelist.metadata.authors, Some(vec!["Emma Bagshaw".to_string()]));
+        assert!(codelist.logs.last().unwrap().ends_with("asthma.csv.meta.yml"));
+        Ok(())
+    }
+}
@@ -0,0 +1,836 @@
+//! This file contains the OMOP concept set support for the codelist
+//!
+//! Codelists can be read from and written to ATLAS concept set expression JSON. Because OMOP
+//! concept sets are made of standard concepts, codelists are mapped to standard `concept_id`s
+//! using a local copy of the OMOP vocabulary tables (`CONCEPT.csv` and `CONCEPT_RELATIONSHIP.csv`
+//! as downloaded from Athena), and codes that cannot be mapped are reported. The tables run to
+//! several gigabytes, so they are streamed and only the rows needed for the requested codelist
+//! types are kept.
+
+// External imports
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use csv::{ReaderBuilder, StringRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::{sniff_csv_delimiter, CodeListFactory};
+use crate::errors::CodeListError;
+use crate::metadata::{Metadata, MetadataSource};
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+
+/// Get the OMOP vocabulary ids that hold the codes of a codelist type
+///
+/// # Arguments
+/// * `codelist_type` - The type of codelist
+///
+/// # Returns
+/// * `&'static [&'static str]` - The vocabulary ids, in the order they are searched
+pub fn omop_vocabulary_ids(codelist_type: &CodeListType) -> &'static [&'static str] {
+    match codelist_type {
+        CodeListType::ICD10 => &["ICD10", "ICD10CM"],
+        CodeListType::SNOMED => &["SNOMED"],
+        CodeListType::OPCS => &["OPCS4"],
+    }
+}
+
+/// Get the codelist type of an OMOP vocabulary id
+///
+/// # Arguments
+/// * `vocabulary_id` - The OMOP vocabulary id
+///
+/// # Returns
+/// * `Result<CodeListType, CodeListError>` - The codelist type or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidCodeListType` - If the vocabulary is not ICD10, SNOMED or OPCS
+pub fn omop_vocabulary_to_type(vocabulary_id: &str) -> Result<CodeListType, CodeListError> {
+    match vocabulary_id {
+        "ICD10" | "ICD10CM" => Ok(CodeListType::ICD10),
+        "SNOMED" => Ok(CodeListType::SNOMED),
+        "OPCS4" => Ok(CodeListType::OPCS),
+        other => Err(CodeListError::invalid_code_list_type(other)),
+    }
+}
+
+/// Struct to represent an OMOP concept, named as in ATLAS concept set expressions
+///
+/// # Fields
+/// * `concept_id` - The OMOP concept id
+/// * `concept_name` - The name of the concept
+/// * `domain_id` - The domain of the concept, e.g. "Condition"
+/// * `vocabulary_id` - The vocabulary of the concept, e.g. "SNOMED"
+/// * `concept_class_id` - The class of the concept, e.g. "Clinical Finding"
+/// * `standard_concept` - "S" for standard concepts, "C" for classification concepts
+/// * `concept_code` - The code of the concept in its vocabulary
+/// * `invalid_reason` - "D" or "U" for deprecated or updated concepts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct OmopConcept {
+    pub concept_id: i64,
+    #[serde(default)]
+    pub concept_name: String,
+    #[serde(default)]
+    pub domain_id: String,
+    #[serde(default)]
+    pub vocabulary_id: String,
+    #[serde(default)]
+    pub concept_class_id: String,
+    #[serde(default)]
+    pub standard_concept: Option<String>,
+    #[serde(default)]
+    pub concept_code: String,
+    #[serde(default)]
+    pub invalid_reason: Option<String>,
+}
+
+impl OmopConcept {
+    /// Whether the concept is a valid standard concept
+    ///
+    /// # Returns
+    /// * `bool` - True if the concept is standard and has not been deprecated or updated
+    pub fn is_standard(&self) -> bool {
+        self.standard_concept.as_deref() == Some("S")
+            && matches!(self.invalid_reason.as_deref(), None | Some("") | Some("V"))
+    }
+}
+
+/// Struct to represent an item of an ATLAS concept set expression
+///
+/// # Fields
+/// * `concept` - The concept
+/// * `is_excluded` - Whether the concept is excluded from the concept set
+/// * `include_descendants` - Whether the descendants of the concept are included
+/// * `include_mapped` - Whether the concepts mapped to the concept are included
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlasConceptSetItem {
+    pub concept: OmopConcept,
+    #[serde(default)]
+    pub is_excluded: bool,
+    #[serde(default)]
+    pub include_descendants: bool,
+    #[serde(default)]
+    pub include_mapped: bool,
+}
+
+/// Struct to represent an ATLAS concept set expression
+///
+/// # Fields
+/// * `items` - The items of the concept set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtlasConceptSetExpression {
+    pub items: Vec<AtlasConceptSetItem>,
+}
+
+/// Struct to represent the OMOP vocabulary tables needed to map codes to standard concepts
+///
+/// # Fields
+/// * `concepts` - The concepts, by concept id
+/// * `concept_ids_by_code` - The concept ids, by vocabulary id and concept code
+/// * `maps_to` - The valid "Maps to" relationships, by source concept id
+#[derive(Debug, Default)]
+pub struct OmopVocabulary {
+    concepts: HashMap<i64, OmopConcept>,
+    concept_ids_by_code: HashMap<(String, String), i64>,
+    maps_to: HashMap<i64, Vec<i64>>,
+}
+
+/// Read a delimited OMOP vocabulary table row by row
+///
+/// Athena downloads are tab delimited without quoting, so quoting is only honoured for other
+/// delimiters. Headers are matched case-insensitively. The table is streamed, so only the rows
+/// that `on_row` keeps are held in memory.
+///
+/// # Arguments
+/// * `reader` - The reader to read the table from
+/// * `table_name` - The file path or name of the table, used in error messages
+/// * `columns` - The columns to read
+/// * `on_row` - Called with the values of the columns for each row
+///
+/// # Errors
+/// * `CodeListError::IOError` - If there is an error reading the table
+/// * `CodeListError::CSVError` - If there is an error parsing the table
+/// * `CodeListError::InvalidInput` - If a column is missing
+/// * Any error returned by `on_row`
+fn read_omop_table<R: Read>(reader: R, table_name: &str, columns: &[&str], mut on_row: impl FnMut(&[&str]) -> Result<(), CodeListError>) -> Result<(), CodeListError> {
+    let mut reader = BufReader::new(reader);
+    let mut header_line = String::new();
+    reader.read_line(&mut header_line)?;
+    let delimiter = sniff_csv_delimiter(&header_line);
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quoting(delimiter != b'\t')
+        .flexible(true)
+        .from_reader(header_line.as_bytes().chain(reader));
+
+    let headers = rdr.headers()?.clone();
+    let indices = columns.iter()
+        .map(|column| {
+            headers.iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(column))
+                .ok_or_else(|| CodeListError::invalid_input(format!("No {} column found in {}", column, table_name)))
+        })
+        .collect::<Result<Vec<usize>, CodeListError>>()?;
+
+    let mut record = StringRecord::new();
+    while rdr.read_record(&mut record)? {
+        let values: Vec<&str> = indices.iter().map(|i| record.get(*i).unwrap_or_default().trim()).collect();
+        on_row(&values)?;
+    }
+    Ok(())
+}
+
+/// Parse an OMOP concept id
+///
+/// # Errors
+/// * `CodeListError::InvalidInput` - If the value is not an integer
+fn parse_concept_id(value: &str) -> Result<i64, CodeListError> {
+    value.parse::<i64>().map_err(|_| CodeListError::invalid_input(format!("Invalid OMOP concept_id: {}", value)))
+}
+
+/// Create an OMOP concept from the values of the concept table columns read by `load_tables`
+///
+/// # Errors
+/// * `CodeListError::InvalidInput` - If the concept id is not an integer
+fn concept_from_row(row: &[&str]) -> Result<OmopConcept, CodeListError> {
+    let optional = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty());
+    Ok(OmopConcept {
+        concept_id: parse_concept_id(row[0])?,
+        concept_name: row[1].to_string(),
+        domain_id: row[2].to_string(),
+        vocabulary_id: row[3].to_string(),
+        concept_class_id: row[4].to_string(),
+        standard_concept: optional(row[5]),
+        concept_code: row[6].to_string(),
+        invalid_reason: optional(row[7]),
+    })
+}
+
+impl OmopVocabulary {
+    /// Load the OMOP vocabulary from `CONCEPT.csv` and `CONCEPT_RELATIONSHIP.csv` files
+    ///
+    /// Only the concepts in the vocabularies of the given codelist types, the valid "Maps to"
+    /// relationships from those concepts and the concepts they map to are kept, so that full
+    /// Athena downloads can be loaded. Codes of other codelist types will not be found.
+    ///
+    /// # Arguments
+    /// * `concept_file_path` - The path to the concept table
+    /// * `concept_relationship_file_path` - The path to the concept relationship table
+    /// * `codelist_types` - The types of the codelists that will be mapped with the vocabulary
+    ///
+    /// # Returns
+    /// * `Result<OmopVocabulary, CodeListError>` - The vocabulary or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading a file
+    /// * `CodeListError::CSVError` - If there is an error parsing a file
+    /// * `CodeListError::InvalidInput` - If a required column is missing or a concept id is not an integer
+    pub fn load_from_files(concept_file_path: &str, concept_relationship_file_path: &str, codelist_types: &[CodeListType]) -> Result<OmopVocabulary, CodeListError> {
+        OmopVocabulary::load_tables(
+            (std::fs::File::open(concept_file_path)?, concept_file_path),
+            (std::fs::File::open(concept_relationship_file_path)?, concept_relationship_file_path),
+            codelist_types,
+        )
+    }
+
+    /// Load the OMOP vocabulary from concept and concept relationship tables read from any readers
+    ///
+    /// The concept table is read twice, once for the concepts of the codelist types and once for
+    /// the standard concepts they map to, so its reader must be seekable.
+    ///
+    /// # Arguments
+    /// * `concept_reader` - The reader to read the concept table from
+    /// * `concept_relationship_reader` - The reader to read the concept relationship table from
+    /// * `codelist_types` - The types of the codelists that will be mapped with the vocabulary
+    ///
+    /// # Returns
+    /// * `Result<OmopVocabulary, CodeListError>` - The vocabulary or an error
+    ///
+    /// # Errors
+    /// * See `load_from_files`
+    pub fn load_from_readers<C: Read + Seek, R: Read>(concept_reader: C, concept_relationship_reader: R, codelist_types: &[CodeListType]) -> Result<OmopVocabulary, CodeListError> {
+        OmopVocabulary::load_tables((concept_reader, "CONCEPT"), (concept_relationship_reader, "CONCEPT_RELATIONSHIP"), codelist_types)
+    }
+
+    /// Load the OMOP vocabulary from the concept and concept relationship tables
+    ///
+    /// # Arguments
+    /// * `concept_table` - The reader and name of the concept table
+    /// * `concept_relationship_table` - The reader and name of the concept relationship table
+    /// * `codelist_types` - The types of the codelists that will be mapped with the vocabulary
+    fn load_tables<C: Read + Seek, R: Read>(concept_table: (C, &str), concept_relationship_table: (R, &str), codelist_types: &[CodeListType]) -> Result<OmopVocabulary, CodeListError> {
+        let (mut concept_reader, concept_table_name) = concept_table;
+        let vocabulary_ids: HashSet<&str> = codelist_types.iter()
+            .flat_map(omop_vocabulary_ids)
+            .copied()
+            .collect();
+        let mut vocabulary = OmopVocabulary::default();
+
+        let concept_columns = ["concept_id", "concept_name", "domain_id", "vocabulary_id", "concept_class_id", "standard_concept", "concept_code", "invalid_reason"];
+        read_omop_table(&mut concept_reader, concept_table_name, &concept_columns, |row| {
+            if vocabulary_ids.contains(row[3]) {
+                vocabulary.add_concept(concept_from_row(row)?);
+            }
+            Ok(())
+        })?;
+
+        let relationship_columns = ["concept_id_1", "concept_id_2", "relationship_id", "invalid_reason"];
+        let mut missing_concept_ids = HashSet::new();
+        read_omop_table(concept_relationship_table.0, concept_relationship_table.1, &relationship_columns, |row| {
+            if row[2] == "Maps to" && row[3].is_empty() {
+                let source_concept_id = parse_concept_id(row[0])?;
+                if vocabulary.concepts.contains_key(&source_concept_id) {
+                    let standard_concept_id = parse_concept_id(row[1])?;
+                    if !vocabulary.concepts.contains_key(&standard_concept_id) {
+                        missing_concept_ids.insert(standard_concept_id);
+                    }
+                    vocabulary.add_maps_to(source_concept_id, standard_concept_id);
+                }
+            }
+            Ok(())
+        })?;
+
+        // The standard concepts can be in any vocabulary, so the concept table is read again for them
+        if !missing_concept_ids.is_empty() {
+            concept_reader.seek(SeekFrom::Start(0))?;
+            read_omop_table(&mut concept_reader, concept_table_name, &concept_columns, |row| {
+                if parse_concept_id(row[0]).is_ok_and(|concept_id| missing_concept_ids.contains(&concept_id)) {
+                    vocabulary.add_concept(concept_from_row(row)?);
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(vocabulary)
+    }
+
+    /// Add a concept to the vocabulary
+    ///
+    /// # Arguments
+    /// * `concept` - The concept to add
+    pub fn add_concept(&mut self, concept: OmopConcept) {
+        self.concept_ids_by_code.insert((concept.vocabulary_id.clone(), concept.concept_code.clone()), concept.concept_id);
+        self.concepts.insert(concept.concept_id, concept);
+    }
+
+    /// Add a "Maps to" relationship to the vocabulary
+    ///
+    /// # Arguments
+    /// * `source_concept_id` - The concept id that is mapped
+    /// * `standard_concept_id` - The concept id it maps to
+    pub fn add_maps_to(&mut self, source_concept_id: i64, standard_concept_id: i64) {
+        self.maps_to.entry(source_concept_id).or_default().push(standard_concept_id);
+    }
+
+    /// Get a concept by its id
+    ///
+    /// # Arguments
+    /// * `concept_id` - The concept id
+    ///
+    /// # Returns
+    /// * `Option<&OmopConcept>` - The concept, if it is in the vocabulary
+    pub fn concept(&self, concept_id: i64) -> Option<&OmopConcept> {
+        self.concepts.get(&concept_id)
+    }
+
+    /// Find the concept for a code of a codelist type
+    ///
+    /// ICD10 codes written without a dot (e.g. "I210") are also looked up in the dotted form
+    /// used by OMOP (e.g. "I21.0").
+    ///
+    /// # Arguments
+    /// * `codelist_type` - The type of codelist the code belongs to
+    /// * `code` - The code
+    ///
+    /// # Returns
+    /// * `Option<&OmopConcept>` - The concept, if it is in the vocabulary
+    pub fn find_concept(&self, codelist_type: &CodeListType, code: &str) -> Option<&OmopConcept> {
+        let mut candidates = vec![code.to_string()];
+        if *codelist_type == CodeListType::ICD10 && code.len() > 3 && !code.contains('.') && code.is_char_boundary(3) {
+            candidates.push(format!("{}.{}", &code[..3], &code[3..]));
+        }
+        omop_vocabulary_ids(codelist_type).iter()
+            .flat_map(|vocabulary_id| candidates.iter().map(move |candidate| (vocabulary_id.to_string(), candidate.clone())))
+            .find_map(|key| self.concept_ids_by_code.get(&key))
+            .and_then(|concept_id| self.concepts.get(concept_id))
+    }
+
+    /// Get the standard concepts for a concept
+    ///
+    /// A standard concept maps to itself; other concepts follow their "Maps to" relationships.
+    ///
+    /// # Arguments
+    /// * `concept` - The concept
+    ///
+    /// # Returns
+    /// * `Vec<&OmopConcept>` - The standard concepts, which may be empty
+    pub fn standard_concepts<'a>(&'a self, concept: &'a OmopConcept) -> Vec<&'a OmopConcept> {
+        if concept.is_standard() {
+            return vec![concept];
+        }
+        self.maps_to.get(&concept.concept_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|concept_id| self.concepts.get(concept_id))
+            .filter(|standard| standard.is_standard())
+            .collect()
+    }
+}
+
+/// Enum to represent why a code could not be mapped to a standard concept
+///
+/// # Variants
+/// * `NotInVocabulary` - The code is not in the vocabulary
+/// * `NoStandardConcept` - The code is in the vocabulary but does not map to a standard concept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OmopUnmappedReason {
+    NotInVocabulary,
+    NoStandardConcept,
+}
+
+/// Struct to represent a code mapped to a standard concept
+///
+/// # Fields
+/// * `code` - The code in the codelist
+/// * `term` - The term in the codelist
+/// * `source_concept_id` - The concept id of the code
+/// * `standard_concept` - The standard concept the code maps to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OmopMappedCode {
+    pub code: String,
+    pub term: String,
+    pub source_concept_id: i64,
+    pub standard_concept: OmopConcept,
+}
+
+/// Struct to represent a code that could not be mapped to a standard concept
+///
+/// # Fields
+/// * `code` - The code in the codelist
+/// * `term` - The term in the codelist
+/// * `reason` - Why the code could not be mapped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OmopUnmappedCode {
+    pub code: String,
+    pub term: String,
+    pub reason: OmopUnmappedReason,
+}
+
+/// Struct to represent the result of mapping a codelist to OMOP standard concepts
+///
+/// # Fields
+/// * `mapped` - The mapped codes, one per standard concept a code maps to, sorted by code
+/// * `unmapped` - The codes that could not be mapped, sorted by code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OmopMappingReport {
+    pub mapped: Vec<OmopMappedCode>,
+    pub unmapped: Vec<OmopUnmappedCode>,
+}
+
+impl OmopMappingReport {
+    /// Get the standard concept ids the codelist maps to
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - The sorted, deduplicated concept ids
+    pub fn standard_concept_ids(&self) -> Vec<i64> {
+        let mut concept_ids: Vec<i64> = self.mapped.iter().map(|m| m.standard_concept.concept_id).collect();
+        concept_ids.sort();
+        concept_ids.dedup();
+        concept_ids
+    }
+
+    /// Convert the mapped standard concepts to an ATLAS concept set expression
+    ///
+    /// Descendants are not included, so the concept set matches the codelist exactly.
+    ///
+    /// # Returns
+    /// * `AtlasConceptSetExpression` - The concept set expression
+    pub fn to_atlas_concept_set_expression(&self) -> AtlasConceptSetExpression {
+        let mut concepts: Vec<&OmopConcept> = self.mapped.iter().map(|m| &m.standard_concept).collect();
+        concepts.sort_by_key(|concept| concept.concept_id);
+        concepts.dedup_by_key(|concept| concept.concept_id);
+        AtlasConceptSetExpression {
+            items: concepts.into_iter()
+                .map(|concept| AtlasConceptSetItem {
+                    concept: concept.clone(),
+                    is_excluded: false,
+                    include_descendants: false,
+                    include_mapped: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Save the unmapped codes to a csv file with code, term and reason columns
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the report to
+    ///
+    /// # Errors
+    /// * `CodeListError::CSVError` - If an error occurs when writing the file
+    pub fn save_unmapped_to_csv(&self, file_path: &str) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_unmapped_to_csv_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the unmapped codes as csv to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the report to
+    ///
+    /// # Errors
+    /// * `CodeListError::CSVError` - If an error occurs when writing
+    pub fn save_unmapped_to_csv_writer<W: Write>(&self, writer: W) -> Result<(), CodeListError> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        for unmapped in &self.unmapped {
+            wtr.serialize(unmapped)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+impl CodeList {
+    /// Map the codelist to OMOP standard concepts
+    ///
+    /// # Arguments
+    /// * `vocabulary` - The OMOP vocabulary to map with
+    ///
+    /// # Returns
+    /// * `OmopMappingReport` - The mapped and unmapped codes
+    pub fn map_to_omop(&self, vocabulary: &OmopVocabulary) -> OmopMappingReport {
+        let mut report = OmopMappingReport::default();
+        for entry in self.entries_sorted_by_code() {
+            let Some(source_concept) = vocabulary.find_concept(&self.codelist_type, &entry.code) else {
+                report.unmapped.push(OmopUnmappedCode {
+                    code: entry.code.clone(),
+                    term: entry.term.clone(),
+                    reason: OmopUnmappedReason::NotInVocabulary,
+                });
+                continue;
+            };
+            let standard_concepts = vocabulary.standard_concepts(source_concept);
+            if standard_concepts.is_empty() {
+                report.unmapped.push(OmopUnmappedCode {
+                    code: entry.code.clone(),
+                    term: entry.term.clone(),
+                    reason: OmopUnmappedReason::NoStandardConcept,
+                });
+            }
+            for standard_concept in standard_concepts {
+                report.mapped.push(OmopMappedCode {
+                    code: entry.code.clone(),
+                    term: entry.term.clone(),
+                    source_concept_id: source_concept.concept_id,
+                    standard_concept: standard_concept.clone(),
+                });
+            }
+        }
+        report
+    }
+
+    /// Map the codelist to OMOP standard concepts and save them as an ATLAS concept set expression
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the concept set expression to
+    /// * `vocabulary` - The OMOP vocabulary to map with
+    ///
+    /// # Returns
+    /// * `Result<OmopMappingReport, CodeListError>` - The mapping report, including unmapped codes, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the expression
+    pub fn save_to_atlas_concept_set(&self, file_path: &str, vocabulary: &OmopVocabulary) -> Result<OmopMappingReport, CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        let report = self.save_to_atlas_concept_set_writer(&mut file, vocabulary)?;
+        file.finish()?;
+        Ok(report)
+    }
+
+    /// Map the codelist to OMOP standard concepts and write them as an ATLAS concept set
+    /// expression to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the concept set expression to
+    /// * `vocabulary` - The OMOP vocabulary to map with
+    ///
+    /// # Returns
+    /// * `Result<OmopMappingReport, CodeListError>` - The mapping report, including unmapped codes, or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the expression
+    pub fn save_to_atlas_concept_set_writer<W: Write>(&self, mut writer: W, vocabulary: &OmopVocabulary) -> Result<OmopMappingReport, CodeListError> {
+        let report = self.map_to_omop(vocabulary);
+        serde_json::to_writer_pretty(&mut writer, &report.to_atlas_concept_set_expression())?;
+        writer.flush()?;
+        Ok(report)
+    }
+}
+
+impl CodeListFactory {
+    /// Load a codelist from an ATLAS concept set expression json file
+    ///
+    /// Both a bare expression (`{"items": [...]}`) and an exported concept set with an
+    /// `expression` field are accepted. Excluded concepts are skipped and descendants are not
+    /// expanded; both are recorded in the logs. The codelist type is taken from the vocabulary
+    /// of the concepts and each entry's comment records its OMOP concept id.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the json file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the file
+    /// * `CodeListError::JSONError` - If the file is not a concept set expression
+    /// * `CodeListError::InvalidInput` - If there are no included concepts or they come from several vocabularies
+    /// * `CodeListError::InvalidCodeListType` - If the vocabulary is not ICD10, SNOMED or OPCS
+    pub fn load_codelist_from_atlas_concept_set_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_atlas_concept_set(std::io::BufReader::new(file), Some(file_path))?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from an ATLAS concept set expression read from any reader
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the concept set json from
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_atlas_concept_set_file`
+    pub fn load_codelist_from_atlas_concept_set_reader<R: Read>(&self, reader: R) -> Result<CodeList, CodeListError> {
+        self.load_atlas_concept_set(reader, None)
+    }
+
+    /// Load a codelist from ATLAS concept set json, recording its source in the logs
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the concept set json from
+    /// * `file_path` - The path the json was read from, if any
+    fn load_atlas_concept_set<R: Read>(&self, reader: R, file_path: Option<&str>) -> Result<CodeList, CodeListError> {
+        let mut json_data: Value = serde_json::from_reader(reader)?;
+        let name = json_data.get("name").and_then(Value::as_str).map(str::to_string);
+        let id = json_data.get("id").and_then(|id| id.as_i64().map(|id| id.to_string()).or_else(|| id.as_str().map(str::to_string)));
+        if let Some(expression) = json_data.get_mut("expression") {
+            json_data = expression.take();
+        }
+        let expression: AtlasConceptSetExpression = serde_json::from_value(json_data)?;
+
+        let (excluded, included): (Vec<_>, Vec<_>) = expression.items.into_iter().partition(|item| item.is_excluded);
+        let mut vocabulary_ids: Vec<&str> = included.iter().map(|item| item.concept.vocabulary_id.as_str()).collect();
+        vocabulary_ids.sort();
+        vocabulary_ids.dedup();
+        let codelist_type = match vocabulary_ids.as_slice() {
+            [] => return Err(CodeListError::invalid_input("ATLAS concept set expression contains no included concepts")),
+            [vocabulary_id] => omop_vocabulary_to_type(vocabulary_id)?,
+            _ => return Err(CodeListError::invalid_input(format!("ATLAS concept set expression contains concepts from multiple vocabularies: {}", vocabulary_ids.join(", ")))),
+        };
+
+        let metadata = Metadata {
+            source: MetadataSource::LoadedFromFile,
+            ..self.metadata.clone()
+        };
+        let mut codelist = CodeList::new(codelist_type, metadata, Some(self.codelist_options.clone()));
+        if let Some(name) = &name {
+            codelist.set_name(name);
+        }
+        if id.is_some() {
+            codelist.id = id;
+        }
+        for item in &included {
+            let concept = &item.concept;
+            codelist.add_entry(concept.concept_code.clone(), concept.concept_name.clone(), Some(format!("OMOP concept_id: {}", concept.concept_id)))?;
+        }
+
+        codelist.add_log(match file_path {
+            Some(file_path) => format!("Loaded from ATLAS concept set expression {}", file_path),
+            None => "Loaded from ATLAS concept set expression".to_string(),
+        });
+        if !excluded.is_empty() {
+            codelist.add_log(format!("Skipped {} excluded concepts", excluded.len()));
+        }
+        let with_descendants = included.iter().filter(|item| item.include_descendants).count();
+        if with_descendants > 0 {
+            codelist.add_log(format!("{} concepts include descendants, which were not expanded", with_descendants));
+        }
+        Ok(codelist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use std::fs;
+    use tempfile::tempdir;
+
+    // Helper function to write a small tab delimited OMOP vocabulary and load it
+    fn create_test_vocabulary(dir: &std::path::Path) -> Result<OmopVocabulary, CodeListError> {
+        let concept_path = dir.join("CONCEPT.csv");
+        fs::write(&concept_path, "concept_id\tconcept_name\tdomain_id\tvocabulary_id\tconcept_class_id\tstandard_concept\tconcept_code\tvalid_start_date\tvalid_end_date\tinvalid_reason\n\
+            4329847\tMyocardial infarction\tCondition\tSNOMED\tClinical Finding\tS\t22298006\t19700101\t20991231\t\n\
+            317009\tAsthma\tCondition\tSNOMED\tClinical Finding\tS\t195967001\t19700101\t20991231\t\n\
+            45591467\tAcute transmural myocardial infarction of anterior wall\tCondition\tICD10\tICD10 code\t\tI21.0\t19700101\t20991231\t\n\
+            45534422\tAcute myocardial infarction\tCondition\tICD10\tICD10 Hierarchy\t\tI21\t19700101\t20991231\t\n")?;
+        let relationship_path = dir.join("CONCEPT_RELATIONSHIP.csv");
+        fs::write(&relationship_path, "concept_id_1\tconcept_id_2\trelationship_id\tvalid_start_date\tvalid_end_date\tinvalid_reason\n\
+            45591467\t4329847\tMaps to\t19700101\t20991231\t\n\
+            45534422\t317009\tMaps to\t19700101\t20991231\tD\n")?;
+        OmopVocabulary::load_from_files(concept_path.to_str().unwrap(), relationship_path.to_str().unwrap(), &[CodeListType::ICD10, CodeListType::SNOMED])
+    }
+
+    #[test]
+    fn test_load_vocabulary() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let vocabulary = create_test_vocabulary(temp_dir.path())?;
+
+        let concept = vocabulary.concept(4329847).unwrap();
+        assert_eq!(concept.concept_code, "22298006");
+        assert!(concept.is_standard());
+        assert_eq!(vocabulary.concept(45591467).unwrap().standard_concept, None);
+        assert_eq!(vocabulary.find_concept(&CodeListType::ICD10, "I210").map(|c| c.concept_id), Some(45591467));
+        assert_eq!(vocabulary.find_concept(&CodeListType::ICD10, "I21.0").map(|c| c.concept_id), Some(45591467));
+        assert!(vocabulary.find_concept(&CodeListType::SNOMED, "I21.0").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_vocabulary_keeps_only_rows_for_codelist_types() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        create_test_vocabulary(temp_dir.path())?;
+        let concept_table = fs::read(temp_dir.path().join("CONCEPT.csv"))?;
+        let relationship_table = fs::read(temp_dir.path().join("CONCEPT_RELATIONSHIP.csv"))?;
+
+        let vocabulary = OmopVocabulary::load_from_readers(std::io::Cursor::new(concept_table), relationship_table.as_slice(), &[CodeListType::ICD10])?;
+
+        // The SNOMED concept an ICD10 code maps to is kept, other SNOMED concepts are not
+        let source_concept = vocabulary.find_concept(&CodeListType::ICD10, "I21.0").unwrap();
+        assert_eq!(vocabulary.standard_concepts(source_concept)[0].concept_id, 4329847);
+        assert!(vocabulary.concept(317009).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_vocabulary_missing_column() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let concept_path = temp_dir.path().join("CONCEPT.csv");
+        fs::write(&concept_path, "concept_id\tconcept_name\n1\tTest\n")?;
+        let path_str = concept_path.to_str().unwrap();
+
+        let error = OmopVocabulary::load_from_files(path_str, path_str, &[CodeListType::ICD10]).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == format!("No domain_id column found in {}", path_str)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_to_omop() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let vocabulary = create_test_vocabulary(temp_dir.path())?;
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("I210".to_string(), "Acute transmural myocardial infarction of anterior wall".to_string(), None)?;
+        codelist.add_entry("I21".to_string(), "Acute myocardial infarction".to_string(), None)?;
+        codelist.add_entry("A01".to_string(), "Typhoid and paratyphoid fevers".to_string(), None)?;
+
+        let report = codelist.map_to_omop(&vocabulary);
+
+        assert_eq!(report.mapped.len(), 1);
+        assert_eq!(report.mapped[0].code, "I210");
+        assert_eq!(report.mapped[0].source_concept_id, 45591467);
+        assert_eq!(report.standard_concept_ids(), vec![4329847]);
+        assert_eq!(report.unmapped, vec![
+            OmopUnmappedCode { code: "A01".to_string(), term: "Typhoid and paratyphoid fevers".to_string(), reason: OmopUnmappedReason::NotInVocabulary },
+            OmopUnmappedCode { code: "I21".to_string(), term: "Acute myocardial infarction".to_string(), reason: OmopUnmappedReason::NoStandardConcept },
+        ]);
+
+        let report_path = temp_dir.path().join("unmapped.csv");
+        report.save_unmapped_to_csv(report_path.to_str().unwrap())?;
+        assert_eq!(fs::read_to_string(report_path)?, "code,term,reason\nA01,Typhoid and paratyphoid fevers,NotInVocabulary\nI21,Acute myocardial infarction,NoStandardConcept\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_atlas_concept_set_round_trip() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let vocabulary = create_test_vocabulary(temp_dir.path())?;
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), None)?;
+        codelist.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+
+        let file_path = temp_dir.path().join("concept_set.json");
+        let file_path_str = file_path.to_str().unwrap();
+        let report = codelist.save_to_atlas_concept_set(file_path_str, &vocabulary)?;
+        assert!(report.unmapped.is_empty());
+
+        let json: Value = serde_json::from_str(&fs::read_to_string(&file_path)?)?;
+        assert_eq!(json["items"][0]["concept"]["CONCEPT_ID"], 317009);
+        assert_eq!(json["items"][0]["concept"]["VOCABULARY_ID"], "SNOMED");
+        assert_eq!(json["items"][0]["includeDescendants"], false);
+
+        let loaded_codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_atlas_concept_set_file(file_path_str)?;
+        assert_eq!(loaded_codelist.codelist_type, CodeListType::SNOMED);
+        assert_eq!(loaded_codelist.entries.len(), 2);
+        assert!(loaded_codelist.entries.iter().any(|e| e.code == "195967001" && e.comment == Some("OMOP concept_id: 317009".to_string())));
+        assert_eq!(loaded_codelist.logs, vec![format!("Loaded from ATLAS concept set expression {}", file_path_str)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_atlas_concept_set_with_wrapper_and_flags() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("concept_set.json");
+        fs::write(&file_path, r#"{"id": 12, "name": "Asthma", "expression": {"items": [
+            {"concept": {"CONCEPT_ID": 317009, "CONCEPT_NAME": "Asthma", "STANDARD_CONCEPT": "S", "STANDARD_CONCEPT_CAPTION": "Standard", "CONCEPT_CODE": "195967001", "VOCABULARY_ID": "SNOMED"}, "includeDescendants": true},
+            {"concept": {"CONCEPT_ID": 4051466, "CONCEPT_NAME": "Childhood asthma", "CONCEPT_CODE": "233678006", "VOCABULARY_ID": "SNOMED"}, "isExcluded": true}
+        ]}}"#)?;
+
+        let codelist = create_test_codelist_factory(CodeListType::ICD10).load_codelist_from_atlas_concept_set_file(file_path.to_str().unwrap())?;
+
+        assert_eq!(codelist.entries.len(), 1);
+        assert_eq!(codelist.metadata.source, MetadataSource::LoadedFromFile);
+        assert_eq!(codelist.logs[1..], ["Skipped 1 excluded concepts".to_string(), "1 concepts include descendants, which were not expanded".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_atlas_concept_set_errors() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let file_path = temp_dir.path().join("concept_set.json");
+        let file_path_str = file_path.to_str().unwrap();
+
+        fs::write(&file_path, r#"{"items": []}"#)?;
+        let error = factory.load_codelist_from_atlas_concept_set_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "ATLAS concept set expression contains no included concepts"));
+
+        fs::write(&file_path, r#"{"items": [
+            {"concept": {"CONCEPT_ID": 317009, "CONCEPT_NAME": "Asthma", "CONCEPT_CODE": "195967001", "VOCABULARY_ID": "SNOMED"}},
+            {"concept": {"CONCEPT_ID": 45534422, "CONCEPT_NAME": "Acute myocardial infarction", "CONCEPT_CODE": "I21", "VOCABULARY_ID": "ICD10"}}
+        ]}"#)?;
+        let error = factory.load_codelist_from_atlas_concept_set_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "ATLAS concept set expression contains concepts from multiple vocabularies: ICD10, SNOMED"));
+
+        fs::write(&file_path, r#"{"items": [{"concept": {"CONCEPT_ID": 1, "CONCEPT_NAME": "Aspirin", "CONCEPT_CODE": "1191", "VOCABULARY_ID": "RxNorm"}}]}"#)?;
+        let error = factory.load_codelist_from_atlas_concept_set_file(file_path_str).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidCodeListType { name } if name == "RxNorm"));
+
+        Ok(())
+    }
+}
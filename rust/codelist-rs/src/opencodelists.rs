@@ -0,0 +1,397 @@
+//! This file contains the OpenCodelists importer and exporters for the codelist
+//!
+//! OpenCodelists (https://www.opencodelists.org) CSV downloads use different column headers
+//! depending on the coding system, and carry the codelist version in the file name, for example
+//! `opensafely-asthma-diagnosis-2020-04-15.csv`.
+
+// External imports
+use std::io::{Read, Write};
+use std::path::Path;
+use serde::Serialize;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::errors::CodeListError;
+use crate::metadata::MetadataSource;
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+
+/// The code and term column headers used by OpenCodelists CSV downloads
+///
+/// * `code`/`term` - SNOMED CT, ICD-10 and OPCS-4 codelists
+/// * `dmd_id`/`dmd_name` - dm+d codelists
+/// * `icd10_code`/`description` - ICD-10 codelists converted from older sources
+pub const OPENCODELISTS_CSV_LAYOUTS: [(&str, &str); 3] = [
+    ("code", "term"),
+    ("dmd_id", "dmd_name"),
+    ("icd10_code", "description"),
+];
+
+/// Struct to represent the JSON payload used to create a codelist on OpenCodelists
+///
+/// # Fields
+/// * `name` - The name of the codelist
+/// * `coding_system_id` - The OpenCodelists identifier of the coding system
+/// * `codes` - The codes in the codelist
+/// * `tag` - The version tag of the codelist
+/// * `description` - The description of the codelist
+#[derive(Debug, Serialize)]
+struct OpenCodelistsPayload<'a> {
+    name: &'a str,
+    coding_system_id: &'static str,
+    codes: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+/// Get the OpenCodelists identifier of a coding system
+///
+/// # Arguments
+/// * `codelist_type` - The type of codelist
+///
+/// # Returns
+/// * `&'static str` - The OpenCodelists coding system identifier
+pub fn opencodelists_coding_system_id(codelist_type: &CodeListType) -> &'static str {
+    match codelist_type {
+        CodeListType::ICD10 => "icd10",
+        CodeListType::SNOMED => "snomedct",
+        CodeListType::OPCS => "opcs4",
+    }
+}
+
+/// Get the version of an OpenCodelists download from its file name
+///
+/// Versions are either a date (`2020-04-15`) or an 8 character hexadecimal hash (`5ec2d4d6`)
+/// at the end of the file stem.
+///
+/// # Arguments
+/// * `file_path` - The path to the downloaded file
+///
+/// # Returns
+/// * `Option<String>` - The version, if the file name contains one
+pub fn opencodelists_version_from_file_name(file_path: &str) -> Option<String> {
+    let stem = Path::new(file_path).file_stem()?.to_str()?;
+
+    if stem.len() > 10 && stem.is_char_boundary(stem.len() - 10) {
+        let (rest, date) = stem.split_at(stem.len() - 10);
+        let is_date = date.char_indices().all(|(idx, c)| match idx {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+        if is_date && rest.ends_with('-') {
+            return Some(date.to_string());
+        }
+    }
+
+    let (_, hash) = stem.rsplit_once('-')?;
+    if hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(hash.to_string());
+    }
+    None
+}
+
+impl CodeListFactory {
+    /// Load a codelist from an OpenCodelists CSV download
+    ///
+    /// The code and term columns are recognised from the known OpenCodelists layouts, ignoring
+    /// the column names in the codelist options. The version is taken from the file name when
+    /// present and a log entry records where the codelist came from.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the csv file
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the headers do not match a known OpenCodelists layout
+    /// * See `load_codelist_from_csv_file` for errors reading the file
+    pub fn load_codelist_from_opencodelists_csv_file(&self, file_path: &str) -> Result<CodeList, CodeListError> {
+        let file = std::fs::File::open(file_path)?;
+        let mut codelist = self.load_opencodelists_csv(file, opencodelists_version_from_file_name(file_path), Some(file_path))?;
+        codelist.set_name_from_file_path(file_path);
+        Ok(codelist)
+    }
+
+    /// Load a codelist from an OpenCodelists CSV download read from any reader
+    ///
+    /// As there is no file name to take the version from, it can be given directly.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the csv data from
+    /// * `version` - The version of the codelist, if known
+    ///
+    /// # Returns
+    /// * `Result<CodeList, CodeListError>` - The codelist or an error
+    ///
+    /// # Errors
+    /// * See `load_codelist_from_opencodelists_csv_file`
+    pub fn load_codelist_from_opencodelists_csv_reader<R: Read>(&self, reader: R, version: Option<&str>) -> Result<CodeList, CodeListError> {
+        self.load_opencodelists_csv(reader, version.map(str::to_string), None)
+    }
+
+    /// Load a codelist from OpenCodelists csv data, recording its source in the logs
+    ///
+    /// # Arguments
+    /// * `reader` - The reader to read the csv data from
+    /// * `version` - The version of the codelist, if known
+    /// * `file_path` - The path the data was read from, if any
+    fn load_opencodelists_csv<R: Read>(&self, mut reader: R, version: Option<String>, file_path: Option<&str>) -> Result<CodeList, CodeListError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let headers = self.csv_reader(bytes.as_slice())?.headers()?.clone();
+        let has_header = |name: &str| headers.iter().any(|header| self.header_matches(header, name));
+        let (code_header, term_header) = OPENCODELISTS_CSV_LAYOUTS.iter()
+            .find(|(code_header, term_header)| has_header(code_header) && has_header(term_header))
+            .ok_or_else(|| CodeListError::invalid_input(format!(
+                "Headers do not match a known OpenCodelists layout: {}", headers.iter().collect::<Vec<_>>().join(", ")
+            )))?;
+
+        let mut codelist_options = self.codelist_options.clone();
+        codelist_options.code_field_name = code_header.to_string();
+        codelist_options.term_field_name = term_header.to_string();
+        let mut metadata = self.metadata.clone();
+        metadata.source = MetadataSource::LoadedFromFile;
+        if let Some(version) = &version {
+            // OpenCodelists versions can be tags or hashes, which are only kept as the source version
+            metadata.version = version.parse().ok();
+            metadata.source_version = Some(version.clone());
+        }
+
+        let factory = CodeListFactory::new(codelist_options, metadata, self.codelist_type.clone());
+        let mut codelist = factory.load_codelist_from_csv_reader(bytes.as_slice())?;
+        let version = version.as_deref().unwrap_or("unknown");
+        codelist.add_log(match file_path {
+            Some(file_path) => format!("Loaded from OpenCodelists download {} (version: {})", file_path, version),
+            None => format!("Loaded from OpenCodelists download (version: {})", version),
+        });
+        Ok(codelist)
+    }
+}
+
+impl CodeList {
+    /// Save the codelist as an OpenCodelists CSV file
+    ///
+    /// The file has `code` and `term` columns sorted by code, which can be uploaded to the
+    /// OpenCodelists codelist builder.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the codelist to
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::CSVError` - If an error occurs when writing the csv
+    pub fn save_to_opencodelists_csv(&self, file_path: &str) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_opencodelists_csv_writer(&mut file)?;
+        file.finish()
+    }
+
+    /// Write the codelist as an OpenCodelists CSV file to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the csv data to
+    ///
+    /// # Errors
+    /// * `CodeListError::CSVError` - If an error occurs when writing the csv
+    pub fn save_to_opencodelists_csv_writer<W: Write>(&self, writer: W) -> Result<(), CodeListError> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record(["code", "term"])?;
+        for entry in self.entries_sorted_by_code() {
+            wtr.write_record([&entry.code, &entry.term])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Save the codelist as an OpenCodelists JSON payload
+    ///
+    /// The payload follows the OpenCodelists API for creating a codelist: the name, coding system,
+    /// codes, version tag and description. OpenCodelists looks terms up from the coding system, so
+    /// they are not included, which means the payload cannot be loaded back as a `CodeList`.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the payload to
+    /// * `name` - The name of the codelist on OpenCodelists
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the payload
+    pub fn save_to_opencodelists_json(&self, file_path: &str, name: &str) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_opencodelists_json_writer(&mut file, name)?;
+        file.finish()
+    }
+
+    /// Write the codelist as an OpenCodelists JSON payload to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the payload to
+    /// * `name` - The name of the codelist on OpenCodelists
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * `CodeListError::JSONError` - If an error occurs when serialising the payload
+    pub fn save_to_opencodelists_json_writer<W: Write>(&self, mut writer: W, name: &str) -> Result<(), CodeListError> {
+        let payload = OpenCodelistsPayload {
+            name,
+            coding_system_id: opencodelists_coding_system_id(&self.codelist_type),
+            codes: self.entries_sorted_by_code().into_iter().map(|entry| entry.code.as_str()).collect(),
+            tag: self.metadata.version_label(),
+            description: self.metadata.description.as_deref(),
+        };
+        serde_json::to_writer_pretty(&mut writer, &payload)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_codelist_factory, create_test_metadata};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_opencodelists_version_from_file_name() {
+        assert_eq!(opencodelists_version_from_file_name("opensafely-asthma-diagnosis-2020-04-15.csv"), Some("2020-04-15".to_string()));
+        assert_eq!(opencodelists_version_from_file_name("/tmp/nhsd-primary-care-domain-refsets-ast_cod.csv"), None);
+        assert_eq!(opencodelists_version_from_file_name("user-caroline-asthma-5ec2d4d6.csv"), Some("5ec2d4d6".to_string()));
+        assert_eq!(opencodelists_version_from_file_name("asthma.csv"), None);
+        assert_eq!(opencodelists_version_from_file_name("2020-04-15.csv"), None);
+    }
+
+    #[test]
+    fn test_load_codelist_from_opencodelists_csv_file_snomed() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("opensafely-asthma-diagnosis-2020-04-15.csv");
+        fs::write(&file_path, "code,term\n195967001,Asthma\n233678006,Childhood asthma\n")?;
+
+        let factory = create_test_codelist_factory(CodeListType::SNOMED);
+        let codelist = factory.load_codelist_from_opencodelists_csv_file(file_path.to_str().unwrap())?;
+
+        assert_eq!(codelist.entries.len(), 2);
+        assert!(codelist.entries.iter().any(|e| e.code == "195967001" && e.term == "Asthma"));
+        assert_eq!(codelist.metadata.version, Some("2020-04-15".parse()?));
+        assert_eq!(codelist.metadata.source, MetadataSource::LoadedFromFile);
+        assert_eq!(codelist.logs.len(), 1);
+        assert!(codelist.logs[0].ends_with("(version: 2020-04-15)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_opencodelists_csv_file_dmd() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("opensafely-salbutamol-5ec2d4d6.csv");
+        fs::write(&file_path, "dmd_type,dmd_id,dmd_name,bnf_code\nVMP,39113611000001102,Salbutamol 100micrograms/dose inhaler,0301011R0AAAPAP\n")?;
+
+        let factory = create_test_codelist_factory(CodeListType::SNOMED);
+        let codelist = factory.load_codelist_from_opencodelists_csv_file(file_path.to_str().unwrap())?;
+
+        assert_eq!(codelist.entries.len(), 1);
+        assert!(codelist.entries.iter().any(|e| e.code == "39113611000001102" && e.term == "Salbutamol 100micrograms/dose inhaler"));
+        assert_eq!(codelist.metadata.version, None);
+        assert_eq!(codelist.metadata.source_version, Some("5ec2d4d6".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_opencodelists_csv_file_icd10() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("icd10-asthma.csv");
+        fs::write(&file_path, "ICD10_code,Description\nJ45,Asthma\n")?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let codelist = factory.load_codelist_from_opencodelists_csv_file(file_path.to_str().unwrap())?;
+
+        assert!(codelist.entries.iter().any(|e| e.code == "J45" && e.term == "Asthma"));
+        // Without a version in the file name the factory's version is kept
+        assert_eq!(codelist.metadata.version, Some("2024-12-19".parse()?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_opencodelists_csv_file_unknown_layout() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("unknown.csv");
+        fs::write(&file_path, "id,name\nJ45,Asthma\n")?;
+
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+        let error = factory.load_codelist_from_opencodelists_csv_file(file_path.to_str().unwrap()).unwrap_err();
+
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Headers do not match a known OpenCodelists layout: id, name"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_opencodelists_csv() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("opencodelists.csv");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("233678006".to_string(), "Childhood asthma".to_string(), None)?;
+        codelist.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+        codelist.save_to_opencodelists_csv(file_path_str)?;
+
+        let content = fs::read_to_string(file_path_str)?;
+        assert_eq!(content, "code,term\n195967001,Asthma\n233678006,Childhood asthma\n");
+
+        let loaded_codelist = create_test_codelist_factory(CodeListType::SNOMED).load_codelist_from_opencodelists_csv_file(file_path_str)?;
+        assert_eq!(loaded_codelist.entries, codelist.entries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_opencodelists_json() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("opencodelists.json");
+        let file_path_str = file_path.to_str().unwrap();
+
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("233678006".to_string(), "Childhood asthma".to_string(), None)?;
+        codelist.add_entry("195967001".to_string(), "Asthma".to_string(), None)?;
+        codelist.save_to_opencodelists_json(file_path_str, "Asthma diagnosis")?;
+
+        let json_data: serde_json::Value = serde_json::from_str(&fs::read_to_string(file_path_str)?)?;
+        assert_eq!(json_data, serde_json::json!({
+            "name": "Asthma diagnosis",
+            "coding_system_id": "snomedct",
+            "codes": ["195967001", "233678006"],
+            "tag": "2024-12-19",
+            "description": "A test codelist",
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_opencodelists_coding_system_id() {
+        assert_eq!(opencodelists_coding_system_id(&CodeListType::ICD10), "icd10");
+        assert_eq!(opencodelists_coding_system_id(&CodeListType::SNOMED), "snomedct");
+        assert_eq!(opencodelists_coding_system_id(&CodeListType::OPCS), "opcs4");
+    }
+
+    #[test]
+    fn test_load_codelist_from_opencodelists_csv_reader() -> Result<(), CodeListError> {
+        let factory = create_test_codelist_factory(CodeListType::SNOMED);
+        let data = "code,term\n195967001,Asthma\n";
+
+        let codelist = factory.load_codelist_from_opencodelists_csv_reader(data.as_bytes(), Some("2020-04-15"))?;
+
+        assert_eq!(codelist.entries.len(), 1);
+        assert_eq!(codelist.metadata.version, Some("2020-04-15".parse()?));
+        assert_eq!(codelist.logs, vec!["Loaded from OpenCodelists download (version: 2020-04-15)".to_string()]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,414 @@
+//! This file contains the processing steps that are applied to codelists after they are loaded
+
+// External imports
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use serde::Serialize;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::code_entry::CodeEntry;
+use crate::codelist_options::CodeListOptions;
+use crate::errors::CodeListError;
+use crate::types::CodeListType;
+
+/// Trait for checking the codes of a codelist in the `Validate` processing step
+///
+/// This lets validators from other crates, such as codelist-validator-rs, be used in a pipeline.
+pub trait CodeValidator: Send + Sync {
+    /// Find the invalid codes in a codelist
+    ///
+    /// # Arguments
+    /// * `codelist` - The codelist to check
+    ///
+    /// # Returns
+    /// * `Vec<(String, String)>` - Each invalid code, with the reason it is invalid
+    fn invalid_codes(&self, codelist: &CodeList) -> Vec<(String, String)>;
+}
+
+/// Enum to represent a step in the processing pipeline
+///
+/// # Variants
+/// * `Normalise` - Remove whitespace from codes, upper case them and collapse whitespace in terms
+/// * `Truncate` - Truncate ICD10 codes to their first 3 characters
+/// * `AddXCodes` - Add an X code (e.g. "A01X") for each 3 character ICD10 code
+/// * `Deduplicate` - Keep a single entry for each code: the one whose term, then comment, sorts
+///   first, so that the entry kept does not depend on the order the entries were loaded in
+/// * `Validate` - Check the codes with a validator, either reporting or removing invalid codes
+/// * `EnrichTerms` - Replace terms with those in a lookup of code to preferred term
+#[derive(Clone)]
+pub enum ProcessingStep {
+    Normalise,
+    Truncate,
+    AddXCodes,
+    Deduplicate,
+    Validate { validator: Arc<dyn CodeValidator>, remove_invalid: bool },
+    EnrichTerms(HashMap<String, String>),
+}
+
+impl fmt::Debug for ProcessingStep {
+    /// Format the step by name, as validators are not printable
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessingStep::Validate { remove_invalid, .. } => write!(f, "Validate {{ remove_invalid: {} }}", remove_invalid),
+            ProcessingStep::EnrichTerms(terms) => write!(f, "EnrichTerms({} terms)", terms.len()),
+            step => write!(f, "{}", step.name()),
+        }
+    }
+}
+
+/// Struct to represent the outcome of applying one step to a codelist
+///
+/// # Fields
+/// * `step` - The name of the step
+/// * `applied` - Whether the step applied to the codelist, as some steps are only for ICD10
+/// * `entries_before` - The number of entries before the step
+/// * `entries_after` - The number of entries after the step
+/// * `changes` - A description of each change the step made
+/// * `issues` - Problems the step found but did not fix, such as invalid codes that were kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepReport {
+    pub step: String,
+    pub applied: bool,
+    pub entries_before: usize,
+    pub entries_after: usize,
+    pub changes: Vec<String>,
+    pub issues: Vec<String>,
+}
+
+/// Struct to represent the outcome of processing a codelist
+///
+/// # Fields
+/// * `name` - The name of the codelist, if it has one
+/// * `steps` - The report of each step, in the order they were applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProcessingReport {
+    pub name: Option<String>,
+    pub steps: Vec<StepReport>,
+}
+
+impl ProcessingReport {
+    /// Whether any step found problems it did not fix
+    ///
+    /// # Returns
+    /// * `bool` - True if any step has issues
+    pub fn has_issues(&self) -> bool {
+        self.steps.iter().any(|step| !step.issues.is_empty())
+    }
+
+    /// Get the total number of changes made by all the steps
+    ///
+    /// # Returns
+    /// * `usize` - The number of changes
+    pub fn change_count(&self) -> usize {
+        self.steps.iter().map(|step| step.changes.len()).sum()
+    }
+}
+
+impl ProcessingStep {
+    /// Get the name of the step
+    ///
+    /// # Returns
+    /// * `&'static str` - The name of the step
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessingStep::Normalise => "normalise",
+            ProcessingStep::Truncate => "truncate",
+            ProcessingStep::AddXCodes => "add_x_codes",
+            ProcessingStep::Deduplicate => "deduplicate",
+            ProcessingStep::Validate { .. } => "validate",
+            ProcessingStep::EnrichTerms(_) => "enrich_terms",
+        }
+    }
+
+    /// Get the pipeline described by the codelist options
+    ///
+    /// Codes are always normalised. They are then truncated and given X codes if the
+    /// `truncate_to_3_digits` and `add_x_codes` options are set, and deduplicated unless the
+    /// `allow_duplicates` option is set.
+    ///
+    /// # Arguments
+    /// * `codelist_options` - The codelist options
+    ///
+    /// # Returns
+    /// * `Vec<ProcessingStep>` - The steps, in the order to apply them
+    pub fn default_pipeline(codelist_options: &CodeListOptions) -> Vec<ProcessingStep> {
+        let mut steps = vec![ProcessingStep::Normalise];
+        if codelist_options.truncate_to_3_digits {
+            steps.push(ProcessingStep::Truncate);
+        }
+        if codelist_options.add_x_codes {
+            steps.push(ProcessingStep::AddXCodes);
+        }
+        if !codelist_options.allow_duplicates {
+            steps.push(ProcessingStep::Deduplicate);
+        }
+        steps
+    }
+
+    /// Apply the step to a codelist
+    ///
+    /// If the step applies to the codelist, a log entry records how many changes it made. If the
+    /// step fails, the codelist is left as it was.
+    ///
+    /// # Arguments
+    /// * `codelist` - The codelist to process
+    ///
+    /// # Returns
+    /// * `Result<StepReport, CodeListError>` - The report of the step or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::EmptyCode` - If a change would leave a code empty
+    /// * `CodeListError::EmptyTerm` - If a change would leave a term empty
+    pub fn apply(&self, codelist: &mut CodeList) -> Result<StepReport, CodeListError> {
+        let mut report = StepReport {
+            step: self.name().to_string(),
+            applied: true,
+            entries_before: codelist.entries.len(),
+            entries_after: codelist.entries.len(),
+            changes: Vec::new(),
+            issues: Vec::new(),
+        };
+        let is_icd10 = codelist.codelist_type == CodeListType::ICD10;
+
+        // Entry changes are collected in a new set, so a failing step leaves the codelist unchanged
+        let mut entries = HashSet::with_capacity(codelist.entries.len());
+        match self {
+            ProcessingStep::Normalise => {
+                let uppercase = codelist.codelist_type != CodeListType::SNOMED;
+                for entry in sorted_entries(codelist) {
+                    let mut code: String = entry.code.chars().filter(|c| !c.is_whitespace()).collect();
+                    if uppercase {
+                        code = code.to_uppercase();
+                    }
+                    let term = entry.term.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    if code != entry.code {
+                        report.changes.push(format!("Normalised code {} to {}", entry.code, code));
+                    } else if term != entry.term {
+                        report.changes.push(format!("Normalised term of {}", code));
+                    }
+                    insert_entry(&mut entries, CodeEntry::new(code, term, entry.comment.clone())?, &mut report);
+                }
+            }
+            ProcessingStep::Truncate | ProcessingStep::AddXCodes if !is_icd10 => {
+                report.applied = false;
+            }
+            ProcessingStep::Truncate => {
+                for entry in sorted_entries(codelist) {
+                    let code: String = entry.code.chars().filter(|c| *c != '.').take(3).collect();
+                    if code != entry.code {
+                        report.changes.push(format!("Truncated {} to {}", entry.code, code));
+                    }
+                    insert_entry(&mut entries, CodeEntry::new(code, entry.term.clone(), entry.comment.clone())?, &mut report);
+                }
+            }
+            ProcessingStep::AddXCodes => {
+                let codes: HashSet<&String> = codelist.codes().into_iter().collect();
+                for entry in sorted_entries(codelist) {
+                    let x_code = format!("{}X", entry.code);
+                    if entry.code.chars().count() == 3 && !codes.contains(&x_code) {
+                        report.changes.push(format!("Added {}", x_code));
+                        entries.insert(CodeEntry::new(x_code, entry.term.clone(), None)?);
+                    }
+                    entries.insert(entry.clone());
+                }
+            }
+            ProcessingStep::Deduplicate => {
+                let mut seen = HashSet::new();
+                for entry in sorted_entries(codelist) {
+                    if seen.insert(&entry.code) {
+                        entries.insert(entry.clone());
+                    } else {
+                        report.changes.push(format!("Removed duplicate of {}: {}", entry.code, entry.term));
+                    }
+                }
+            }
+            ProcessingStep::Validate { validator, remove_invalid } => {
+                let mut invalid_codes = validator.invalid_codes(codelist);
+                invalid_codes.sort();
+                invalid_codes.dedup();
+                let mut removed_codes = HashSet::new();
+                for (code, reason) in &invalid_codes {
+                    if *remove_invalid {
+                        removed_codes.insert(code);
+                        report.changes.push(format!("Removed invalid code {}: {}", code, reason));
+                    } else {
+                        report.issues.push(format!("Invalid code {}: {}", code, reason));
+                    }
+                }
+                entries.extend(codelist.entries.iter().filter(|entry| !removed_codes.contains(&entry.code)).cloned());
+            }
+            ProcessingStep::EnrichTerms(terms) => {
+                for entry in sorted_entries(codelist) {
+                    match terms.get(&entry.code).filter(|term| !term.trim().is_empty() && **term != entry.term) {
+                        Some(term) => {
+                            report.changes.push(format!("Replaced term of {}: {} with {}", entry.code, entry.term, term));
+                            insert_entry(&mut entries, CodeEntry::new(entry.code.clone(), term.clone(), entry.comment.clone())?, &mut report);
+                        }
+                        None => insert_entry(&mut entries, entry.clone(), &mut report),
+                    }
+                }
+            }
+        }
+        if report.applied {
+            codelist.entries = entries;
+        }
+
+        report.entries_after = codelist.entries.len();
+        if report.applied {
+            codelist.add_log(format!("Processing step {} made {} changes", report.step, report.changes.len()));
+        }
+        Ok(report)
+    }
+}
+
+/// Get the entries of a codelist, sorted by code, term and comment
+///
+/// Sorting keeps the order of changes in step reports stable.
+///
+/// # Arguments
+/// * `codelist` - The codelist to get the entries of
+///
+/// # Returns
+/// * `Vec<&CodeEntry>` - The entries
+fn sorted_entries(codelist: &CodeList) -> Vec<&CodeEntry> {
+    let mut entries: Vec<&CodeEntry> = codelist.entries.iter().collect();
+    entries.sort_by(|a, b| (&a.code, &a.term, &a.comment).cmp(&(&b.code, &b.term, &b.comment)));
+    entries
+}
+
+/// Insert a changed entry into the new entries of a step, reporting when it merges into an
+/// identical entry that is already there
+///
+/// # Arguments
+/// * `entries` - The new entries of the step
+/// * `entry` - The entry to insert
+/// * `report` - The report of the step
+fn insert_entry(entries: &mut HashSet<CodeEntry>, entry: CodeEntry, report: &mut StepReport) {
+    if entries.contains(&entry) {
+        report.changes.push(format!("Merged duplicate entry {}: {}", entry.code, entry.term));
+    } else {
+        entries.insert(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+
+    struct LengthValidator;
+
+    impl CodeValidator for LengthValidator {
+        fn invalid_codes(&self, codelist: &CodeList) -> Vec<(String, String)> {
+            codelist.codes().into_iter()
+                .filter(|code| code.len() > 4)
+                .map(|code| (code.clone(), "Code is longer than 4 characters".to_string()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_default_pipeline() {
+        let steps = ProcessingStep::default_pipeline(&CodeListOptions::default());
+        assert_eq!(steps.iter().map(ProcessingStep::name).collect::<Vec<_>>(), vec!["normalise", "deduplicate"]);
+
+        let codelist_options = CodeListOptions {
+            truncate_to_3_digits: true,
+            add_x_codes: true,
+            allow_duplicates: true,
+            ..Default::default()
+        };
+        let steps = ProcessingStep::default_pipeline(&codelist_options);
+        assert_eq!(steps.iter().map(ProcessingStep::name).collect::<Vec<_>>(), vec!["normalise", "truncate", "add_x_codes"]);
+    }
+
+    #[test]
+    fn test_normalise_truncate_and_add_x_codes() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry(" a01.1 ".to_string(), "Typhoid   fever".to_string(), None)?;
+        codelist.add_entry("B02".to_string(), "Zoster".to_string(), None)?;
+
+        let report = ProcessingStep::Normalise.apply(&mut codelist)?;
+        assert_eq!(report.changes, vec!["Normalised code  a01.1  to A01.1".to_string()]);
+        assert!(codelist.entries.contains(&CodeEntry::new("A01.1", "Typhoid fever".to_string(), None)?));
+
+        let report = ProcessingStep::Truncate.apply(&mut codelist)?;
+        assert_eq!(report.changes, vec!["Truncated A01.1 to A01".to_string()]);
+
+        let report = ProcessingStep::AddXCodes.apply(&mut codelist)?;
+        assert_eq!(report.changes, vec!["Added A01X".to_string(), "Added B02X".to_string()]);
+        assert_eq!(report.entries_before, 2);
+        assert_eq!(report.entries_after, 4);
+        assert_eq!(codelist.logs.last(), Some(&"Processing step add_x_codes made 2 changes".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalise_reports_merged_entries() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("a01".to_string(), "Typhoid fever".to_string(), None)?;
+        codelist.add_entry("A01".to_string(), "Typhoid  fever".to_string(), None)?;
+
+        let report = ProcessingStep::Normalise.apply(&mut codelist)?;
+        assert_eq!(report.changes, vec![
+            "Normalised term of A01".to_string(),
+            "Normalised code a01 to A01".to_string(),
+            "Merged duplicate entry A01: Typhoid fever".to_string(),
+        ]);
+        assert_eq!(report.entries_after, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_step_leaves_codelist_unchanged() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A01.1".to_string(), "Typhoid fever".to_string(), None)?;
+        codelist.add_entry("...".to_string(), "Not a code".to_string(), None)?;
+        let original = codelist.clone();
+
+        let error = ProcessingStep::Truncate.apply(&mut codelist).unwrap_err();
+        assert!(matches!(error, CodeListError::EmptyCode { .. }));
+        assert_eq!(codelist, original);
+        Ok(())
+    }
+
+    #[test]
+    fn test_icd10_steps_do_not_apply_to_snomed() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), None)?;
+
+        let report = ProcessingStep::Truncate.apply(&mut codelist)?;
+        assert!(!report.applied);
+        assert!(codelist.codes().contains(&"22298006".to_string()));
+        assert!(codelist.logs.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduplicate_validate_and_enrich_terms() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A01".to_string(), "Typhoid".to_string(), None)?;
+        codelist.add_entry("A01".to_string(), "Enteric fever".to_string(), None)?;
+        codelist.add_entry("A01.12".to_string(), "Typhoid meningitis".to_string(), None)?;
+
+        let report = ProcessingStep::Deduplicate.apply(&mut codelist)?;
+        assert_eq!(report.changes, vec!["Removed duplicate of A01: Typhoid".to_string()]);
+
+        let validator: Arc<dyn CodeValidator> = Arc::new(LengthValidator);
+        let report = ProcessingStep::Validate { validator: validator.clone(), remove_invalid: false }.apply(&mut codelist)?;
+        assert_eq!(report.issues, vec!["Invalid code A01.12: Code is longer than 4 characters".to_string()]);
+        assert_eq!(codelist.entries.len(), 2);
+
+        let report = ProcessingStep::Validate { validator, remove_invalid: true }.apply(&mut codelist)?;
+        assert!(report.issues.is_empty());
+        assert_eq!(codelist.entries.len(), 1);
+
+        let terms = HashMap::from([("A01".to_string(), "Typhoid fever".to_string())]);
+        let report = ProcessingStep::EnrichTerms(terms).apply(&mut codelist)?;
+        assert_eq!(report.changes, vec!["Replaced term of A01: Enteric fever with Typhoid fever".to_string()]);
+        assert!(codelist.entries.contains(&CodeEntry::new("A01", "Typhoid fever".to_string(), None)?));
+        Ok(())
+    }
+}
@@ -0,0 +1,411 @@
+//! This file contains the renderer of human-readable codelist reports, as standalone HTML or
+//! Markdown documents
+//!
+//! A report holds the codelist metadata, summary statistics, the entries sorted by code with
+//! their comments, and the change log from the codelist logs.
+
+// External imports
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use chrono::{DateTime, Utc};
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::errors::CodeListError;
+use crate::metadata::format_timestamp;
+use crate::compression::OutputFile;
+
+/// The text shown for metadata that was not recorded
+const NOT_RECORDED: &str = "Not recorded";
+
+/// The style sheet of HTML reports
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f2f2f2; }
+td.code { font-family: monospace; white-space: nowrap; }";
+
+/// Enum to represent the formats reports can be rendered in
+///
+/// # Variants
+/// * `Html` - A standalone HTML document
+/// * `Markdown` - A Markdown document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl fmt::Display for ReportFormat {
+    /// Format the report format as its name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ReportFormat::Html => "HTML",
+            ReportFormat::Markdown => "Markdown",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl ReportFormat {
+    /// Get the file extension of the format
+    ///
+    /// # Returns
+    /// * `&'static str` - The extension, without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Escape text for HTML
+///
+/// # Arguments
+/// * `text` - The text
+///
+/// # Returns
+/// * `String` - The escaped text
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape text for a Markdown table cell or list item, keeping line breaks as `<br>`
+///
+/// # Arguments
+/// * `text` - The text
+///
+/// # Returns
+/// * `String` - The escaped text
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.replace("\r\n", "\n").replace('\n', "<br>")
+}
+
+impl CodeList {
+    /// Get the title of a report on the codelist
+    ///
+    /// # Returns
+    /// * `String` - The metadata title, the codelist name, or a title naming its type
+    fn report_title(&self) -> String {
+        self.metadata.title.clone()
+            .or_else(|| self.name.clone())
+            .unwrap_or_else(|| format!("{} codelist", self.codelist_type))
+    }
+
+    /// Get the metadata rows of a report on the codelist
+    ///
+    /// # Returns
+    /// * `Vec<(&'static str, String)>` - The field names and values
+    fn report_metadata(&self) -> Vec<(&'static str, String)> {
+        let or_not_recorded = |value: Option<String>| value.unwrap_or_else(|| NOT_RECORDED.to_string());
+        let timestamp = |value: Option<DateTime<Utc>>| value.as_ref().map(format_timestamp);
+        let terminology = match (&self.metadata.terminology, &self.metadata.terminology_release) {
+            (Some(terminology), Some(release)) => Some(format!("{}, release {}", terminology, release)),
+            (Some(terminology), None) => Some(terminology.clone()),
+            (None, Some(release)) => Some(format!("Release {}", release)),
+            (None, None) => None,
+        };
+        vec![
+            ("Type", self.codelist_type.to_string()),
+            ("Id", or_not_recorded(self.id.clone())),
+            ("Source", self.metadata.source.to_string()),
+            ("Authors", or_not_recorded(self.metadata.authors.as_ref().filter(|authors| !authors.is_empty()).map(|authors| authors.join(", ")))),
+            ("Version", or_not_recorded(self.metadata.version.as_ref().map(ToString::to_string))),
+            ("Source version", or_not_recorded(self.metadata.source_version.clone())),
+            ("Description", or_not_recorded(self.metadata.description.clone())),
+            ("Created", or_not_recorded(timestamp(self.metadata.created))),
+            ("Updated", or_not_recorded(timestamp(self.metadata.updated))),
+            ("Licence", or_not_recorded(self.metadata.licence.clone())),
+            ("Terminology", or_not_recorded(terminology)),
+            ("References", or_not_recorded(self.metadata.references.as_ref().filter(|references| !references.is_empty()).map(|references| references.join(", ")))),
+            ("Intended use", or_not_recorded(self.metadata.intended_use.clone())),
+        ]
+    }
+
+    /// Get the summary statistics of a report on the codelist
+    ///
+    /// # Returns
+    /// * `Vec<(&'static str, String)>` - The statistic names and values
+    fn report_statistics(&self) -> Vec<(&'static str, String)> {
+        let code_lengths: Vec<usize> = self.entries.iter().map(|entry| entry.code.chars().count()).collect();
+        let length = |value: Option<&usize>| value.map(|length| length.to_string()).unwrap_or_else(|| "-".to_string());
+        vec![
+            ("Entries", self.entries.len().to_string()),
+            ("Distinct codes", self.codes().len().to_string()),
+            ("Distinct terms", self.entries.iter().map(|entry| &entry.term).collect::<HashSet<_>>().len().to_string()),
+            ("Entries with comments", self.entries.iter().filter(|entry| entry.comment.is_some()).count().to_string()),
+            ("Shortest code", length(code_lengths.iter().min())),
+            ("Longest code", length(code_lengths.iter().max())),
+            ("Changes logged", self.logs.len().to_string()),
+        ]
+    }
+
+    /// Render a report on the codelist as a standalone HTML or Markdown document
+    ///
+    /// The report has the codelist metadata, summary statistics, the entries sorted by code with
+    /// their comments, and the change log from the codelist logs, oldest first.
+    ///
+    /// # Arguments
+    /// * `format` - The format of the report
+    ///
+    /// # Returns
+    /// * `String` - The report
+    pub fn to_report(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => self.to_html_report(),
+            ReportFormat::Markdown => self.to_markdown_report(),
+        }
+    }
+
+    /// Render a report on the codelist as a Markdown document
+    ///
+    /// # Returns
+    /// * `String` - The report
+    fn to_markdown_report(&self) -> String {
+        let mut report = format!("# {}\n\n## Metadata\n\n| Field | Value |\n| --- | --- |\n", escape_markdown(&self.report_title()));
+        for (field, value) in self.report_metadata() {
+            report.push_str(&format!("| {} | {} |\n", field, escape_markdown(&value)));
+        }
+
+        report.push_str("\n## Summary\n\n| Statistic | Value |\n| --- | --- |\n");
+        for (statistic, value) in self.report_statistics() {
+            report.push_str(&format!("| {} | {} |\n", statistic, value));
+        }
+
+        report.push_str("\n## Entries\n\n");
+        if self.entries.is_empty() {
+            report.push_str("No entries.\n");
+        } else {
+            report.push_str("| Code | Term | Comment |\n| --- | --- | --- |\n");
+            for entry in self.entries_sorted_by_code() {
+                report.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    escape_markdown(&entry.code),
+                    escape_markdown(&entry.term),
+                    entry.comment.as_deref().map(escape_markdown).unwrap_or_default(),
+                ));
+            }
+        }
+
+        report.push_str("\n## Change log\n\n");
+        if self.logs.is_empty() {
+            report.push_str("No changes logged.\n");
+        } else {
+            for (index, log) in self.logs.iter().enumerate() {
+                report.push_str(&format!("{}. {}\n", index + 1, escape_markdown(log)));
+            }
+        }
+        report
+    }
+
+    /// Render a report on the codelist as a standalone HTML document
+    ///
+    /// # Returns
+    /// * `String` - The report
+    fn to_html_report(&self) -> String {
+        let title = escape_html(&self.report_title());
+        let mut report = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, HTML_STYLE, title,
+        );
+        let key_value_table = |rows: Vec<(&'static str, String)>| -> String {
+            let rows: String = rows.iter()
+                .map(|(key, value)| format!("<tr><th>{}</th><td>{}</td></tr>\n", key, escape_html(value).replace('\n', "<br>")))
+                .collect();
+            format!("<table>\n{}</table>\n", rows)
+        };
+
+        report.push_str("<h2>Metadata</h2>\n");
+        report.push_str(&key_value_table(self.report_metadata()));
+        report.push_str("<h2>Summary</h2>\n");
+        report.push_str(&key_value_table(self.report_statistics()));
+
+        report.push_str("<h2>Entries</h2>\n");
+        if self.entries.is_empty() {
+            report.push_str("<p>No entries.</p>\n");
+        } else {
+            report.push_str("<table>\n<thead><tr><th>Code</th><th>Term</th><th>Comment</th></tr></thead>\n<tbody>\n");
+            for entry in self.entries_sorted_by_code() {
+                report.push_str(&format!(
+                    "<tr><td class=\"code\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape_html(&entry.code),
+                    escape_html(&entry.term),
+                    entry.comment.as_deref().map(escape_html).unwrap_or_default(),
+                ));
+            }
+            report.push_str("</tbody>\n</table>\n");
+        }
+
+        report.push_str("<h2>Change log</h2>\n");
+        if self.logs.is_empty() {
+            report.push_str("<p>No changes logged.</p>\n");
+        } else {
+            report.push_str("<ol>\n");
+            for log in &self.logs {
+                report.push_str(&format!("<li>{}</li>\n", escape_html(log)));
+            }
+            report.push_str("</ol>\n");
+        }
+        report.push_str("</body>\n</html>\n");
+        report
+    }
+
+    /// Save a report on the codelist as a standalone HTML or Markdown document
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the report to
+    /// * `format` - The format of the report
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    pub fn save_to_report(&self, file_path: &str, format: ReportFormat) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_report_writer(&mut file, format)?;
+        file.finish()
+    }
+
+    /// Write a report on the codelist as a standalone HTML or Markdown document to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the report to
+    /// * `format` - The format of the report
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    pub fn save_to_report_writer<W: Write>(&self, mut writer: W, format: ReportFormat) -> Result<(), CodeListError> {
+        writer.write_all(self.to_report(format).as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use crate::metadata::Metadata;
+    use crate::types::CodeListType;
+    use tempfile::tempdir;
+
+    fn create_test_codelist() -> Result<CodeList, CodeListError> {
+        let metadata = Metadata {
+            authors: Some(vec!["Caroline Morton".to_string(), "Emma Bagshaw".to_string()]),
+            description: None,
+            licence: Some("CC-BY-4.0".to_string()),
+            terminology: Some("ICD-10".to_string()),
+            terminology_release: Some("2019".to_string()),
+            ..create_test_metadata()
+        };
+        let mut codelist = CodeList::new(CodeListType::ICD10, metadata, None);
+        codelist.set_name("Diabetes");
+        codelist.add_entry("E11.9".to_string(), "Type 2 diabetes mellitus without complications".to_string(), None)?;
+        codelist.add_entry("E10".to_string(), "Type 1 diabetes <T1DM>".to_string(), Some("Check | with team".to_string()))?;
+        codelist.add_log("Added E10 & E11.9".to_string());
+        Ok(codelist)
+    }
+
+    #[test]
+    fn test_markdown_report() -> Result<(), CodeListError> {
+        let report = create_test_codelist()?.to_report(ReportFormat::Markdown);
+        assert_eq!(report, "\
+# Diabetes
+
+## Metadata
+
+| Field | Value |
+| --- | --- |
+| Type | ICD10 |
+| Id | diabetes |
+| Source | Manually created |
+| Authors | Caroline Morton, Emma Bagshaw |
+| Version | 2024-12-19 |
+| Source version | Not recorded |
+| Description | Not recorded |
+| Created | Not recorded |
+| Updated | Not recorded |
+| Licence | CC-BY-4.0 |
+| Terminology | ICD-10, release 2019 |
+| References | Not recorded |
+| Intended use | Not recorded |
+
+## Summary
+
+| Statistic | Value |
+| --- | --- |
+| Entries | 2 |
+| Distinct codes | 2 |
+| Distinct terms | 2 |
+| Entries with comments | 1 |
+| Shortest code | 3 |
+| Longest code | 5 |
+| Changes logged | 1 |
+
+## Entries
+
+| Code | Term | Comment |
+| --- | --- | --- |
+| E10 | Type 1 diabetes \\<T1DM\\> | Check \\| with team |
+| E11.9 | Type 2 diabetes mellitus without complications |  |
+
+## Change log
+
+1. Added E10 & E11.9
+");
+        Ok(())
+    }
+
+    #[test]
+    fn test_html_report() -> Result<(), CodeListError> {
+        let report = create_test_codelist()?.to_report(ReportFormat::Html);
+        assert!(report.starts_with("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Diabetes</title>\n"));
+        assert!(report.contains("<tr><th>Authors</th><td>Caroline Morton, Emma Bagshaw</td></tr>\n"));
+        assert!(report.contains("<tr><th>Entries with comments</th><td>1</td></tr>\n"));
+        assert!(report.contains("\
+<tr><td class=\"code\">E10</td><td>Type 1 diabetes &lt;T1DM&gt;</td><td>Check | with team</td></tr>
+<tr><td class=\"code\">E11.9</td><td>Type 2 diabetes mellitus without complications</td><td></td></tr>
+"));
+        assert!(report.contains("<ol>\n<li>Added E10 &amp; E11.9</li>\n</ol>\n"));
+        assert!(report.ends_with("</body>\n</html>\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_of_empty_codelist() {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        let report = codelist.to_report(ReportFormat::Markdown);
+        assert!(report.starts_with("# SNOMED codelist\n"));
+        assert!(report.contains("| Id | Not recorded |\n"));
+        assert!(report.contains("| Shortest code | - |\n"));
+        assert!(report.contains("## Entries\n\nNo entries.\n"));
+        assert!(report.ends_with("## Change log\n\nNo changes logged.\n"));
+        assert!(codelist.to_report(ReportFormat::Html).contains("<p>No entries.</p>\n"));
+
+        codelist.metadata.title = Some("Diabetes (any type)".to_string());
+        assert!(codelist.to_report(ReportFormat::Markdown).starts_with("# Diabetes (any type)\n"));
+    }
+
+    #[test]
+    fn test_save_to_report() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let codelist = create_test_codelist()?;
+        for format in [ReportFormat::Html, ReportFormat::Markdown] {
+            let file_path = temp_dir.path().join(format!("diabetes.{}", format.extension()));
+            codelist.save_to_report(file_path.to_str().unwrap(), format)?;
+            assert_eq!(std::fs::read_to_string(&file_path)?, codelist.to_report(format));
+        }
+        Ok(())
+    }
+}
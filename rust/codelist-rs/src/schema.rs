@@ -0,0 +1,251 @@
+//! This file contains the versioned JSON schema of saved codelist files, and the migrations that
+//! upgrade files written by older versions of the library
+//!
+//! Version 0 files are the plain serde form of `CodeList` written before files carried a
+//! `schema_version` field. Each migration upgrades a file by one version, so a file of any
+//! version is brought up to date by running the migrations after its version in order.
+
+// External imports
+use serde_json::{Map, Value};
+
+// Internal imports
+use crate::codelist::CODELIST_SCHEMA_VERSION;
+use crate::errors::CodeListError;
+use crate::version::CodeListVersion;
+
+/// The JSON Schema of the current version of saved codelist files
+pub const CODELIST_JSON_SCHEMA: &str = include_str!("../schema/codelist.schema.json");
+
+/// A migration that upgrades a saved codelist object by one schema version
+type Migration = fn(&mut Map<String, Value>) -> Result<(), CodeListError>;
+
+/// The migrations, where the migration at index `n` upgrades version `n` to version `n + 1`
+const MIGRATIONS: [Migration; 5] = [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3, migrate_v3_to_v4, migrate_v4_to_v5];
+
+// Every schema version below the current one needs a migration
+const _: () = assert!(MIGRATIONS.len() as u64 == CODELIST_SCHEMA_VERSION);
+
+/// Whether JSON is a saved codelist, either with a `schema_version` field or in the version 0 form
+///
+/// # Arguments
+/// * `json_data` - The parsed JSON
+///
+/// # Returns
+/// * `bool` - True if the JSON is a saved codelist
+pub fn is_saved_codelist_json(json_data: &Value) -> bool {
+    json_data.get("schema_version").is_some() || is_version_0(json_data)
+}
+
+/// Whether JSON is a version 0 saved codelist, which has the codelist fields but no `schema_version`
+///
+/// # Arguments
+/// * `json_data` - The parsed JSON
+///
+/// # Returns
+/// * `bool` - True if the JSON is a version 0 saved codelist
+fn is_version_0(json_data: &Value) -> bool {
+    json_data.get("schema_version").is_none()
+        && json_data.get("entries").is_some_and(Value::is_array)
+        && json_data.get("codelist_type").is_some()
+}
+
+/// Upgrade a saved codelist to the current schema version
+///
+/// # Arguments
+/// * `json_data` - The parsed JSON of a saved codelist
+///
+/// # Returns
+/// * `Result<(Value, u64), CodeListError>` - The upgraded JSON, without its `schema_version`
+///   field, and the version the file was written with, or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidInput` - If the JSON is not an object or has no valid `schema_version` field
+/// * `CodeListError::UnsupportedSchemaVersion` - If the file was written by a newer version of the library
+pub fn migrate_saved_json(mut json_data: Value) -> Result<(Value, u64), CodeListError> {
+    let version_0 = is_version_0(&json_data);
+    let object = json_data.as_object_mut()
+        .ok_or_else(|| CodeListError::invalid_input("Saved codelist JSON must be an object"))?;
+    let version = match object.remove("schema_version") {
+        Some(version) => version.as_u64()
+            .ok_or_else(|| CodeListError::invalid_input(format!("schema_version must be a positive integer, found: {}", version)))?,
+        None if version_0 => 0,
+        None => return Err(CodeListError::invalid_input("No schema_version field found in saved codelist JSON")),
+    };
+    if version > CODELIST_SCHEMA_VERSION {
+        return Err(CodeListError::unsupported_schema_version(version.to_string(), CODELIST_SCHEMA_VERSION.to_string()));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(object)?;
+    }
+    Ok((json_data, version))
+}
+
+/// Upgrade version 0 to version 1
+///
+/// Version 1 only added the `schema_version` field, and options added since then have defaults,
+/// so there is nothing to change.
+fn migrate_v0_to_v1(_object: &mut Map<String, Value>) -> Result<(), CodeListError> {
+    Ok(())
+}
+
+/// Upgrade version 1 to version 2
+///
+/// Version 2 adds the `name` and `id` fields, which older files do not have.
+fn migrate_v1_to_v2(object: &mut Map<String, Value>) -> Result<(), CodeListError> {
+    object.entry("name").or_insert(Value::Null);
+    object.entry("id").or_insert(Value::Null);
+    Ok(())
+}
+
+/// Upgrade version 2 to version 3
+///
+/// Version 3 adds the title, timestamps, licence, terminology release, references and intended
+/// use fields to the metadata, which older files do not have.
+fn migrate_v2_to_v3(object: &mut Map<String, Value>) -> Result<(), CodeListError> {
+    let metadata = object.get_mut("metadata")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| CodeListError::invalid_input("Saved codelist JSON must have a metadata object"))?;
+    for field in ["title", "created", "updated", "licence", "terminology", "terminology_release", "references", "intended_use"] {
+        metadata.entry(field).or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// Upgrade version 3 to version 4
+///
+/// Version 4 requires the metadata version to be a date or a semantic version. Older files could
+/// hold any string, so versions that are neither are removed and noted in the logs. The removed
+/// version is kept aside as the `source_version`, which version 5 adds.
+fn migrate_v3_to_v4(object: &mut Map<String, Value>) -> Result<(), CodeListError> {
+    let metadata = object.get_mut("metadata")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| CodeListError::invalid_input("Saved codelist JSON must have a metadata object"))?;
+    let version = match metadata.get("version") {
+        Some(Value::String(version)) if version.parse::<CodeListVersion>().is_err() => version.clone(),
+        _ => return Ok(()),
+    };
+    metadata.insert("version".to_string(), Value::Null);
+    metadata.insert("source_version".to_string(), Value::String(version.clone()));
+    if let Some(logs) = object.get_mut("logs").and_then(Value::as_array_mut) {
+        logs.push(Value::String(format!("Moved version {}, which is not a date or semantic version, to the source version", version)));
+    }
+    Ok(())
+}
+
+/// Upgrade version 4 to version 5
+///
+/// Version 5 adds the stable `id` and the `source_version` fields to the metadata, which older
+/// files do not have. A source version kept aside by `migrate_v3_to_v4` is left in place.
+fn migrate_v4_to_v5(object: &mut Map<String, Value>) -> Result<(), CodeListError> {
+    let metadata = object.get_mut("metadata")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| CodeListError::invalid_input("Saved codelist JSON must have a metadata object"))?;
+    for field in ["id", "source_version"] {
+        metadata.entry(field).or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use std::collections::BTreeSet;
+    use serde_json::json;
+    use crate::codelist::CodeList;
+    use crate::codelist_options::CodeListOptions;
+    use crate::types::CodeListType;
+
+    fn keys(value: &Value) -> BTreeSet<String> {
+        value.as_object().map(|object| object.keys().cloned().collect()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_json_schema_matches_saved_codelist() -> Result<(), CodeListError> {
+        let schema: Value = serde_json::from_str(CODELIST_JSON_SCHEMA)?;
+        assert_eq!(schema["properties"]["schema_version"]["const"], CODELIST_SCHEMA_VERSION);
+
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A01".to_string(), "Typhoid".to_string(), Some("comment".to_string()))?;
+        let mut data = Vec::new();
+        codelist.save_to_json_writer(&mut data)?;
+        let saved: Value = serde_json::from_slice(&data)?;
+
+        let required: BTreeSet<String> = schema["required"].as_array().unwrap().iter().map(|key| key.as_str().unwrap().to_string()).collect();
+        assert_eq!(keys(&saved), required);
+        assert_eq!(keys(&schema["properties"]), required);
+        assert_eq!(keys(&saved["metadata"]), keys(&schema["properties"]["metadata"]["properties"]));
+        assert_eq!(keys(&saved["entries"][0]), keys(&schema["properties"]["entries"]["items"]["properties"]));
+        assert_eq!(keys(&serde_json::to_value(CodeListOptions::default())?), keys(&schema["properties"]["codelist_options"]["properties"]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_version_0() -> Result<(), CodeListError> {
+        let json_data = json!({
+            "entries": [{"code": "A01", "term": "Typhoid", "comment": null}],
+            "codelist_type": "ICD10",
+            "metadata": {"source": "ManuallyCreated", "authors": null, "version": null, "description": null},
+            "logs": [],
+            "codelist_options": {
+                "allow_duplicates": false,
+                "truncate_to_3_digits": false,
+                "add_x_codes": false,
+                "code_column_name": "code",
+                "term_column_name": "term",
+                "code_field_name": "code",
+                "term_field_name": "term"
+            }
+        });
+        assert!(is_saved_codelist_json(&json_data));
+
+        let (migrated, version) = migrate_saved_json(json_data)?;
+        assert_eq!(version, 0);
+        assert_eq!(migrated["name"], Value::Null);
+        assert_eq!(migrated["metadata"]["licence"], Value::Null);
+        assert_eq!(migrated["metadata"]["id"], Value::Null);
+        assert_eq!(migrated["metadata"]["source_version"], Value::Null);
+        assert_eq!(migrated.get("schema_version"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_version_3_free_form_version() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A01".to_string(), "Typhoid".to_string(), None)?;
+        let mut data = Vec::new();
+        codelist.save_to_json_writer(&mut data)?;
+        let mut saved: Value = serde_json::from_slice(&data)?;
+        saved["schema_version"] = json!(3);
+
+        let (migrated, version) = migrate_saved_json(saved.clone())?;
+        assert_eq!(version, 3);
+        assert_eq!(migrated["metadata"]["version"], "2024-12-19");
+        assert_eq!(migrated["metadata"]["source_version"], Value::Null);
+        assert_eq!(migrated["logs"], json!([]));
+
+        // Version 3 files have no source version field
+        saved["metadata"].as_object_mut().unwrap().remove("source_version");
+        saved["metadata"]["version"] = json!("v2 draft");
+        let (migrated, _) = migrate_saved_json(saved)?;
+        assert_eq!(migrated["metadata"]["version"], Value::Null);
+        assert_eq!(migrated["metadata"]["source_version"], "v2 draft");
+        assert_eq!(migrated["logs"], json!(["Moved version v2 draft, which is not a date or semantic version, to the source version"]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_errors() {
+        assert!(!is_saved_codelist_json(&json!([{"code": "A01", "term": "Typhoid"}])));
+
+        let error = migrate_saved_json(json!({"entries": []})).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "No schema_version field found in saved codelist JSON"));
+
+        let error = migrate_saved_json(json!({"schema_version": 2, "entries": []})).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "Saved codelist JSON must have a metadata object"));
+
+        let error = migrate_saved_json(json!({"schema_version": "one"})).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "schema_version must be a positive integer, found: \"one\""));
+    }
+}
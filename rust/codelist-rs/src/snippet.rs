@@ -0,0 +1,431 @@
+//! This file contains the exporters that render a codelist as a snippet of Stata, R, SAS or Python,
+//! for pasting into analysis scripts
+//!
+//! Each snippet starts with a comment describing the codelist and its metadata, followed by the
+//! codes in sorted order in the forms analysts use in that language.
+
+// External imports
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::errors::CodeListError;
+use crate::compression::OutputFile;
+
+/// The most strings Stata accepts in one `inlist()`, after the expression being tested
+const STATA_INLIST_MAX_STRINGS: usize = 9;
+
+/// The longest variable (and format) name Stata and SAS accept
+const MAX_NAME_LENGTH: usize = 32;
+
+/// The longest local macro name Stata accepts
+const STATA_MAX_LOCAL_NAME_LENGTH: usize = 31;
+
+/// The suffix of the Stata local macro holding the codes
+const STATA_CODES_SUFFIX: &str = "_codes";
+
+/// The number of codes on each line of a SAS format value list
+const SAS_CODES_PER_LINE: usize = 10;
+
+/// Enum to represent the languages snippets can be rendered in
+///
+/// # Variants
+/// * `Stata` - A local macro of the codes and a variable made with `inlist()`
+/// * `R` - A character vector
+/// * `Sas` - A character format and a macro variable for `IN` lists
+/// * `Python` - A list and a set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnippetLanguage {
+    Stata,
+    R,
+    Sas,
+    Python,
+}
+
+impl fmt::Display for SnippetLanguage {
+    /// Format the language as its name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SnippetLanguage::Stata => "Stata",
+            SnippetLanguage::R => "R",
+            SnippetLanguage::Sas => "SAS",
+            SnippetLanguage::Python => "Python",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl SnippetLanguage {
+    /// Get the usual file extension of scripts in the language
+    ///
+    /// # Returns
+    /// * `&'static str` - The extension, without the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SnippetLanguage::Stata => "do",
+            SnippetLanguage::R => "R",
+            SnippetLanguage::Sas => "sas",
+            SnippetLanguage::Python => "py",
+        }
+    }
+
+    /// Render a line of text as a comment
+    ///
+    /// # Arguments
+    /// * `line` - The text, without line breaks
+    ///
+    /// # Returns
+    /// * `String` - The comment
+    fn comment(&self, line: &str) -> String {
+        match self {
+            SnippetLanguage::Stata => format!("* {}", line),
+            SnippetLanguage::R | SnippetLanguage::Python => format!("# {}", line),
+            SnippetLanguage::Sas => format!("/* {} */", line.replace("*/", "* /")),
+        }
+    }
+
+    /// Render a string literal
+    ///
+    /// # Arguments
+    /// * `value` - The string
+    ///
+    /// # Returns
+    /// * `String` - The literal
+    fn literal(&self, value: &str) -> String {
+        match self {
+            SnippetLanguage::Stata => {
+                // Stata expands macros in strings unless `$` and backticks are escaped, and needs
+                // compound quotes around strings containing double quotes
+                let escaped = value.replace('$', "\\$").replace('`', "\\`");
+                if escaped.contains('"') {
+                    format!("`\"{}\"'", escaped)
+                } else {
+                    format!("\"{}\"", escaped)
+                }
+            }
+            SnippetLanguage::R | SnippetLanguage::Python => {
+                format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            SnippetLanguage::Sas => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+/// Struct to represent the options for rendering a codelist as a snippet
+///
+/// # Fields
+/// * `language` - The language of the snippet
+/// * `variable_name` - The name of the variable holding the codes, or `None` to use the codelist
+///   id (or "codelist" if it has none). It is changed to a valid name if needed.
+/// * `code_variable` - The name of the variable holding the codes in the dataset, used by the
+///   Stata `inlist()` expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetOptions {
+    pub language: SnippetLanguage,
+    pub variable_name: Option<String>,
+    pub code_variable: String,
+}
+
+impl Default for SnippetOptions {
+    /// Default implementation for SnippetOptions
+    ///
+    /// # Returns
+    /// * `SnippetOptions` - A Stata snippet named after the codelist, testing the `code` variable
+    fn default() -> Self {
+        Self {
+            language: SnippetLanguage::Stata,
+            variable_name: None,
+            code_variable: "code".to_string(),
+        }
+    }
+}
+
+/// Turn text into a valid variable name in every supported language
+///
+/// Characters other than ASCII letters, digits and underscores become underscores, names that do
+/// not start with a letter or underscore get a `codelist_` prefix, and names are cut to the Stata
+/// and SAS limit of 32 characters.
+///
+/// # Arguments
+/// * `name` - The text
+///
+/// # Returns
+/// * `String` - The variable name
+fn variable_name(name: &str) -> String {
+    let mut variable: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if !variable.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        variable = format!("codelist_{}", variable);
+    }
+    variable.truncate(MAX_NAME_LENGTH);
+    variable
+}
+
+impl CodeList {
+    /// Get the lines of the comment that describes the codelist at the top of a snippet
+    ///
+    /// # Arguments
+    /// * `code_count` - The number of distinct codes in the snippet
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The lines, without comment markers
+    fn snippet_header_lines(&self, code_count: usize) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Codelist: {} ({}), {} codes",
+            self.name.as_deref().unwrap_or("unnamed"),
+            self.codelist_type,
+            code_count,
+        )];
+        if let Some(title) = &self.metadata.title {
+            lines.push(format!("Title: {}", title));
+        }
+        lines.push(format!("Source: {}", self.metadata.source));
+        if let Some(authors) = self.metadata.authors.as_ref().filter(|authors| !authors.is_empty()) {
+            lines.push(format!("Authors: {}", authors.join(", ")));
+        }
+        if let Some(version) = &self.metadata.version {
+            lines.push(format!("Version: {}", version));
+        }
+        if let Some(terminology) = &self.metadata.terminology {
+            match &self.metadata.terminology_release {
+                Some(release) => lines.push(format!("Terminology: {}, release {}", terminology, release)),
+                None => lines.push(format!("Terminology: {}", terminology)),
+            }
+        }
+        if let Some(licence) = &self.metadata.licence {
+            lines.push(format!("Licence: {}", licence));
+        }
+        if let Some(description) = &self.metadata.description {
+            let mut description_lines = description.lines();
+            lines.push(format!("Description: {}", description_lines.next().unwrap_or_default()));
+            lines.extend(description_lines.map(|line| format!("  {}", line)));
+        }
+        lines
+    }
+
+    /// Render the codes of the codelist as a snippet of Stata, R, SAS or Python
+    ///
+    /// The snippet starts with a comment describing the codelist, then holds the codes, sorted and
+    /// without duplicates:
+    /// * Stata: a local macro of the codes, named `<variable>_codes` and cut to the 31 characters
+    ///   Stata allows, and a byte variable that is 1 for the codes, made with `inlist()` calls of
+    ///   at most 9 codes each, as Stata allows
+    /// * R: a character vector
+    /// * SAS: a `$` format mapping the codes to 'Y' and anything else to 'N', and a macro
+    ///   variable for `IN` lists
+    /// * Python: a list and a set
+    ///
+    /// # Arguments
+    /// * `options` - The snippet options
+    ///
+    /// # Returns
+    /// * `String` - The snippet
+    pub fn to_snippet(&self, options: &SnippetOptions) -> String {
+        let language = options.language;
+        let codes: BTreeSet<&String> = self.codes().into_iter().collect();
+        let literals: Vec<String> = codes.iter().map(|code| language.literal(code)).collect();
+        let name = variable_name(
+            options.variable_name.as_deref()
+                .or(self.id.as_deref())
+                .unwrap_or("codelist"),
+        );
+
+        let mut snippet: String = self.snippet_header_lines(codes.len()).iter()
+            .map(|line| format!("{}\n", language.comment(line)))
+            .collect();
+        match language {
+            SnippetLanguage::Stata => {
+                let mut macro_name = name.clone();
+                macro_name.truncate(STATA_MAX_LOCAL_NAME_LENGTH - STATA_CODES_SUFFIX.len());
+                let macro_name = format!("{}{}", macro_name.trim_end_matches('_'), STATA_CODES_SUFFIX);
+                snippet.push_str(&format!("local {} `\"{}\"'\n", macro_name, literals.join(" ")));
+                let condition = if literals.is_empty() {
+                    "0".to_string()
+                } else {
+                    literals.chunks(STATA_INLIST_MAX_STRINGS)
+                        .map(|chunk| format!("inlist({}, {})", options.code_variable, chunk.join(", ")))
+                        .collect::<Vec<String>>()
+                        .join(" ///\n    | ")
+                };
+                snippet.push_str(&format!("generate byte {} = {}\n", name, condition));
+            }
+            SnippetLanguage::R => {
+                if literals.is_empty() {
+                    snippet.push_str(&format!("{} <- character(0)\n", name));
+                } else {
+                    snippet.push_str(&format!("{} <- c(\n  {}\n)\n", name, literals.join(",\n  ")));
+                }
+            }
+            SnippetLanguage::Sas => {
+                let mut format_name = name.clone();
+                format_name.truncate(MAX_NAME_LENGTH - 2);
+                if format_name.ends_with(|c: char| c.is_ascii_digit()) {
+                    format_name.push('f');
+                }
+                snippet.push_str(&format!("proc format;\n    value ${}\n", format_name));
+                for chunk in literals.chunks(SAS_CODES_PER_LINE) {
+                    snippet.push_str(&format!("        {} = 'Y'\n", chunk.join(", ")));
+                }
+                snippet.push_str("        other = 'N';\nrun;\n");
+                snippet.push_str(&language.comment(&format!("Use as: where put(code, ${}.) = 'Y'; or where code in (&{});", format_name, name)));
+                snippet.push_str(&format!("\n%let {} = {};\n", name, literals.join(" ")));
+            }
+            SnippetLanguage::Python => {
+                if literals.is_empty() {
+                    snippet.push_str(&format!("{} = []\n", name));
+                } else {
+                    snippet.push_str(&format!("{} = [\n    {},\n]\n", name, literals.join(",\n    ")));
+                }
+                snippet.push_str(&format!("{}_set = set({})\n", name, name));
+            }
+        }
+        snippet
+    }
+
+    /// Save the codes of the codelist as a snippet of Stata, R, SAS or Python
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the snippet to
+    /// * `options` - The snippet options
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    pub fn save_to_snippet(&self, file_path: &str, options: &SnippetOptions) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_snippet_writer(&mut file, options)?;
+        file.finish()
+    }
+
+    /// Write the codes of the codelist as a snippet of Stata, R, SAS or Python to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the snippet to
+    /// * `options` - The snippet options
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    pub fn save_to_snippet_writer<W: Write>(&self, mut writer: W, options: &SnippetOptions) -> Result<(), CodeListError> {
+        writer.write_all(self.to_snippet(options).as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use crate::types::CodeListType;
+    use tempfile::tempdir;
+
+    fn create_test_codelist(code_count: usize) -> Result<CodeList, CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.set_name("Diabetes");
+        for index in 0..code_count {
+            codelist.add_entry(format!("E1{}", index), format!("Diabetes {}", index), None)?;
+        }
+        Ok(codelist)
+    }
+
+    fn options(language: SnippetLanguage) -> SnippetOptions {
+        SnippetOptions { language, ..SnippetOptions::default() }
+    }
+
+    const HEADER: &str = "Codelist: Diabetes (ICD10), 2 codes";
+
+    #[test]
+    fn test_stata_snippet() -> Result<(), CodeListError> {
+        let snippet = create_test_codelist(2)?.to_snippet(&options(SnippetLanguage::Stata));
+        assert_eq!(snippet, format!("\
+* {}
+* Source: Manually created
+* Authors: Caroline Morton
+* Version: 2024-12-19
+* Description: A test codelist
+local diabetes_codes `\"\"E10\" \"E11\"\"'
+generate byte diabetes = inlist(code, \"E10\", \"E11\")
+", HEADER));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stata_snippet_splits_inlist() -> Result<(), CodeListError> {
+        let snippet = create_test_codelist(10)?.to_snippet(&SnippetOptions { code_variable: "icd10".to_string(), ..SnippetOptions::default() });
+        assert!(snippet.ends_with("generate byte diabetes = inlist(icd10, \"E10\", \"E11\", \"E12\", \"E13\", \"E14\", \"E15\", \"E16\", \"E17\", \"E18\") ///\n    | inlist(icd10, \"E19\")\n"));
+
+        let empty = CodeList::new(CodeListType::ICD10, create_test_metadata(), None).to_snippet(&SnippetOptions::default());
+        assert!(empty.ends_with("local codelist_codes `\"\"'\ngenerate byte codelist = 0\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stata_snippet_long_name() -> Result<(), CodeListError> {
+        let mut codelist = create_test_codelist(1)?;
+        codelist.id = Some("type-2-diabetes-mellitus-with-complications".to_string());
+        let snippet = codelist.to_snippet(&SnippetOptions::default());
+        assert!(snippet.contains("\nlocal type_2_diabetes_mellitus_codes `\"\"E10\"\"'\n"));
+        assert!(snippet.ends_with("\ngenerate byte type_2_diabetes_mellitus_with_co = inlist(code, \"E10\")\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_r_and_python_snippets() -> Result<(), CodeListError> {
+        let mut codelist = create_test_codelist(2)?;
+        codelist.metadata.title = Some("Diabetes mellitus".to_string());
+        codelist.metadata.terminology = Some("ICD-10".to_string());
+        codelist.metadata.terminology_release = Some("2019".to_string());
+        let snippet = codelist.to_snippet(&options(SnippetLanguage::R));
+        assert!(snippet.starts_with(&format!("# {}\n# Title: Diabetes mellitus\n# Source: Manually created\n", HEADER)));
+        assert!(snippet.contains("# Version: 2024-12-19\n# Terminology: ICD-10, release 2019\n"));
+        assert!(snippet.ends_with("diabetes <- c(\n  \"E10\",\n  \"E11\"\n)\n"));
+
+        let options = SnippetOptions { language: SnippetLanguage::Python, variable_name: Some("2 diabetes-codes".to_string()), ..SnippetOptions::default() };
+        let snippet = codelist.to_snippet(&options);
+        assert!(snippet.ends_with("codelist_2_diabetes_codes = [\n    \"E10\",\n    \"E11\",\n]\ncodelist_2_diabetes_codes_set = set(codelist_2_diabetes_codes)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sas_snippet() -> Result<(), CodeListError> {
+        let snippet = create_test_codelist(2)?.to_snippet(&options(SnippetLanguage::Sas));
+        assert!(snippet.starts_with(&format!("/* {} */\n", HEADER)));
+        assert!(snippet.ends_with("\
+proc format;
+    value $diabetes
+        'E10', 'E11' = 'Y'
+        other = 'N';
+run;
+/* Use as: where put(code, $diabetes.) = 'Y'; or where code in (&diabetes); */
+%let diabetes = 'E10' 'E11';
+"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet_quoting() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.metadata.description = Some("Ends */ early\nand spans lines".to_string());
+        codelist.add_entry("O'Brien \"$x\"".to_string(), "Odd code".to_string(), None)?;
+
+        assert!(codelist.to_snippet(&options(SnippetLanguage::Stata)).contains("inlist(code, `\"O'Brien \"\\$x\"\"')"));
+        assert!(codelist.to_snippet(&options(SnippetLanguage::Python)).contains("\"O'Brien \\\"$x\\\"\""));
+        let sas = codelist.to_snippet(&options(SnippetLanguage::Sas));
+        assert!(sas.contains("/* Description: Ends * / early */\n/*   and spans lines */\n"));
+        assert!(sas.contains("'O''Brien \"$x\"' = 'Y'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_snippet() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let options = options(SnippetLanguage::R);
+        let file_path = temp_dir.path().join(format!("diabetes.{}", options.language.extension()));
+        let codelist = create_test_codelist(2)?;
+        codelist.save_to_snippet(file_path.to_str().unwrap(), &options)?;
+        assert_eq!(std::fs::read_to_string(&file_path)?, codelist.to_snippet(&options));
+        Ok(())
+    }
+}
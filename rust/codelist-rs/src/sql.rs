@@ -0,0 +1,403 @@
+//! This file contains SQL generation, for applying codelists in SQL Server and PostgreSQL databases
+
+// External imports
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::errors::CodeListError;
+use crate::types::CodeListType;
+use crate::compression::OutputFile;
+
+/// The most rows SQL Server accepts in the VALUES list of a single INSERT
+const SQL_SERVER_MAX_INSERT_ROWS: usize = 1000;
+
+/// Enum to represent the SQL dialects that scripts can be generated for
+///
+/// # Variants
+/// * `SqlServer` - Microsoft SQL Server (2016 or later)
+/// * `PostgreSql` - PostgreSQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SqlDialect {
+    SqlServer,
+    PostgreSql,
+}
+
+impl fmt::Display for SqlDialect {
+    /// Format the dialect as its product name
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SqlDialect::SqlServer => "SQL Server",
+            SqlDialect::PostgreSql => "PostgreSQL",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl SqlDialect {
+    /// Quote an identifier, such as a table or column name
+    ///
+    /// # Arguments
+    /// * `identifier` - The identifier
+    ///
+    /// # Returns
+    /// * `String` - The quoted identifier
+    pub fn quote_identifier(&self, identifier: &str) -> String {
+        match self {
+            SqlDialect::SqlServer => format!("[{}]", identifier.replace(']', "]]")),
+            SqlDialect::PostgreSql => format!("\"{}\"", identifier.replace('"', "\"\"")),
+        }
+    }
+
+    /// Quote a string literal, doubling single quotes
+    ///
+    /// SQL Server literals get the `N` prefix so that terms outside the code page are kept.
+    ///
+    /// # Arguments
+    /// * `value` - The string
+    ///
+    /// # Returns
+    /// * `String` - The quoted literal
+    pub fn quote_literal(&self, value: &str) -> String {
+        let escaped = value.replace('\'', "''");
+        match self {
+            SqlDialect::SqlServer => format!("N'{}'", escaped),
+            SqlDialect::PostgreSql => format!("'{}'", escaped),
+        }
+    }
+
+    /// Quote a code as a string literal, doubling single quotes
+    ///
+    /// Codes are plain ASCII, so SQL Server codes are not given the `N` prefix: comparing a
+    /// `VARCHAR` code column with an `NVARCHAR` literal converts the column and stops an index on
+    /// it from being used.
+    ///
+    /// # Arguments
+    /// * `code` - The code
+    ///
+    /// # Returns
+    /// * `String` - The quoted literal
+    pub fn quote_code_literal(&self, code: &str) -> String {
+        format!("'{}'", code.replace('\'', "''"))
+    }
+
+    /// Get the column type for text of at most a given length
+    ///
+    /// # Arguments
+    /// * `length` - The maximum length, or `None` for unlimited text
+    ///
+    /// # Returns
+    /// * `String` - The column type
+    fn text_type(&self, length: Option<usize>) -> String {
+        match (self, length) {
+            (SqlDialect::SqlServer, Some(length)) => format!("NVARCHAR({})", length),
+            (SqlDialect::SqlServer, None) => "NVARCHAR(MAX)".to_string(),
+            (SqlDialect::PostgreSql, Some(length)) => format!("VARCHAR({})", length),
+            (SqlDialect::PostgreSql, None) => "TEXT".to_string(),
+        }
+    }
+}
+
+/// Struct to represent the options for generating SQL from a codelist
+///
+/// # Fields
+/// * `dialect` - The SQL dialect
+/// * `table_name` - The table name, which may be schema-qualified (e.g. `dbo.codelist`), or `None` to
+///   use the codelist id (or "codelist" if it has none)
+/// * `temporary` - Whether to create a temporary table, dropping any existing one first
+/// * `batch_size` - The number of rows in each INSERT statement
+/// * `icd10_prefix_match` - Whether ICD10 predicates treat 3 character codes as prefixes, so that
+///   they match every code in the category
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlOptions {
+    pub dialect: SqlDialect,
+    pub table_name: Option<String>,
+    pub temporary: bool,
+    pub batch_size: usize,
+    pub icd10_prefix_match: bool,
+}
+
+impl Default for SqlOptions {
+    /// Default implementation for SqlOptions
+    ///
+    /// # Returns
+    /// * `SqlOptions` - A permanent SQL Server table, inserting 1000 rows at a time, with ICD10 prefix matching
+    fn default() -> Self {
+        Self {
+            dialect: SqlDialect::SqlServer,
+            table_name: None,
+            temporary: false,
+            batch_size: SQL_SERVER_MAX_INSERT_ROWS,
+            icd10_prefix_match: true,
+        }
+    }
+}
+
+impl CodeList {
+    /// Get the quoted name of the table the codelist is written to
+    ///
+    /// A schema-qualified table name such as `dbo.codelist` has each part quoted. SQL Server
+    /// temporary tables get the `#` prefix on the last part.
+    ///
+    /// # Arguments
+    /// * `options` - The SQL options
+    ///
+    /// # Returns
+    /// * `String` - The quoted table name
+    fn sql_table_name(&self, options: &SqlOptions) -> String {
+        let dialect = options.dialect;
+        let mut parts: Vec<String> = match &options.table_name {
+            Some(table_name) => table_name.split('.').map(str::to_string).collect(),
+            None => vec![self.id.as_ref().map(|id| id.replace('-', "_")).unwrap_or_else(|| "codelist".to_string())],
+        };
+        if let Some(name) = parts.last_mut() {
+            if options.temporary && dialect == SqlDialect::SqlServer && !name.starts_with('#') {
+                name.insert(0, '#');
+            }
+        }
+        parts.iter().map(|part| dialect.quote_identifier(part)).collect::<Vec<_>>().join(".")
+    }
+
+    /// Generate a SQL script that creates a table of the codelist entries and fills it
+    ///
+    /// The table has code, term and comment columns and is filled with batched INSERT statements,
+    /// in code order. Temporary tables are dropped first if they exist; SQL Server temporary table
+    /// names are given the `#` prefix.
+    ///
+    /// # Arguments
+    /// * `options` - The SQL options
+    ///
+    /// # Returns
+    /// * `Result<String, CodeListError>` - The SQL script or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidInput` - If the batch size is 0, or more than SQL Server allows
+    pub fn to_sql_script(&self, options: &SqlOptions) -> Result<String, CodeListError> {
+        let dialect = options.dialect;
+        if options.batch_size == 0 {
+            return Err(CodeListError::invalid_input("SQL batch size must be at least 1"));
+        }
+        if dialect == SqlDialect::SqlServer && options.batch_size > SQL_SERVER_MAX_INSERT_ROWS {
+            return Err(CodeListError::invalid_input(format!("SQL Server allows at most {} rows in an INSERT, found batch size: {}", SQL_SERVER_MAX_INSERT_ROWS, options.batch_size)));
+        }
+
+        let table = self.sql_table_name(options);
+        let entries = self.entries_sorted_by_code();
+        let code_length = entries.iter().map(|entry| entry.code.chars().count()).max().unwrap_or_default().max(50);
+
+        let mut script = format!(
+            "-- {} codelist {} with {} entries, for {}\n",
+            self.codelist_type,
+            self.name.as_deref().unwrap_or("without a name"),
+            entries.len(),
+            dialect,
+        );
+        if options.temporary {
+            script.push_str(&format!("DROP TABLE IF EXISTS {};\n", table));
+        }
+        script.push_str(&format!(
+            "CREATE {}TABLE {} (\n    {} {} NOT NULL,\n    {} {} NOT NULL,\n    {} {} NULL\n);\n",
+            if options.temporary && dialect == SqlDialect::PostgreSql { "TEMPORARY " } else { "" },
+            table,
+            dialect.quote_identifier("code"), dialect.text_type(Some(code_length)),
+            dialect.quote_identifier("term"), dialect.text_type(None),
+            dialect.quote_identifier("comment"), dialect.text_type(None),
+        ));
+
+        let columns = ["code", "term", "comment"].map(|column| dialect.quote_identifier(column)).join(", ");
+        for batch in entries.chunks(options.batch_size) {
+            let rows: Vec<String> = batch.iter().map(|entry| format!(
+                "    ({}, {}, {})",
+                dialect.quote_literal(&entry.code),
+                dialect.quote_literal(&entry.term),
+                entry.comment.as_deref().map(|comment| dialect.quote_literal(comment)).unwrap_or_else(|| "NULL".to_string()),
+            )).collect();
+            script.push_str(&format!("INSERT INTO {} ({}) VALUES\n{};\n", table, columns, rows.join(",\n")));
+        }
+        Ok(script)
+    }
+
+    /// Save a SQL script that creates and fills a table of the codelist entries
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file to save the script to
+    /// * `options` - The SQL options
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing to the file
+    /// * See `to_sql_script` for other errors
+    pub fn save_to_sql_script(&self, file_path: &str, options: &SqlOptions) -> Result<(), CodeListError> {
+        let mut file = OutputFile::create(file_path)?;
+        self.save_to_sql_script_writer(&mut file, options)?;
+        file.finish()
+    }
+
+    /// Write a SQL script that creates and fills a table of the codelist entries to any writer
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to write the script to
+    /// * `options` - The SQL options
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If an error occurs when writing
+    /// * See `to_sql_script` for other errors
+    pub fn save_to_sql_script_writer<W: Write>(&self, mut writer: W, options: &SqlOptions) -> Result<(), CodeListError> {
+        writer.write_all(self.to_sql_script(options)?.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Generate a SQL predicate that matches the codes of the codelist
+    ///
+    /// Codes are matched with `IN`. With `icd10_prefix_match`, a 3 character ICD10 code (or its X
+    /// code, such as "A01X") stands for its whole category and is matched with `LIKE 'A01%'`,
+    /// which covers codes stored with or without a dot; codes inside such a category are then
+    /// left out of the `IN` list. An empty codelist gives a predicate that matches nothing.
+    ///
+    /// # Arguments
+    /// * `column` - The column (or expression, such as `e.code`) to match, inserted as it is
+    /// * `options` - The SQL options
+    ///
+    /// # Returns
+    /// * `String` - The predicate
+    pub fn to_sql_predicate(&self, column: &str, options: &SqlOptions) -> String {
+        let dialect = options.dialect;
+        let prefixes: BTreeSet<String> = if options.icd10_prefix_match && self.codelist_type == CodeListType::ICD10 {
+            self.codes().into_iter()
+                .filter_map(|code| match code.chars().count() {
+                    3 => Some(code.clone()),
+                    4 if code.ends_with('X') => Some(code.chars().take(3).collect()),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        let exact: BTreeSet<&String> = self.codes().into_iter()
+            .filter(|code| !prefixes.iter().any(|prefix| code.starts_with(prefix.as_str())))
+            .collect();
+
+        let mut conditions = Vec::new();
+        if !exact.is_empty() {
+            let codes: Vec<String> = exact.iter().map(|code| dialect.quote_code_literal(code)).collect();
+            conditions.push(format!("{} IN ({})", column, codes.join(", ")));
+        }
+        for prefix in &prefixes {
+            let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            let escaped = match dialect {
+                SqlDialect::SqlServer => escaped.replace('[', "\\["),
+                SqlDialect::PostgreSql => escaped,
+            };
+            let pattern = dialect.quote_code_literal(&format!("{}%", escaped));
+            if escaped == *prefix {
+                conditions.push(format!("{} LIKE {}", column, pattern));
+            } else {
+                conditions.push(format!("{} LIKE {} ESCAPE '\\'", column, pattern));
+            }
+        }
+
+        match conditions.len() {
+            0 => "1 = 0".to_string(),
+            1 => conditions.remove(0),
+            _ => format!("({})", conditions.join(" OR ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_metadata;
+    use tempfile::tempdir;
+
+    fn create_test_codelist() -> Result<CodeList, CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.set_name("Diabetes");
+        codelist.add_entry("E11".to_string(), "Type 2 diabetes mellitus".to_string(), None)?;
+        codelist.add_entry("E11.9".to_string(), "Type 2 diabetes mellitus without complications".to_string(), None)?;
+        codelist.add_entry("E10.1".to_string(), "Type 1 diabetes mellitus with ketoacidosis".to_string(), Some("Patient's own report".to_string()))?;
+        Ok(codelist)
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(SqlDialect::SqlServer.quote_identifier("odd]name"), "[odd]]name]");
+        assert_eq!(SqlDialect::PostgreSql.quote_identifier("odd\"name"), "\"odd\"\"name\"");
+        assert_eq!(SqlDialect::SqlServer.quote_literal("Crohn's disease"), "N'Crohn''s disease'");
+        assert_eq!(SqlDialect::PostgreSql.quote_literal("Crohn's disease"), "'Crohn''s disease'");
+        assert_eq!(SqlDialect::SqlServer.quote_code_literal("A0'1"), "'A0''1'");
+    }
+
+    #[test]
+    fn test_to_sql_script_sql_server() -> Result<(), CodeListError> {
+        let options = SqlOptions { batch_size: 2, ..SqlOptions::default() };
+        let script = create_test_codelist()?.to_sql_script(&options)?;
+
+        assert_eq!(script, "\
+-- ICD10 codelist Diabetes with 3 entries, for SQL Server
+CREATE TABLE [diabetes] (
+    [code] NVARCHAR(50) NOT NULL,
+    [term] NVARCHAR(MAX) NOT NULL,
+    [comment] NVARCHAR(MAX) NULL
+);
+INSERT INTO [diabetes] ([code], [term], [comment]) VALUES
+    (N'E10.1', N'Type 1 diabetes mellitus with ketoacidosis', N'Patient''s own report'),
+    (N'E11', N'Type 2 diabetes mellitus', NULL);
+INSERT INTO [diabetes] ([code], [term], [comment]) VALUES
+    (N'E11.9', N'Type 2 diabetes mellitus without complications', NULL);
+");
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_sql_script_temporary_tables() -> Result<(), CodeListError> {
+        let codelist = create_test_codelist()?;
+
+        let options = SqlOptions { temporary: true, table_name: Some("dm".to_string()), ..SqlOptions::default() };
+        let script = codelist.to_sql_script(&options)?;
+        assert!(script.contains("DROP TABLE IF EXISTS [#dm];\nCREATE TABLE [#dm] ("));
+
+        let options = SqlOptions { table_name: Some("dbo.dm".to_string()), ..SqlOptions::default() };
+        let script = codelist.to_sql_script(&options)?;
+        assert!(script.contains("CREATE TABLE [dbo].[dm] ("));
+        assert!(script.contains("INSERT INTO [dbo].[dm] ([code], [term], [comment]) VALUES"));
+
+        let options = SqlOptions { dialect: SqlDialect::PostgreSql, temporary: true, ..SqlOptions::default() };
+        let script = codelist.to_sql_script(&options)?;
+        assert!(script.contains("DROP TABLE IF EXISTS \"diabetes\";\nCREATE TEMPORARY TABLE \"diabetes\" (\n    \"code\" VARCHAR(50) NOT NULL,"));
+        assert!(script.contains("('E10.1', 'Type 1 diabetes mellitus with ketoacidosis', 'Patient''s own report')"));
+
+        let options = SqlOptions { batch_size: 1001, ..SqlOptions::default() };
+        let error = codelist.to_sql_script(&options).unwrap_err();
+        assert!(matches!(error, CodeListError::InvalidInput { msg } if msg == "SQL Server allows at most 1000 rows in an INSERT, found batch size: 1001"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_sql_predicate() -> Result<(), CodeListError> {
+        let codelist = create_test_codelist()?;
+        let options = SqlOptions::default();
+        assert_eq!(codelist.to_sql_predicate("e.code", &options), "(e.code IN ('E10.1') OR e.code LIKE 'E11%')");
+
+        let options = SqlOptions { dialect: SqlDialect::PostgreSql, icd10_prefix_match: false, ..SqlOptions::default() };
+        assert_eq!(codelist.to_sql_predicate("code", &options), "code IN ('E10.1', 'E11', 'E11.9')");
+
+        let mut snomed_codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        assert_eq!(snomed_codelist.to_sql_predicate("code", &options), "1 = 0");
+        snomed_codelist.add_entry("123".to_string(), "Three digit code".to_string(), None)?;
+        assert_eq!(snomed_codelist.to_sql_predicate("code", &SqlOptions::default()), "code IN ('123')");
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_sql_script() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("diabetes.sql");
+        let codelist = create_test_codelist()?;
+        codelist.save_to_sql_script(file_path.to_str().unwrap(), &SqlOptions::default())?;
+        assert_eq!(std::fs::read_to_string(&file_path)?, codelist.to_sql_script(&SqlOptions::default())?);
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+//! This file contains the fixtures shared by the tests of the library
+
+// Internal imports
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_options::CodeListOptions;
+use crate::metadata::{Metadata, MetadataSource};
+use crate::types::CodeListType;
+
+/// Create the metadata used by test codelists
+pub(crate) fn create_test_metadata() -> Metadata {
+    Metadata {
+        source: MetadataSource::ManuallyCreated,
+        authors: Some(vec!["Caroline Morton".to_string()]),
+        version: Some("2024-12-19".parse().unwrap()),
+        description: Some("A test codelist".to_string()),
+        ..Metadata::default()
+    }
+}
+
+/// Create a codelist factory with default options and the test metadata
+///
+/// # Arguments
+/// * `codelist_type` - The type of the codelists the factory loads
+pub(crate) fn create_test_codelist_factory(codelist_type: CodeListType) -> CodeListFactory {
+    CodeListFactory::new(CodeListOptions::default(), create_test_metadata(), codelist_type)
+}
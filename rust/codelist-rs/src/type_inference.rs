@@ -0,0 +1,489 @@
+//! This file contains codelist type inference, so that folders mixing SNOMED, ICD10 and OPCS
+//! codelists can be loaded in one call
+//!
+//! The type is taken from a type column when there is one. Otherwise each type is scored by the
+//! share of codes that have its shape, and column headers such as `snomedcode` only break ties
+//! between types the codes fit equally well.
+
+// External imports
+use std::collections::HashSet;
+use serde::Serialize;
+use serde_json::Value;
+
+// Internal imports
+use crate::codelist::CodeList;
+use crate::codelist_factory::CodeListFactory;
+use crate::codelist_format::{strip_utf8_bom, CodeListFormat};
+use crate::compression::{decompress_if_compressed, strip_compression_extension};
+use crate::errors::CodeListError;
+use crate::schema::is_saved_codelist_json;
+use crate::types::CodeListType;
+
+/// The lowest score the inferred type can have
+pub const MIN_TYPE_CONFIDENCE: f64 = 0.5;
+
+/// How far ahead of the next best type the inferred type must score
+pub const AMBIGUITY_MARGIN: f64 = 0.1;
+
+/// The types that can be inferred, in the order their scores are reported
+const CODELIST_TYPES: [CodeListType; 3] = [CodeListType::ICD10, CodeListType::SNOMED, CodeListType::OPCS];
+
+/// Struct to represent the inferred type of a codelist
+///
+/// # Fields
+/// * `codelist_type` - The inferred type
+/// * `confidence` - The score of the inferred type, from 0 to 1
+/// * `scores` - The score of every type, from 0 to 1
+/// * `evidence` - What the type was inferred from, e.g. "codes and headers"
+/// * `code_column` - The column holding the codes, if a header naming a coding system was used
+///   because no column has the configured code header
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeInference {
+    pub codelist_type: CodeListType,
+    pub confidence: f64,
+    pub scores: Vec<(CodeListType, f64)>,
+    pub evidence: String,
+    pub code_column: Option<String>,
+}
+
+/// Get the codelist type named by a label, such as a column header or a type column value
+///
+/// Case and punctuation are ignored, so "SNOMED CT", "snomed_code", "ICD-10" and "OPCS4" are all
+/// recognised.
+///
+/// # Arguments
+/// * `label` - The label
+///
+/// # Returns
+/// * `Option<CodeListType>` - The type, if the label names one
+pub fn codelist_type_from_label(label: &str) -> Option<CodeListType> {
+    let label: String = label.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+    if label.contains("snomed") || label == "sct" || label == "sctid" {
+        Some(CodeListType::SNOMED)
+    } else if label.contains("icd") {
+        Some(CodeListType::ICD10)
+    } else if label.contains("opcs") {
+        Some(CodeListType::OPCS)
+    } else {
+        None
+    }
+}
+
+/// Check whether a code has the shape of a code of the given type
+///
+/// The shapes are those of the regexes in codelist-validator-rs, so a category such as "A01" has
+/// the shape of both ICD10 and OPCS.
+///
+/// # Arguments
+/// * `codelist_type` - The type to check against
+/// * `code` - The code
+///
+/// # Returns
+/// * `bool` - True if the code has the shape of the type
+pub fn code_has_shape_of(codelist_type: &CodeListType, code: &str) -> bool {
+    let code = code.trim().to_uppercase();
+    let chars: Vec<char> = code.chars().collect();
+    let all_digits = |chars: &[char]| chars.iter().all(char::is_ascii_digit);
+    let category = chars.len() >= 3 && chars[0].is_ascii_uppercase() && all_digits(&chars[1..3]);
+
+    match codelist_type {
+        CodeListType::SNOMED => (6..=18).contains(&chars.len()) && all_digits(&chars),
+        CodeListType::ICD10 => category && match &chars[3..] {
+            [] | ['X'] => true,
+            ['.', rest @ ..] => (1..=3).contains(&rest.len()) && all_digits(rest),
+            rest => rest.len() <= 4 && all_digits(rest),
+        },
+        CodeListType::OPCS => category && match &chars[3..] {
+            ['.', rest @ ..] => (1..=2).contains(&rest.len()) && all_digits(rest),
+            rest => rest.len() <= 2 && all_digits(rest),
+        },
+    }
+}
+
+/// Infer the type of a codelist from its column headers, codes and type column
+///
+/// If there are type column values they decide the type, with full confidence. Otherwise each type
+/// scores the share of codes with its shape. The headers are only used when the codes are
+/// inconclusive: if they name exactly one coding system and that type is among the best fitting
+/// types (such as OPCS for "A01", which could also be ICD10), or there are no codes, the named
+/// type is chosen. A header naming another coding system, such as an `icd10_map` column in a
+/// SNOMED codelist, cannot outweigh the codes.
+///
+/// # Arguments
+/// * `headers` - The column headers
+/// * `codes` - The codes
+/// * `type_labels` - The values of the type column, empty if there is none
+///
+/// # Returns
+/// * `Result<TypeInference, CodeListError>` - The inferred type or an error
+///
+/// # Errors
+/// * `CodeListError::InvalidCodeListType` - If a type column value does not name a type
+/// * `CodeListError::AmbiguousCodeListType` - If the type column names several types, or no type
+///   scores at least `MIN_TYPE_CONFIDENCE` and `AMBIGUITY_MARGIN` ahead of the others
+pub fn infer_codelist_type(headers: &[String], codes: &[String], type_labels: &[String]) -> Result<TypeInference, CodeListError> {
+    let labelled_types = type_labels.iter()
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty())
+        .map(|label| codelist_type_from_label(label).ok_or_else(|| CodeListError::invalid_code_list_type(label)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(first) = labelled_types.first() {
+        if let Some(other) = labelled_types.iter().find(|codelist_type| *codelist_type != first) {
+            return Err(CodeListError::ambiguous_code_list_type(format!("Type column contains more than one type: {} and {}", first, other)));
+        }
+        return Ok(TypeInference {
+            codelist_type: first.clone(),
+            confidence: 1.0,
+            scores: CODELIST_TYPES.iter().map(|codelist_type| (codelist_type.clone(), if codelist_type == first { 1.0 } else { 0.0 })).collect(),
+            evidence: "type column".to_string(),
+            code_column: None,
+        });
+    }
+
+    let hinted_types: Vec<CodeListType> = headers.iter().filter_map(|header| codelist_type_from_label(header)).collect();
+    let hint = match hinted_types.as_slice() {
+        [first, rest @ ..] if rest.iter().all(|codelist_type| codelist_type == first) => Some(first.clone()),
+        _ => None,
+    };
+    if codes.is_empty() && hint.is_none() {
+        return Err(CodeListError::ambiguous_code_list_type("There are no codes or headers to infer the type from"));
+    }
+
+    let scores: Vec<(CodeListType, f64)> = CODELIST_TYPES.iter().map(|codelist_type| {
+        let coverage = if codes.is_empty() {
+            0.0
+        } else {
+            codes.iter().filter(|code| code_has_shape_of(codelist_type, code)).count() as f64 / codes.len() as f64
+        };
+        (codelist_type.clone(), coverage)
+    }).collect();
+
+    let mut ranked = scores.clone();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let (best_type, best_score) = ranked[0].clone();
+    let (next_type, next_score) = ranked[1].clone();
+    let conclusive = best_score >= MIN_TYPE_CONFIDENCE && best_score - next_score >= AMBIGUITY_MARGIN;
+    if conclusive {
+        return Ok(TypeInference {
+            codelist_type: best_type,
+            confidence: best_score,
+            scores,
+            evidence: "codes".to_string(),
+            code_column: None,
+        });
+    }
+
+    let hinted_score = hint.as_ref()
+        .and_then(|hinted| scores.iter().find(|(codelist_type, _)| codelist_type == hinted))
+        .map(|(_, score)| *score);
+    match (hint, hinted_score) {
+        (Some(hinted), Some(_)) if codes.is_empty() => Ok(TypeInference {
+            codelist_type: hinted,
+            confidence: MIN_TYPE_CONFIDENCE,
+            scores,
+            evidence: "headers".to_string(),
+            code_column: None,
+        }),
+        (Some(hinted), Some(score)) if best_score >= MIN_TYPE_CONFIDENCE && best_score - score < AMBIGUITY_MARGIN => Ok(TypeInference {
+            codelist_type: hinted,
+            confidence: score,
+            scores,
+            evidence: "codes and headers".to_string(),
+            code_column: None,
+        }),
+        _ if best_score < MIN_TYPE_CONFIDENCE => Err(CodeListError::ambiguous_code_list_type(format!("No type fits the codes, the best is {} ({:.2})", best_type, best_score))),
+        _ => Err(CodeListError::ambiguous_code_list_type(format!("The codes fit both {} ({:.2}) and {} ({:.2})", best_type, best_score, next_type, next_score))),
+    }
+}
+
+/// Struct to represent the columns of a file that type inference looks at
+///
+/// # Fields
+/// * `headers` - The column headers
+/// * `codes` - The values of the code column
+/// * `type_labels` - The values of the type column, if there is one
+/// * `code_column` - The code column, if it was found from a header naming a coding system
+#[derive(Default)]
+struct TypeSample {
+    headers: Vec<String>,
+    codes: Vec<String>,
+    type_labels: Vec<String>,
+    code_column: Option<String>,
+}
+
+impl CodeListFactory {
+    /// Infer the type of the codelist in a file
+    ///
+    /// The code column is the one with the configured code header or, failing that, a header that
+    /// names a coding system (such as `snomedcode`). The type column is set with the
+    /// `type_column_name` option. A codelist saved with `save_to_json` already records its type,
+    /// which is used with full confidence. See `infer_codelist_type` for how the type is scored.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Result<TypeInference, CodeListError>` - The inferred type or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::IOError` - If there is an error reading the file
+    /// * See the loader of each format for errors reading the file
+    /// * See `infer_codelist_type` for errors inferring the type
+    pub fn infer_codelist_type_from_file(&self, file_path: &str) -> Result<TypeInference, CodeListError> {
+        let bytes = decompress_if_compressed(std::fs::read(file_path)?)?;
+        let format = CodeListFormat::from_path(strip_compression_extension(file_path))
+            .unwrap_or_else(|| CodeListFormat::detect(&bytes));
+        let sample = match format {
+            CodeListFormat::Csv => {
+                let mut rdr = self.csv_reader(bytes.as_slice())?;
+                let headers: Vec<String> = rdr.headers()?.iter().map(str::to_string).collect();
+                let rows = rdr.records()
+                    .filter_map(Result::ok)
+                    .map(|record| record.iter().map(str::to_string).collect())
+                    .collect();
+                self.type_sample_from_rows(headers, rows)
+            }
+            CodeListFormat::Xlsx => {
+                let range = self.xlsx_sheet_range(bytes.as_slice())?;
+                let mut rows = range.rows().map(|row| row.iter().map(|cell| cell.to_string().trim().to_string()).collect::<Vec<String>>());
+                let headers = rows.next().unwrap_or_default();
+                self.type_sample_from_rows(headers, rows.collect())
+            }
+            CodeListFormat::Json => {
+                let json_data: Value = serde_json::from_slice(strip_utf8_bom(&bytes))?;
+                if is_saved_codelist_json(&json_data) {
+                    let codelist = CodeList::from_saved_json(json_data)?;
+                    return Ok(TypeInference {
+                        scores: CODELIST_TYPES.iter().map(|codelist_type| (codelist_type.clone(), if *codelist_type == codelist.codelist_type { 1.0 } else { 0.0 })).collect(),
+                        codelist_type: codelist.codelist_type,
+                        confidence: 1.0,
+                        evidence: "saved codelist".to_string(),
+                        code_column: None,
+                    });
+                }
+                let objects = json_data.as_array().cloned().unwrap_or_default();
+                let mut headers: Vec<String> = Vec::new();
+                for key in objects.iter().filter_map(Value::as_object).flat_map(|object| object.keys()) {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+                let rows = objects.iter().map(|object| headers.iter().map(|header| match object.get(header) {
+                    Some(Value::String(value)) => value.trim().to_string(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(value) => value.to_string(),
+                }).collect()).collect();
+                self.type_sample_from_rows(headers, rows)
+            }
+            #[cfg(feature = "arrow")]
+            CodeListFormat::ArrowIpc => self.type_sample_from_codelist(bytes.as_slice(), format)?,
+            #[cfg(feature = "parquet")]
+            CodeListFormat::Parquet => self.type_sample_from_codelist(bytes.as_slice(), format)?,
+        };
+
+        let mut inference = infer_codelist_type(&sample.headers, &sample.codes, &sample.type_labels)?;
+        inference.code_column = sample.code_column;
+        Ok(inference)
+    }
+
+    /// Load a codelist in a columnar format so that its codes can be used for type inference
+    ///
+    /// # Arguments
+    /// * `bytes` - The contents of the file
+    /// * `format` - The format of the file
+    ///
+    /// # Returns
+    /// * `Result<TypeSample, CodeListError>` - The codes of the codelist or an error
+    #[cfg(any(feature = "arrow", feature = "parquet"))]
+    fn type_sample_from_codelist(&self, bytes: &[u8], format: CodeListFormat) -> Result<TypeSample, CodeListError> {
+        let codelist = self.load_codelist_from_reader(bytes, Some(format))?;
+        Ok(TypeSample {
+            codes: codelist.codes().into_iter().cloned().collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Pick out the columns type inference looks at from the rows of a table
+    ///
+    /// # Arguments
+    /// * `headers` - The column headers
+    /// * `rows` - The rows, as cell values
+    ///
+    /// # Returns
+    /// * `TypeSample` - The headers, codes and type column values
+    fn type_sample_from_rows(&self, headers: Vec<String>, rows: Vec<Vec<String>>) -> TypeSample {
+        let configured_code_idx = headers.iter().position(|header| self.header_matches(header, &self.codelist_options.code_column_name)
+            || self.header_matches(header, &self.codelist_options.code_field_name));
+        let hinted_headers: Vec<usize> = (0..headers.len()).filter(|idx| codelist_type_from_label(&headers[*idx]).is_some()).collect();
+        let hinted_code_idx = hinted_headers.iter()
+            .find(|idx| {
+                let header = headers[**idx].to_lowercase();
+                header.contains("code") || header.ends_with("id")
+            })
+            .or(hinted_headers.first())
+            .copied();
+        let code_idx = configured_code_idx.or(hinted_code_idx);
+        let type_idx = self.codelist_options.type_column_name.as_ref()
+            .and_then(|name| headers.iter().position(|header| self.header_matches(header, name)));
+
+        let column_values = |idx: Option<usize>| -> Vec<String> {
+            let Some(idx) = idx else { return Vec::new() };
+            let mut seen = HashSet::new();
+            rows.iter()
+                .filter_map(|row| row.get(idx))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty() && seen.insert(value.clone()))
+                .collect()
+        };
+
+        TypeSample {
+            codes: column_values(code_idx),
+            type_labels: column_values(type_idx),
+            code_column: if configured_code_idx.is_none() { code_idx.map(|idx| headers[idx].clone()) } else { None },
+            headers,
+        }
+    }
+
+    /// Load a codelist from a file, inferring its type from the file contents
+    ///
+    /// The type is inferred with `infer_codelist_type_from_file` and the file is then loaded with
+    /// `load_codelist_from_file` as if the factory had that type. A log entry records the inferred
+    /// type and its confidence.
+    ///
+    /// # Arguments
+    /// * `file_path` - The path to the file
+    ///
+    /// # Returns
+    /// * `Result<(CodeList, TypeInference), CodeListError>` - The codelist and how its type was inferred, or an error
+    ///
+    /// # Errors
+    /// * See `infer_codelist_type_from_file` and `load_codelist_from_file`
+    pub fn load_codelist_from_file_inferring_type(&self, file_path: &str) -> Result<(CodeList, TypeInference), CodeListError> {
+        let inference = self.infer_codelist_type_from_file(file_path)?;
+
+        let mut codelist_options = self.codelist_options.clone();
+        if let Some(code_column) = &inference.code_column {
+            codelist_options.code_column_name = code_column.clone();
+            codelist_options.code_field_name = code_column.clone();
+        }
+        let mut factory = CodeListFactory::new(codelist_options, self.metadata.clone(), inference.codelist_type.clone());
+        factory.processing_steps = self.processing_steps.clone();
+
+        let format = CodeListFormat::from_path(strip_compression_extension(file_path));
+        let mut codelist = factory.load_codelist_from_file_with_format(file_path, format)?;
+        codelist.add_log(format!(
+            "Inferred codelist type {} from {} (confidence: {:.2})",
+            inference.codelist_type, inference.evidence, inference.confidence
+        ));
+        Ok((codelist, inference))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_codelist_factory;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_codelist_type_from_label() {
+        assert_eq!(codelist_type_from_label("SNOMED CT"), Some(CodeListType::SNOMED));
+        assert_eq!(codelist_type_from_label("snomedcode"), Some(CodeListType::SNOMED));
+        assert_eq!(codelist_type_from_label("ICD-10"), Some(CodeListType::ICD10));
+        assert_eq!(codelist_type_from_label("opcs4_code"), Some(CodeListType::OPCS));
+        assert_eq!(codelist_type_from_label("code"), None);
+    }
+
+    #[test]
+    fn test_code_has_shape_of() {
+        assert!(code_has_shape_of(&CodeListType::SNOMED, "22298006"));
+        assert!(!code_has_shape_of(&CodeListType::SNOMED, "A01"));
+        assert!(code_has_shape_of(&CodeListType::ICD10, "A01.1"));
+        assert!(code_has_shape_of(&CodeListType::ICD10, "A01X"));
+        assert!(code_has_shape_of(&CodeListType::OPCS, "K40.1"));
+        assert!(code_has_shape_of(&CodeListType::OPCS, "I21"));
+        assert!(code_has_shape_of(&CodeListType::OPCS, "O01.1"));
+        assert!(!code_has_shape_of(&CodeListType::OPCS, "A01X"));
+    }
+
+    #[test]
+    fn test_infer_codelist_type() -> Result<(), CodeListError> {
+        let inference = infer_codelist_type(&strings(&["code", "term"]), &strings(&["22298006", "195967001"]), &[])?;
+        assert_eq!(inference.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.confidence, 1.0);
+        assert_eq!(inference.evidence, "codes");
+
+        let inference = infer_codelist_type(&strings(&["code", "term"]), &strings(&["I21", "I21.0", "A01X", "B20.123"]), &[])?;
+        assert_eq!(inference.codelist_type, CodeListType::ICD10);
+
+        // A01 and B02 fit both ICD10 and OPCS, so the header decides
+        let error = infer_codelist_type(&strings(&["code", "term"]), &strings(&["A01", "B02"]), &[]).unwrap_err();
+        assert!(matches!(error, CodeListError::AmbiguousCodeListType { msg } if msg == "The codes fit both ICD10 (1.00) and OPCS (1.00)"));
+        let inference = infer_codelist_type(&strings(&["opcs_code", "term"]), &strings(&["A01", "B02"]), &[])?;
+        assert_eq!(inference.codelist_type, CodeListType::OPCS);
+        assert_eq!(inference.scores, vec![(CodeListType::ICD10, 1.0), (CodeListType::SNOMED, 0.0), (CodeListType::OPCS, 1.0)]);
+        assert_eq!(inference.evidence, "codes and headers");
+
+        // A header naming another coding system does not outweigh the codes
+        let inference = infer_codelist_type(&strings(&["code", "term", "icd10_map"]), &strings(&["22298006", "195967001"]), &[])?;
+        assert_eq!(inference.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.confidence, 1.0);
+        assert_eq!(inference.evidence, "codes");
+        let error = infer_codelist_type(&strings(&["code", "icd10_map"]), &strings(&["hello", "world"]), &[]).unwrap_err();
+        assert!(matches!(error, CodeListError::AmbiguousCodeListType { msg } if msg == "No type fits the codes, the best is ICD10 (0.00)"));
+
+        let inference = infer_codelist_type(&strings(&["snomed_code"]), &[], &[])?;
+        assert_eq!(inference.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.evidence, "headers");
+
+        let inference = infer_codelist_type(&[], &strings(&["A01"]), &strings(&["SNOMED CT"]))?;
+        assert_eq!(inference.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.evidence, "type column");
+        let error = infer_codelist_type(&[], &[], &strings(&["SNOMED", "ICD10"])).unwrap_err();
+        assert!(matches!(error, CodeListError::AmbiguousCodeListType { msg } if msg == "Type column contains more than one type: SNOMED and ICD10"));
+
+        let error = infer_codelist_type(&strings(&["code"]), &strings(&["hello", "world"]), &[]).unwrap_err();
+        assert!(matches!(error, CodeListError::AmbiguousCodeListType { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_codelist_from_file_inferring_type() -> Result<(), CodeListError> {
+        let temp_dir = tempdir()?;
+        let factory = create_test_codelist_factory(CodeListType::ICD10);
+
+        let snomed_path = temp_dir.path().join("snomed.csv");
+        fs::write(&snomed_path, "snomedcode,term\n22298006,Myocardial infarction\n195967001,Asthma\n")?;
+        let (codelist, inference) = factory.load_codelist_from_file_inferring_type(snomed_path.to_str().unwrap())?;
+        assert_eq!(codelist.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.code_column, Some("snomedcode".to_string()));
+        assert_eq!(codelist.entries.len(), 2);
+        assert_eq!(codelist.logs.last(), Some(&"Inferred codelist type SNOMED from codes (confidence: 1.00)".to_string()));
+
+        let snomed_json_path = temp_dir.path().join("snomed.json");
+        fs::write(&snomed_json_path, r#"[{"snomedcode": "22298006", "term": "Myocardial infarction"}]"#)?;
+        let (codelist, inference) = factory.load_codelist_from_file_inferring_type(snomed_json_path.to_str().unwrap())?;
+        assert_eq!(codelist.codelist_type, CodeListType::SNOMED);
+        assert_eq!(inference.code_column, Some("snomedcode".to_string()));
+        assert!(codelist.codes().contains(&"22298006".to_string()));
+
+        let mut factory = create_test_codelist_factory(CodeListType::ICD10);
+        factory.codelist_options.type_column_name = Some("coding_system".to_string());
+        let opcs_path = temp_dir.path().join("opcs.json");
+        fs::write(&opcs_path, r#"[{"code": "K40.1", "term": "Saphenous vein graft", "coding_system": "OPCS-4"}]"#)?;
+        let (codelist, inference) = factory.load_codelist_from_file_inferring_type(opcs_path.to_str().unwrap())?;
+        assert_eq!(codelist.codelist_type, CodeListType::OPCS);
+        assert_eq!(inference.evidence, "type column");
+
+        let ambiguous_path = temp_dir.path().join("ambiguous.csv");
+        fs::write(&ambiguous_path, "code,term\nA01,Something\n")?;
+        let error = factory.load_codelist_from_file_inferring_type(ambiguous_path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, CodeListError::AmbiguousCodeListType { .. }));
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! This file defines the different types of codelists that can be used
 
 /// External imports
+use std::fmt;
 use std::str::FromStr;
 use serde::{Serialize, Deserialize};
 
@@ -44,17 +45,18 @@ impl FromStr for CodeListType {
     }
 }
 
-impl ToString for CodeListType {
-    /// Convert a CodeListType to a string
+impl fmt::Display for CodeListType {
+    /// Format a CodeListType as a string
     ///
     /// # Returns
-    /// * `String` - The string representation of the CodeListType
-    fn to_string(&self) -> String {
-        match self {
-            CodeListType::ICD10 => "ICD10".to_string(),
-            CodeListType::SNOMED => "SNOMED".to_string(),
-            CodeListType::OPCS => "OPCS".to_string(),
-        }
+    /// * `fmt::Result` - The string representation of the CodeListType
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CodeListType::ICD10 => "ICD10",
+            CodeListType::SNOMED => "SNOMED",
+            CodeListType::OPCS => "OPCS",
+        };
+        write!(f, "{}", s)
     }
 }
 
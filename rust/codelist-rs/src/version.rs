@@ -0,0 +1,221 @@
+//! This file contains the version of a codelist, which is either a date or a semantic version
+//!
+//! Versions are validated when they are parsed, so a codelist can only hold a date such as
+//! `2024-12-19` or a semantic version such as `1.2.0`. Versions are saved as their string form.
+
+// External imports
+use std::fmt;
+use std::str::FromStr;
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+// Internal imports
+use crate::errors::CodeListError;
+
+/// The format of date versions
+const DATE_VERSION_FORMAT: &str = "%Y-%m-%d";
+
+/// Enum to represent the version of a codelist
+///
+/// Versions are ordered by date or by semantic version precedence. Date versions are ordered
+/// before semantic versions, so that a codelist moving from dates to semantic versions keeps
+/// increasing.
+///
+/// # Variants
+/// * `Date` - A date version, written as `YYYY-MM-DD`
+/// * `Semantic` - A semantic version, written as `MAJOR.MINOR.PATCH` with optional pre-release and build parts
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum CodeListVersion {
+    Date(NaiveDate),
+    Semantic(semver::Version),
+}
+
+/// Enum to represent which part of a version to increase when bumping it
+///
+/// # Variants
+/// * `Major` - Increase the major version, for changes that alter which records a codelist selects
+/// * `Minor` - Increase the minor version, for additions that keep existing codes
+/// * `Patch` - Increase the patch version, for corrections such as to terms or comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl fmt::Display for CodeListVersion {
+    /// Format the version as a date or semantic version string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeListVersion::Date(date) => write!(f, "{}", date.format(DATE_VERSION_FORMAT)),
+            CodeListVersion::Semantic(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+impl FromStr for CodeListVersion {
+    type Err = CodeListError;
+
+    /// Parse a version from a date or semantic version string
+    ///
+    /// # Arguments
+    /// * `s` - The version, e.g. "2024-12-19" or "1.2.0"
+    ///
+    /// # Returns
+    /// * `Result<CodeListVersion, CodeListError>` - The version or an error
+    ///
+    /// # Errors
+    /// * `CodeListError::InvalidVersion` - If the string is not a valid date or semantic version
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, DATE_VERSION_FORMAT) {
+            // Only accept the zero padded form, so that the version is saved as it was given
+            if date.format(DATE_VERSION_FORMAT).to_string() == s {
+                return Ok(CodeListVersion::Date(date));
+            }
+        }
+        semver::Version::parse(s)
+            .map(CodeListVersion::Semantic)
+            .map_err(|_| CodeListError::invalid_version(s))
+    }
+}
+
+impl TryFrom<String> for CodeListVersion {
+    type Error = CodeListError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CodeListVersion> for String {
+    fn from(version: CodeListVersion) -> Self {
+        version.to_string()
+    }
+}
+
+impl CodeListVersion {
+    /// Create a semantic version
+    ///
+    /// # Arguments
+    /// * `major` - The major version
+    /// * `minor` - The minor version
+    /// * `patch` - The patch version
+    ///
+    /// # Returns
+    /// * `CodeListVersion` - The semantic version
+    pub fn semantic(major: u64, minor: u64, patch: u64) -> CodeListVersion {
+        CodeListVersion::Semantic(semver::Version::new(major, minor, patch))
+    }
+
+    /// Get the version after this one, for a codelist edited after it was published
+    ///
+    /// Semantic versions increase the given part and reset the parts after it, dropping any
+    /// pre-release and build parts. Date versions move to today, or to the day after this version
+    /// if that is not before today, so the new version is always greater.
+    ///
+    /// # Arguments
+    /// * `bump` - Which part of a semantic version to increase
+    ///
+    /// # Returns
+    /// * `CodeListVersion` - The next version
+    pub fn bumped(&self, bump: VersionBump) -> CodeListVersion {
+        self.bumped_on(bump, Utc::now().date_naive())
+    }
+
+    /// Get the version after this one, taking date versions to the given day
+    ///
+    /// # Arguments
+    /// * `bump` - Which part of a semantic version to increase
+    /// * `today` - The date to move date versions to, if it is after this version
+    ///
+    /// # Returns
+    /// * `CodeListVersion` - The next version
+    pub fn bumped_on(&self, bump: VersionBump, today: NaiveDate) -> CodeListVersion {
+        match self {
+            CodeListVersion::Date(date) => {
+                let next_day = date.checked_add_days(Days::new(1)).unwrap_or(*date);
+                CodeListVersion::Date(today.max(next_day))
+            }
+            CodeListVersion::Semantic(version) => {
+                let (major, minor, patch) = (version.major, version.minor, version.patch);
+                match bump {
+                    VersionBump::Major => CodeListVersion::semantic(major + 1, 0, 0),
+                    VersionBump::Minor => CodeListVersion::semantic(major, minor + 1, 0),
+                    // A pre-release comes before its release, so bumping it releases it
+                    VersionBump::Patch if !version.pre.is_empty() => CodeListVersion::semantic(major, minor, patch),
+                    VersionBump::Patch => CodeListVersion::semantic(major, minor, patch + 1),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() -> Result<(), CodeListError> {
+        assert_eq!("2024-12-19".parse::<CodeListVersion>()?, CodeListVersion::Date(date(2024, 12, 19)));
+        assert_eq!("1.2.0".parse::<CodeListVersion>()?, CodeListVersion::semantic(1, 2, 0));
+        for version in ["2024-12-19", "1.2.0", "2.0.0-rc.1", "1.0.0+build.5"] {
+            assert_eq!(version.parse::<CodeListVersion>()?.to_string(), version);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_versions() {
+        for version in ["", "3", "1.2", "v1.2.0", "2024-2-5", "2024-02-30", "5ec2d4d6", "19/12/2024"] {
+            let error = version.parse::<CodeListVersion>().unwrap_err();
+            assert!(matches!(error, CodeListError::InvalidVersion { version: v } if v == version));
+        }
+        assert_eq!(
+            "1.2".parse::<CodeListVersion>().unwrap_err().to_string(),
+            "Invalid codelist version: 1.2. Versions must be a date (YYYY-MM-DD) or a semantic version (MAJOR.MINOR.PATCH)",
+        );
+    }
+
+    #[test]
+    fn test_ordering() -> Result<(), CodeListError> {
+        let mut versions: Vec<CodeListVersion> = ["1.10.0", "2024-12-19", "1.2.0", "1.2.0-rc.1", "2023-01-31"]
+            .iter()
+            .map(|version| version.parse())
+            .collect::<Result<_, _>>()?;
+        versions.sort();
+        let sorted: Vec<String> = versions.iter().map(CodeListVersion::to_string).collect();
+        assert_eq!(sorted, vec!["2023-01-31", "2024-12-19", "1.2.0-rc.1", "1.2.0", "1.10.0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bumped() -> Result<(), CodeListError> {
+        let version: CodeListVersion = "1.2.3".parse()?;
+        assert_eq!(version.bumped(VersionBump::Major), CodeListVersion::semantic(2, 0, 0));
+        assert_eq!(version.bumped(VersionBump::Minor), CodeListVersion::semantic(1, 3, 0));
+        assert_eq!(version.bumped(VersionBump::Patch), CodeListVersion::semantic(1, 2, 4));
+        assert_eq!("1.2.3-rc.1".parse::<CodeListVersion>()?.bumped(VersionBump::Patch), version);
+
+        let version = CodeListVersion::Date(date(2024, 12, 19));
+        assert_eq!(version.bumped_on(VersionBump::Minor, date(2025, 1, 2)), CodeListVersion::Date(date(2025, 1, 2)));
+        assert_eq!(version.bumped_on(VersionBump::Minor, date(2024, 12, 19)), CodeListVersion::Date(date(2024, 12, 20)));
+        assert!(version.bumped(VersionBump::Patch) > version);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_as_string() -> Result<(), serde_json::Error> {
+        let version = CodeListVersion::semantic(1, 0, 0);
+        assert_eq!(serde_json::to_string(&version)?, "\"1.0.0\"");
+        assert_eq!(serde_json::from_str::<CodeListVersion>("\"2024-12-19\"")?, CodeListVersion::Date(date(2024, 12, 19)));
+
+        let error = serde_json::from_str::<CodeListVersion>("\"draft\"").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid codelist version: draft."));
+        Ok(())
+    }
+}
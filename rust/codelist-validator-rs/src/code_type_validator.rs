@@ -0,0 +1,70 @@
+use codelist_rs::codelist::CodeList;
+use codelist_rs::processing::CodeValidator;
+use codelist_rs::types::CodeListType;
+use crate::icd10_validator::ICD10Validator;
+use crate::opcs_validator::OPCSValidator;
+use crate::snomed_validator::{SNOMEDValidator, MAX_LENGTH, MIN_LENGTH};
+
+/// Validator that checks each code with the validator for the type of its codelist
+///
+/// It can be used in a `ProcessingStep::Validate` step of a codelist processing pipeline. SNOMED
+/// codes are checked with the default length limits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodeTypeValidator;
+
+impl CodeValidator for CodeTypeValidator {
+    /// Find the codes in the codelist that are not valid for its type
+    ///
+    /// # Arguments
+    ///
+    /// * `codelist`: the codelist to check
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, String)>`: each invalid code, sorted by code, with the reason it is invalid
+    fn invalid_codes(&self, codelist: &CodeList) -> Vec<(String, String)> {
+        codelist.entries_sorted_by_code().into_iter().filter_map(|entry| {
+            let result = match codelist.codelist_type {
+                CodeListType::ICD10 => ICD10Validator::validate_code(codelist, &entry.code),
+                CodeListType::SNOMED => SNOMEDValidator::validate_code(codelist, &entry.code, MIN_LENGTH, MAX_LENGTH),
+                CodeListType::OPCS => OPCSValidator::validate_code(codelist, &entry.code),
+            };
+            result.err().map(|err| (entry.code.clone(), err.to_string()))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::test_utils::create_test_metadata;
+    use codelist_rs::errors::CodeListError;
+    use codelist_rs::processing::ProcessingStep;
+
+    #[test]
+    fn test_invalid_codes() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::ICD10, create_test_metadata(), None);
+        codelist.add_entry("A009".to_string(), "Cholera, unspecified".to_string(), None)?;
+        codelist.add_entry("1009".to_string(), "Not a code".to_string(), None)?;
+
+        let invalid_codes = CodeTypeValidator.invalid_codes(&codelist);
+        assert_eq!(invalid_codes.len(), 1);
+        assert_eq!(invalid_codes[0].0, "1009");
+        assert!(invalid_codes[0].1.contains("does not match the expected format"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_step_removes_invalid_codes() -> Result<(), CodeListError> {
+        let mut codelist = CodeList::new(CodeListType::SNOMED, create_test_metadata(), None);
+        codelist.add_entry("22298006".to_string(), "Myocardial infarction".to_string(), None)?;
+        codelist.add_entry("123".to_string(), "Too short".to_string(), None)?;
+
+        let step = ProcessingStep::Validate { validator: Arc::new(CodeTypeValidator), remove_invalid: true };
+        let report = step.apply(&mut codelist)?;
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(codelist.entries.len(), 1);
+        Ok(())
+    }
+}
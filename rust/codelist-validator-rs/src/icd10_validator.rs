@@ -76,20 +76,10 @@ impl ICD10Validator for CodeList {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use codelist_rs::metadata::{ Metadata, MetadataSource };
     use codelist_rs::codelist::CodeList;
     use codelist_rs::types::CodeListType;
     use codelist_rs::errors::CodeListError;
-
-    // Helper function to create test metadata
-    fn create_test_metadata() -> Metadata {
-        Metadata {
-            source: MetadataSource::ManuallyCreated,
-            authors: Some(vec!["Caroline Morton".to_string()]),
-            version: Some("2024-12-19".to_string()),
-            description: Some("A test codelist".to_string()),
-        }
-    }
+    use crate::test_utils::create_test_metadata;
 
     // Helper function to create a test codelist with two entries, default options and test metadata
     fn create_test_codelist() -> Result<CodeList, CodeListError> {
@@ -98,7 +88,7 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_code_with_valid_code() -> Result<(), CodeListError> {;
+    fn test_validate_code_with_valid_code() -> Result<(), CodeListError> {
         let codelist = create_test_codelist()?;
         let code = "A009";
         assert!(codelist.validate_code(code).is_ok());
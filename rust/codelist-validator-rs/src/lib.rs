@@ -3,4 +3,7 @@ extern crate core;
 pub mod opcs_validator;
 pub mod snomed_validator;
 pub mod icd10_validator;
+pub mod code_type_validator;
 pub mod errors;
+#[cfg(test)]
+pub(crate) mod test_utils;
@@ -78,20 +78,10 @@ impl OPCSValidator for CodeList {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use codelist_rs::metadata::{ Metadata, MetadataSource };
     use codelist_rs::codelist::CodeList;
     use codelist_rs::types::CodeListType;
     use codelist_rs::errors::CodeListError;
-
-    // Helper function to create test metadata
-    fn create_test_metadata() -> Metadata {
-        Metadata {
-            source: MetadataSource::ManuallyCreated,
-            authors: Some(vec!["Caroline Morton".to_string()]),
-            version: Some("2024-12-19".to_string()),
-            description: Some("A test codelist".to_string()),
-        }
-    }
+    use crate::test_utils::create_test_metadata;
 
     // Helper function to create a test codelist with two entries, default options and test metadata
     fn create_test_codelist() -> Result<CodeList, CodeListError> {
@@ -1,8 +1,8 @@
 use codelist_rs::codelist::CodeList;
 use crate::errors::CodeListValidatorError;
 
-const MAX_LENGTH: u32 = 18;
-const MIN_LENGTH: u32 = 6;
+pub(crate) const MAX_LENGTH: u32 = 18;
+pub(crate) const MIN_LENGTH: u32 = 6;
 
 pub trait SNOMEDValidator {
     fn validate_code(&self, code: &str, min_length: u32, max_length: u32) -> Result<(), CodeListValidatorError>; // for 1 code
@@ -72,20 +72,10 @@ impl SNOMEDValidator for CodeList {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use codelist_rs::metadata::{ Metadata, MetadataSource };
     use codelist_rs::codelist::CodeList;
     use codelist_rs::types::CodeListType;
     use codelist_rs::errors::CodeListError;
-
-    // Helper function to create test metadata
-    fn create_test_metadata() -> Metadata {
-        Metadata {
-            source: MetadataSource::ManuallyCreated,
-            authors: Some(vec!["Caroline Morton".to_string()]),
-            version: Some("2024-12-19".to_string()),
-            description: Some("A test codelist".to_string()),
-        }
-    }
+    use crate::test_utils::create_test_metadata;
 
     // Helper function to create a test codelist with two entries, default options and test metadata
     fn create_test_codelist() -> Result<CodeList, CodeListError> {
@@ -0,0 +1,15 @@
+//! This file contains the fixtures shared by the tests of the validators
+
+// External imports
+use codelist_rs::metadata::{Metadata, MetadataSource};
+
+/// Create the metadata used by test codelists
+pub(crate) fn create_test_metadata() -> Metadata {
+    Metadata {
+        source: MetadataSource::ManuallyCreated,
+        authors: Some(vec!["Caroline Morton".to_string()]),
+        version: Some("2024-12-19".parse().unwrap()),
+        description: Some("A test codelist".to_string()),
+        ..Metadata::default()
+    }
+}